@@ -6,7 +6,7 @@ fn main() {
     println!("{:#?}", parse_md_docblock(DOC_STR).unwrap());
 }
 
-const DOC_STR: &'static str = "\
+const DOC_STR: &str = "\
 Lorem ipsum
 
 A longer description lorem ipsum dolor sit amet.
@@ -0,0 +1,631 @@
+//! Check the documentation of all crates in a Cargo workspace, or report how
+//! much of it is documented
+//!
+//! Run `cargo docstrings --help` for usage. The exit status is `1` if any
+//! documentation is invalid (or the coverage is below `--fail-under`), and `2`
+//! if the workspace can't be checked at all (e.g. because of invalid
+//! arguments).
+//!
+//! Crates can be configured in their `Cargo.toml`:
+//!
+//! ```toml
+//! [package.metadata.docstrings]
+//! # Don't check this crate at all
+//! skip = false
+//! # Also check items that are not public
+//! private = false
+//! # Check documented parameters, generics and return values against the code
+//! validate = true
+//! # Items not to check, along with everything inside them
+//! exclude = ["generated", "Foo::internal_method"]
+//! # Codes of diagnostics not to report
+//! allow = ["DS0103"]
+//! ```
+//!
+//! Crates can also be excluded for the whole workspace:
+//!
+//! ```toml
+//! [workspace.metadata.docstrings]
+//! exclude = ["some-crate"]
+//! ```
+
+extern crate docstrings;
+#[macro_use] extern crate serde;
+extern crate serde_json;
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+
+use serde_json::Value;
+
+use docstrings::*;
+
+const USAGE: &str = "\
+Usage: cargo docstrings check [OPTIONS]
+       cargo docstrings coverage [OPTIONS] [--format FORMAT] [--fail-under PERCENT]
+
+`check` checks the doc comments of public items in all crates of a workspace:
+It parses them, and checks documented parameters, generics and return values
+against the code.
+
+`coverage` reports the percentage of public functions that have a teaser, a
+`Parameters` section documenting all parameters, a `Returns` section (unless
+they return `()`) and an `Examples` section, and of those that have all of it.
+
+Options:
+        --manifest-path PATH  Path to the Cargo.toml of the workspace
+    -p, --package NAME        Only check this crate (may be repeated)
+        --exclude NAME        Don't check this crate (may be repeated)
+        --private             Also check items that are not public
+        --allow CODE          Don't report diagnostics with this code, e.g.
+                              `DS0103` (may be repeated)
+        --message-format FMT  How to print diagnostics: `human` (default) or
+                              `json` (one object per line on stdout)
+    -f, --format FORMAT       How to print the coverage: `table` (default),
+                              `json` or `html`
+        --fail-under PERCENT  Fail if less than PERCENT of all functions are
+                              completely documented
+    -h, --help                Print this help
+
+Crates can be configured with a `[package.metadata.docstrings]` table in their
+Cargo.toml, with the keys `skip`, `private`, `validate`, `exclude` (a list of
+item paths) and `allow` (a list of codes), and excluded with an `exclude` list
+of crate names in `[workspace.metadata.docstrings]`.
+";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Subcommand {
+    Check,
+    Coverage,
+}
+
+/// How to print diagnostics
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MessageFormat {
+    /// Like rustc, on stderr
+    Human,
+    /// One JSON object per line on stdout, see `JsonDiagnostic`
+    Json,
+}
+
+/// How to print a coverage report
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Table,
+    Json,
+    Html,
+}
+
+#[derive(Debug)]
+struct Args {
+    command: Subcommand,
+    manifest_path: Option<String>,
+    packages: Vec<String>,
+    exclude: Vec<String>,
+    private: bool,
+    /// Codes of diagnostics not to report
+    allow: Vec<String>,
+    message_format: MessageFormat,
+    format: Format,
+    fail_under: Option<f64>,
+}
+
+/// Configuration of a crate, from `[package.metadata.docstrings]`
+#[derive(Debug)]
+struct Config {
+    skip: bool,
+    private: bool,
+    validate: bool,
+    /// Paths of items not to check
+    exclude: Vec<String>,
+    /// Codes of diagnostics not to report
+    allow: Vec<String>,
+}
+
+impl Config {
+    fn from_metadata(metadata: &Value) -> Result<Config, String> {
+        let table = &metadata["docstrings"];
+        let flag = |key: &str, default: bool| match table[key] {
+            Value::Null => Ok(default),
+            Value::Bool(value) => Ok(value),
+            _ => Err(format!("`{}` must be `true` or `false`", key)),
+        };
+
+        Ok(Config {
+            skip: flag("skip", false)?,
+            private: flag("private", false)?,
+            validate: flag("validate", true)?,
+            exclude: strings(&table["exclude"]).map_err(|_| "`exclude` must be a list of item paths".to_string())?,
+            allow: strings(&table["allow"]).map_err(|_| "`allow` must be a list of codes".to_string())?,
+        })
+    }
+
+    /// Whether the item at `path` (or an item it is in) is excluded
+    fn excludes(&self, path: &str) -> bool {
+        self.exclude.iter().any(|excluded| {
+            path == excluded || (path.starts_with(&excluded[..]) && path[excluded.len()..].starts_with("::"))
+        })
+    }
+}
+
+/// A crate of the workspace, as far as it matters here
+#[derive(Debug)]
+struct Package {
+    name: String,
+    version: String,
+    /// Root files of the library and binaries
+    roots: Vec<PathBuf>,
+    config: Config,
+}
+
+fn main() {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return;
+        }
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let (root, packages) = match workspace(&args) {
+        Ok(workspace) => workspace,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            process::exit(2);
+        }
+    };
+
+    let failed = match args.command {
+        Subcommand::Check => check_all(&packages, &root, &args),
+        Subcommand::Coverage => coverage_all(&packages, args.private, args.format, args.fail_under),
+    };
+    if failed {
+        process::exit(1);
+    }
+}
+
+/// Check all crates, and return whether any errors were found
+fn check_all(packages: &[Package], root: &Path, args: &Args) -> bool {
+    let mut errors = 0;
+    let mut failed_packages = 0;
+    for package in packages {
+        eprintln!("{:>12} {} v{}", "Checking", package.name, package.version);
+        let found = check(package, root, args);
+        if found > 0 {
+            errors += found;
+            failed_packages += 1;
+        }
+    }
+
+    if errors > 0 {
+        eprintln!("error: found {} documentation {} in {} {}",
+                  errors, plural(errors, "error", "errors"),
+                  failed_packages, plural(failed_packages, "crate", "crates"));
+    }
+    errors > 0
+}
+
+/// Print the coverage of all crates, and return whether it is too low
+fn coverage_all(packages: &[Package], private: bool, format: Format, fail_under: Option<f64>) -> bool {
+    let mut report = Report { crates: vec![], total: CoverageSummary::default() };
+    let mut total = Coverage::default();
+
+    for package in packages {
+        let coverage = coverage(package, private);
+        report.crates.push(CrateReport {
+            name: package.name.clone(),
+            version: package.version.clone(),
+            summary: coverage.summary(),
+            functions: coverage.functions.clone(),
+        });
+        total.extend(coverage);
+    }
+    report.total = total.summary();
+
+    match format {
+        Format::Table => print!("{}", table(&report)),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&report).unwrap()),
+        Format::Html => print!("{}", html(&report)),
+    }
+
+    match fail_under {
+        Some(threshold) if report.total.complete < threshold => {
+            eprintln!("error: {:.1}% of all functions are completely documented, less than {:.1}%",
+                      report.total.complete, threshold);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Parse command-line arguments, `None` if help was requested
+fn parse_args<I: Iterator<Item=String>>(args: I) -> Result<Option<Args>, String> {
+    let mut args = args.peekable();
+    let mut parsed = Args {
+        command: Subcommand::Check,
+        manifest_path: None,
+        packages: vec![],
+        exclude: vec![],
+        private: false,
+        allow: vec![],
+        message_format: MessageFormat::Human,
+        format: Format::Table,
+        fail_under: None,
+    };
+
+    // Cargo passes the name of the subcommand
+    if args.peek().map(|arg| arg == "docstrings").unwrap_or(false) {
+        args.next();
+    }
+
+    match args.next() {
+        Some(ref command) if command == "check" => {}
+        Some(ref command) if command == "coverage" => parsed.command = Subcommand::Coverage,
+        Some(ref arg) if arg == "-h" || arg == "--help" => return Ok(None),
+        Some(command) => return Err(format!("Unknown command `{}`", command)),
+        None => return Err("Missing command".into()),
+    }
+
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "-h" | "--help" => return Ok(None),
+            "--private" => parsed.private = true,
+            "--manifest-path" | "-p" | "--package" | "--exclude" | "--allow" | "--message-format" |
+            "-f" | "--format" | "--fail-under" => {
+                let value = args.next().ok_or_else(|| format!("Missing value after `{}`", arg))?;
+                match &arg[..] {
+                    "--manifest-path" => parsed.manifest_path = Some(value),
+                    "--exclude" => parsed.exclude.push(value),
+                    "--allow" => parsed.allow.push(value),
+                    "--message-format" => parsed.message_format = match &value[..] {
+                        "human" => MessageFormat::Human,
+                        "json" => MessageFormat::Json,
+                        _ => return Err(format!("Unknown message format `{}`", value)),
+                    },
+                    "-f" | "--format" => parsed.format = match &value[..] {
+                        "table" => Format::Table,
+                        "json" => Format::Json,
+                        "html" => Format::Html,
+                        _ => return Err(format!("Unknown format `{}`", value)),
+                    },
+                    "--fail-under" => parsed.fail_under = Some(value.trim_end_matches('%').parse()
+                        .map_err(|_| format!("Invalid percentage `{}`", value))?),
+                    _ => parsed.packages.push(value),
+                }
+            }
+            _ => return Err(format!("Unknown argument `{}`", arg)),
+        }
+    }
+
+    if parsed.command == Subcommand::Check && (parsed.format != Format::Table || parsed.fail_under.is_some()) {
+        return Err("`--format` and `--fail-under` only apply to `coverage`".into());
+    }
+    if parsed.command == Subcommand::Coverage && (!parsed.allow.is_empty() || parsed.message_format != MessageFormat::Human) {
+        return Err("`--allow` and `--message-format` only apply to `check`".into());
+    }
+
+    Ok(Some(parsed))
+}
+
+/// Root directory and crates to check of the workspace, from `cargo metadata`
+fn workspace(args: &Args) -> Result<(PathBuf, Vec<Package>), String> {
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let mut command = Command::new(cargo);
+    command.args(["metadata", "--format-version", "1", "--no-deps"]);
+    if let Some(ref manifest_path) = args.manifest_path {
+        command.arg("--manifest-path").arg(manifest_path);
+    }
+
+    let output = command.output().map_err(|error| format!("Could not run `cargo metadata`: {}", error))?;
+    if !output.status.success() {
+        return Err(format!("`cargo metadata` failed:\n{}", String::from_utf8_lossy(&output.stderr)));
+    }
+    let metadata: Value = serde_json::from_slice(&output.stdout)
+        .map_err(|error| format!("Invalid output of `cargo metadata`: {}", error))?;
+
+    let root = PathBuf::from(metadata["workspace_root"].as_str().unwrap_or("."));
+    let mut excluded: HashSet<String> = args.exclude.iter().cloned().collect();
+    excluded.extend(strings(&metadata["metadata"]["docstrings"]["exclude"])
+        .map_err(|_| "`exclude` in `[workspace.metadata.docstrings]` must be a list of crate names".to_string())?);
+
+    let members: HashSet<&str> = metadata["workspace_members"].as_array()
+        .map(|members| members.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    let mut packages = vec![];
+    for package in metadata["packages"].as_array().map(|packages| &packages[..]).unwrap_or(&[]) {
+        let name = package["name"].as_str().unwrap_or("").to_string();
+        let id = package["id"].as_str().unwrap_or("");
+        if !members.contains(id) || excluded.contains(&name) ||
+           (!args.packages.is_empty() && !args.packages.contains(&name)) {
+            continue;
+        }
+
+        let config = Config::from_metadata(&package["metadata"])
+            .map_err(|message| format!("Invalid `[package.metadata.docstrings]` of `{}`: {}", name, message))?;
+        if config.skip {
+            continue;
+        }
+
+        let roots = package["targets"].as_array().map(|targets| &targets[..]).unwrap_or(&[]).iter()
+            .filter(|target| strings(&target["kind"]).unwrap_or_default().iter().any(|kind| is_checked(kind)))
+            .filter_map(|target| target["src_path"].as_str().map(PathBuf::from))
+            .collect();
+
+        packages.push(Package {
+            name,
+            version: package["version"].as_str().unwrap_or("").to_string(),
+            roots,
+            config,
+        });
+    }
+
+    for name in &args.packages {
+        if !packages.iter().any(|package| package.name == *name) && !excluded.contains(name) {
+            return Err(format!("Package `{}` is not a member of the workspace", name));
+        }
+    }
+
+    Ok((root, packages))
+}
+
+/// Whether targets of this kind are checked (libraries and binaries, but not
+/// tests, examples, benchmarks or build scripts)
+fn is_checked(kind: &str) -> bool {
+    matches!(kind, "lib" | "rlib" | "dylib" | "cdylib" | "staticlib" | "proc-macro" | "bin")
+}
+
+/// A list of strings in JSON, where `null` is an empty list
+fn strings(value: &Value) -> Result<Vec<String>, ()> {
+    match *value {
+        Value::Null => Ok(vec![]),
+        Value::Array(ref values) => values.iter()
+            .map(|value| value.as_str().map(String::from).ok_or(()))
+            .collect(),
+        _ => Err(()),
+    }
+}
+
+/// Files of all targets of a crate, by target, each file only once
+fn files(package: &Package) -> Vec<Vec<SourceFile>> {
+    let mut seen = HashSet::new();
+
+    package.roots.iter()
+        .map(|root| parse_rust_crate(root).into_iter()
+            .filter(|file| seen.insert(file.path.clone()))
+            .collect())
+        .collect()
+}
+
+/// Whether an item is checked, or counted in the coverage
+fn is_included(item: &SourceItem, package: &Package, private: bool) -> bool {
+    (item.public || private || package.config.private) && !package.config.excludes(&item.path)
+}
+
+/// Check all files of a crate, printing diagnostics, and return the number
+/// of errors
+fn check(package: &Package, root: &Path, args: &Args) -> usize {
+    let private = args.private || package.config.private;
+    let allowed = |diagnostic: &Diagnostic| {
+        args.allow.contains(&diagnostic.code) || package.config.allow.contains(&diagnostic.code)
+    };
+    let mut errors = 0;
+
+    for files in files(package) {
+        // Enums returned by functions may be declared in any file
+        let items: Vec<SourceItem> = files.iter()
+            .filter_map(|file| file.items.as_ref().ok())
+            .flat_map(|items| items.iter().cloned())
+            .collect();
+        let enums: HashMap<String, Vec<String>> = local_enums(&items);
+
+        for file in &files {
+            let source = read(&file.path);
+            let display = file.path.strip_prefix(root).unwrap_or(&file.path);
+
+            let mut diagnostics = vec![];
+            match file.items {
+                Ok(ref items) => for item in items {
+                    if !is_included(item, package, private) {
+                        continue;
+                    }
+
+                    match item.doc {
+                        Some(Ok(_)) if !package.config.validate => {}
+                        _ => diagnostics.extend(item.diagnostics(&enums).into_iter().map(|diagnostic| {
+                            if item.path.is_empty() {
+                                (None, diagnostic)
+                            } else {
+                                (Some(&item.path[..]), diagnostic.with_note(format!("in the docs of `{}`", item.path)))
+                            }
+                        })),
+                    }
+                },
+                Err(ref error) => diagnostics.push((None, error.to_diagnostic(&source))),
+            }
+
+            for (item, diagnostic) in diagnostics {
+                if allowed(&diagnostic) {
+                    continue;
+                }
+                if diagnostic.severity == Severity::Error {
+                    errors += 1;
+                }
+                report(&diagnostic, display, &source, item, args.message_format);
+            }
+        }
+    }
+
+    errors
+}
+
+/// Coverage of the functions of a crate
+///
+/// Files that can't be parsed are left out, `check` reports them.
+fn coverage(package: &Package, private: bool) -> Coverage {
+    files(package).iter()
+        .flat_map(|files| files.iter())
+        .filter_map(|file| file.items.as_ref().ok())
+        .flat_map(|items| items.iter())
+        .filter(|item| is_included(item, package, private))
+        .filter_map(FunctionCoverage::of)
+        .collect()
+}
+
+/// A coverage report, as written in JSON
+#[derive(Serialize)]
+struct Report {
+    crates: Vec<CrateReport>,
+    total: CoverageSummary,
+}
+
+#[derive(Serialize)]
+struct CrateReport {
+    name: String,
+    version: String,
+    summary: CoverageSummary,
+    functions: Vec<FunctionCoverage>,
+}
+
+const COLUMNS: [&str; 5] = ["Teaser", "Parameters", "Returns", "Examples", "Complete"];
+
+fn percentages(summary: &CoverageSummary) -> [f64; 5] {
+    [summary.teaser, summary.parameters, summary.returns, summary.examples, summary.complete]
+}
+
+/// A coverage report as a table for the terminal
+fn table(report: &Report) -> String {
+    let width = report.crates.iter()
+        .map(|krate| krate.name.len())
+        .chain(vec!["Crate".len(), "Total".len()])
+        .max()
+        .unwrap_or(0);
+
+    let mut table = format!("{:<width$}  {:>9}", "Crate", "Functions", width = width);
+    for column in &COLUMNS {
+        table.push_str(&format!("  {:>10}", column));
+    }
+    table.push('\n');
+
+    let rows = report.crates.iter()
+        .map(|krate| (&krate.name[..], &krate.summary))
+        .chain(Some(("Total", &report.total)));
+    for (name, summary) in rows {
+        table.push_str(&format!("{:<width$}  {:>9}", name, summary.functions, width = width));
+        for percentage in &percentages(summary) {
+            table.push_str(&format!("  {:>9.1}%", percentage));
+        }
+        table.push('\n');
+    }
+
+    table
+}
+
+/// A coverage report as an HTML page, with a summary table and the functions
+/// of each crate
+fn html(report: &Report) -> String {
+    let mut html = String::from("\
+<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>Documentation coverage</title>
+<style>
+body { font-family: sans-serif; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { border: 1px solid #ccc; padding: 0.25em 0.75em; }
+td.number { text-align: right; }
+td.yes { background: #dfd; }
+td.no { background: #fdd; }
+</style>
+</head>
+<body>
+<h1>Documentation coverage</h1>
+<table>
+<tr><th>Crate</th><th>Functions</th>");
+    for column in &COLUMNS {
+        html.push_str(&format!("<th>{}</th>", column));
+    }
+    html.push_str("</tr>\n");
+
+    let rows = report.crates.iter()
+        .map(|krate| (&krate.name[..], &krate.summary))
+        .chain(Some(("Total", &report.total)));
+    for (name, summary) in rows {
+        html.push_str(&format!("<tr><td>{}</td><td class=\"number\">{}</td>", escape(name), summary.functions));
+        for percentage in &percentages(summary) {
+            html.push_str(&format!("<td class=\"number\">{:.1}%</td>", percentage));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+
+    for krate in &report.crates {
+        html.push_str(&format!("<h2>{} {}</h2>\n<table>\n<tr><th>Function</th>", escape(&krate.name), escape(&krate.version)));
+        for column in &COLUMNS[..4] {
+            html.push_str(&format!("<th>{}</th>", column));
+        }
+        html.push_str("</tr>\n");
+
+        for function in &krate.functions {
+            html.push_str(&format!("<tr><td><code>{}</code></td>", escape(&function.path)));
+            for covered in &[Some(function.teaser), function.parameters, function.returns, Some(function.examples)] {
+                html.push_str(match *covered {
+                    Some(true) => "<td class=\"yes\">yes</td>",
+                    Some(false) => "<td class=\"no\">no</td>",
+                    None => "<td></td>",
+                });
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn read(path: &Path) -> String {
+    let mut source = String::new();
+    let _ = File::open(path).and_then(|mut file| file.read_to_string(&mut source));
+    source
+}
+
+/// Print a diagnostic like rustc does, or as JSON
+fn report(diagnostic: &Diagnostic, file: &Path, source: &str, item: Option<&str>, format: MessageFormat) {
+    match format {
+        MessageFormat::Human => eprintln!("{}", diagnostic.render(&file.display().to_string(), source)),
+        MessageFormat::Json => println!("{}", serde_json::to_string(&JsonDiagnostic {
+            file: file.display().to_string(),
+            item,
+            diagnostic,
+        }).unwrap()),
+    }
+}
+
+/// A diagnostic as written with `--message-format json`, with positions in
+/// the source file
+#[derive(Serialize)]
+struct JsonDiagnostic<'a> {
+    file: String,
+    /// Path of the documented item, `null` for the file itself
+    item: Option<&'a str>,
+    #[serde(flatten)]
+    diagnostic: &'a Diagnostic,
+}
+
+fn plural<'a>(n: usize, one: &'a str, many: &'a str) -> &'a str {
+    if n == 1 { one } else { many }
+}
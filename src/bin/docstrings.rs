@@ -0,0 +1,266 @@
+//! Parse documentation in Markdown or Rust source files and print it
+//!
+//! Run `docstrings --help` for usage. The exit status is `1` if any
+//! documentation (or Rust source) could not be parsed, and `2` for invalid
+//! arguments.
+
+extern crate docstrings;
+#[macro_use] extern crate serde;
+extern crate serde_json;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::process;
+
+use docstrings::*;
+
+const USAGE: &str = "\
+Usage: docstrings [OPTIONS] [FILE...]
+
+Parse the documentation in Markdown files (one doc block per file) and Rust
+source files (the doc comments of all items in it), and print it. Files ending
+in `.rs` are Rust source, all others Markdown. Reads Markdown from standard
+input if no FILE is given, or if FILE is `-`.
+
+Options:
+    -f, --format FORMAT  How to print the documentation: `json` (default),
+                         `debug` or `markdown`
+        --rust           Parse all input as Rust source
+        --markdown       Parse all input as Markdown
+    -h, --help           Print this help
+";
+
+/// How to print doc blocks
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    /// A JSON array of entries, see `JsonEntry`
+    Json,
+    /// Pretty-printed `Debug` output
+    Debug,
+    /// Canonical Markdown, see `render_md_docblock`
+    Markdown,
+}
+
+/// How to parse input
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Syntax {
+    Markdown,
+    Rust,
+}
+
+#[derive(Debug)]
+struct Args {
+    format: Format,
+    /// Syntax of all input, instead of guessing it from the file extension
+    syntax: Option<Syntax>,
+    /// Files to read, `-` for standard input
+    files: Vec<String>,
+}
+
+/// A parsed doc block, and where it comes from
+struct Entry {
+    file: String,
+    /// The documented item, `None` for Markdown files
+    item: Option<SourceItem>,
+    doc: DocBlock,
+}
+
+fn main() {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return;
+        }
+        Err(message) => {
+            eprintln!("docstrings: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let mut entries = vec![];
+    let mut failed = false;
+
+    for file in &args.files {
+        let name = if file == "-" { "<stdin>" } else { file };
+        let source = match read(file) {
+            Ok(source) => source,
+            Err(error) => {
+                eprintln!("{}: Could not read file: {}", name, error);
+                failed = true;
+                continue;
+            }
+        };
+
+        let syntax = args.syntax.unwrap_or_else(|| if file.ends_with(".rs") {
+            Syntax::Rust
+        } else {
+            Syntax::Markdown
+        });
+        let parsed = match syntax {
+            Syntax::Markdown => markdown_entries(name, &source),
+            Syntax::Rust => rust_entries(name, &source),
+        };
+
+        // Print whatever could be parsed, even if some docs couldn't
+        let (parsed, errors) = parsed;
+        entries.extend(parsed);
+        for error in &errors {
+            eprintln!("{}", error);
+        }
+        failed |= !errors.is_empty();
+    }
+
+    print(&entries, args.format);
+
+    if failed {
+        process::exit(1);
+    }
+}
+
+/// Parse command-line arguments, `None` if help was requested
+fn parse_args<I: Iterator<Item=String>>(mut args: I) -> Result<Option<Args>, String> {
+    let mut parsed = Args {
+        format: Format::Json,
+        syntax: None,
+        files: vec![],
+    };
+
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "-h" | "--help" => return Ok(None),
+            "-f" | "--format" => {
+                parsed.format = match args.next().as_ref().map(|format| &format[..]) {
+                    Some("json") => Format::Json,
+                    Some("debug") => Format::Debug,
+                    Some("markdown") | Some("md") => Format::Markdown,
+                    Some(format) => return Err(format!("Unknown format `{}`", format)),
+                    None => return Err(format!("Missing format after `{}`", arg)),
+                };
+            }
+            "--rust" => parsed.syntax = Some(Syntax::Rust),
+            "--markdown" => parsed.syntax = Some(Syntax::Markdown),
+            "-" => parsed.files.push(arg.clone()),
+            _ if arg.starts_with('-') => return Err(format!("Unknown option `{}`", arg)),
+            _ => parsed.files.push(arg.clone()),
+        }
+    }
+
+    if parsed.files.is_empty() {
+        parsed.files.push("-".into());
+    }
+
+    Ok(Some(parsed))
+}
+
+/// Read a file, or standard input for `-`
+fn read(file: &str) -> io::Result<String> {
+    let mut source = String::new();
+    if file == "-" {
+        io::stdin().read_to_string(&mut source)?;
+    } else {
+        File::open(Path::new(file))?.read_to_string(&mut source)?;
+    }
+    Ok(source)
+}
+
+/// The doc block of a Markdown file, and the error if there is none
+fn markdown_entries(file: &str, source: &str) -> (Vec<Entry>, Vec<String>) {
+    match parse_md_docblock(source) {
+        Ok(doc) => (vec![Entry { file: file.into(), item: None, doc }], vec![]),
+        Err(error) => (vec![], vec![format!("{}:{}", file, error)]),
+    }
+}
+
+/// The doc blocks of all items in a Rust source file with valid docs, and
+/// the errors of those with invalid docs
+fn rust_entries(file: &str, source: &str) -> (Vec<Entry>, Vec<String>) {
+    let items = match parse_rust_source(source) {
+        Ok(items) => items,
+        Err(error) => return (vec![], vec![format!("{}:{}", file, error)]),
+    };
+    let mut entries = vec![];
+    let mut errors = vec![];
+
+    for item in items {
+        match item.doc.clone() {
+            None => {}
+            Some(Ok(doc)) => entries.push(Entry { file: file.into(), item: Some(item), doc: doc.doc }),
+            Some(Err(error)) => errors.push(format!("{}:{}: {} (in the docs of {})",
+                file, item.source_position(error.position()), error.message(), describe(&item))),
+        }
+    }
+
+    (entries, errors)
+}
+
+/// Name an item in messages
+fn describe(item: &SourceItem) -> String {
+    if item.path.is_empty() {
+        "the file".into()
+    } else {
+        format!("`{}`", item.path)
+    }
+}
+
+fn print(entries: &[Entry], format: Format) {
+    if format == Format::Json {
+        let entries: Vec<_> = entries.iter().map(JsonEntry::from).collect();
+        println!("{}", serde_json::to_string_pretty(&entries).unwrap());
+        return;
+    }
+
+    // Tell doc blocks apart, unless there is just one
+    let labelled = entries.len() > 1;
+
+    for entry in entries {
+        if labelled {
+            match entry.item {
+                Some(ref item) => println!("==> {}: {} <==", entry.file, describe(item)),
+                None => println!("==> {} <==", entry.file),
+            }
+        }
+
+        match format {
+            Format::Debug => println!("{:#?}", entry.doc),
+            Format::Markdown => print!("{}", render_md_docblock(&entry.doc)),
+            Format::Json => unreachable!(),
+        }
+
+        if labelled {
+            println!();
+        }
+    }
+}
+
+/// An entry as written in JSON output
+#[derive(Serialize)]
+struct JsonEntry<'a> {
+    file: &'a str,
+    /// `null` for Markdown files
+    item: Option<JsonItem<'a>>,
+    doc: &'a DocBlock,
+}
+
+#[derive(Serialize)]
+struct JsonItem<'a> {
+    path: &'a str,
+    kind: ItemKind,
+    signature: &'a str,
+}
+
+impl<'a> From<&'a Entry> for JsonEntry<'a> {
+    fn from(entry: &'a Entry) -> JsonEntry<'a> {
+        JsonEntry {
+            file: &entry.file,
+            item: entry.item.as_ref().map(|item| JsonItem {
+                path: &item.path,
+                kind: item.kind,
+                signature: &item.signature,
+            }),
+            doc: &entry.doc,
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use ::std::iter::FromIterator;
+use ::syn;
+
+use ::errors::ValidationError;
+use ::source::{ItemKind, SourceItem};
+use ::types::DocSection;
+use ::validate;
+
+/// How well a function (or method) is documented
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FunctionCoverage {
+    /// Path of the function, see `SourceItem::path`
+    pub path: String,
+    /// Whether the function has docs starting with a teaser
+    pub teaser: bool,
+    /// Whether the `Parameters` section documents all parameters, `None` if
+    /// the function takes no parameters (besides `self`)
+    pub parameters: Option<bool>,
+    /// Whether there is a `Returns` section, `None` if the function returns
+    /// `()`
+    pub returns: Option<bool>,
+    /// Whether there is an `Examples` section
+    pub examples: bool,
+}
+
+impl FunctionCoverage {
+    /// Check how well a function is documented
+    ///
+    /// Docs that can't be parsed count as missing.
+    ///
+    /// # Parameters
+    ///
+    /// - `item`: The function, as returned by `parse_rust_source`
+    ///
+    /// # Returns
+    ///
+    /// The coverage of the function, or `None` if `item` is not a function or
+    /// method.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use self::docstrings::*;
+    /// let items = parse_rust_source("
+    /// /// Lorem ipsum
+    /// ///
+    /// /// # Parameters
+    /// ///
+    /// /// - `x`: Foo
+    /// pub fn foo(x: u32, y: u32) {}
+    /// ").unwrap();
+    ///
+    /// let coverage = FunctionCoverage::of(&items[0]).unwrap();
+    /// assert!(coverage.teaser);
+    /// assert_eq!(coverage.parameters, Some(false));
+    /// assert_eq!(coverage.returns, None);
+    /// assert!(!coverage.is_complete());
+    /// ```
+    pub fn of(item: &SourceItem) -> Option<FunctionCoverage> {
+        let sig = match (item.kind, &item.sig) {
+            (ItemKind::Function, &Some(ref sig)) | (ItemKind::Method, &Some(ref sig)) => sig,
+            _ => return None,
+        };
+        let doc = match item.doc {
+            Some(Ok(ref doc)) => Some(doc),
+            _ => None,
+        };
+        let has_section = |matches: fn(&DocSection) -> bool| {
+            doc.map(|doc| doc.doc.sections.iter().any(matches)).unwrap_or(false)
+        };
+
+        let has_parameters = sig.inputs.iter().any(|input| match *input {
+            syn::FnArg::Typed(_) => true,
+            syn::FnArg::Receiver(_) => false,
+        });
+        let returns_unit = match sig.output {
+            syn::ReturnType::Default => true,
+            syn::ReturnType::Type(_, ref ty) => match **ty {
+                syn::Type::Tuple(ref tuple) => tuple.elems.is_empty(),
+                _ => false,
+            },
+        };
+
+        Some(FunctionCoverage {
+            path: item.path.clone(),
+            teaser: doc.map(|doc| !doc.doc.teaser.is_empty()).unwrap_or(false),
+            parameters: if has_parameters {
+                // Without a `Parameters` section, they aren't validated
+                Some(has_section(|section| matches!(*section, DocSection::Parameters(..))) && doc.map(|doc| {
+                    !validate::validate_parameters_spanned(sig, doc).iter()
+                        .any(|error| matches!(*error, ValidationError::UndocumentedParameter(..)))
+                }).unwrap_or(false))
+            } else {
+                None
+            },
+            returns: if returns_unit {
+                None
+            } else {
+                Some(has_section(|section| matches!(*section, DocSection::Returns(..))))
+            },
+            examples: has_section(|section| matches!(*section, DocSection::Examples(..))),
+        })
+    }
+
+    /// Whether the function is documented in every way checked
+    pub fn is_complete(&self) -> bool {
+        self.teaser && self.parameters.unwrap_or(true) && self.returns.unwrap_or(true) && self.examples
+    }
+}
+
+/// Documentation coverage of a set of functions
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Coverage {
+    /// The functions, in the order they were added
+    pub functions: Vec<FunctionCoverage>,
+}
+
+/// Percentages of the functions of a `Coverage` that are documented in a
+/// certain way
+///
+/// Each percentage is of the functions it applies to, e.g. `returns` only
+/// counts functions not returning `()`. It is `100.0` if it applies to no
+/// function at all.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CoverageSummary {
+    /// Number of functions
+    pub functions: usize,
+    /// Functions with a teaser
+    pub teaser: f64,
+    /// Functions with all parameters documented
+    pub parameters: f64,
+    /// Functions with a `Returns` section
+    pub returns: f64,
+    /// Functions with an `Examples` section
+    pub examples: f64,
+    /// Functions documented in every way checked
+    pub complete: f64,
+}
+
+impl Coverage {
+    /// Coverage of all public functions and methods in `items`
+    ///
+    /// # Parameters
+    ///
+    /// - `items`: Items as returned by `parse_rust_source`
+    pub fn of_public<'a, I: IntoIterator<Item=&'a SourceItem>>(items: I) -> Coverage {
+        items.into_iter()
+            .filter(|item| item.public)
+            .filter_map(FunctionCoverage::of)
+            .collect()
+    }
+
+    /// Add the functions of another `Coverage`
+    pub fn extend(&mut self, other: Coverage) {
+        self.functions.extend(other.functions);
+    }
+
+    /// Percentages of documented functions
+    pub fn summary(&self) -> CoverageSummary {
+        CoverageSummary {
+            functions: self.functions.len(),
+            teaser: self.percentage(|function| Some(function.teaser)),
+            parameters: self.percentage(|function| function.parameters),
+            returns: self.percentage(|function| function.returns),
+            examples: self.percentage(|function| Some(function.examples)),
+            complete: self.percentage(|function| Some(function.is_complete())),
+        }
+    }
+
+    /// Percentage of functions for which `covered` is `Some(true)`, of those
+    /// it is not `None` for
+    fn percentage<F: Fn(&FunctionCoverage) -> Option<bool>>(&self, covered: F) -> f64 {
+        let applicable: Vec<bool> = self.functions.iter().filter_map(covered).collect();
+        if applicable.is_empty() {
+            return 100.0;
+        }
+
+        let count = applicable.iter().filter(|&&covered| covered).count();
+        100.0 * count as f64 / applicable.len() as f64
+    }
+}
+
+impl FromIterator<FunctionCoverage> for Coverage {
+    fn from_iter<I: IntoIterator<Item=FunctionCoverage>>(functions: I) -> Coverage {
+        Coverage {
+            functions: functions.into_iter().collect(),
+        }
+    }
+}
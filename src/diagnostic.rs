@@ -0,0 +1,446 @@
+//! Diagnostics with stable codes, for editors and CI
+
+use ::std::cmp;
+use ::std::fmt;
+
+use ::span::{Position, Span};
+
+/// How serious a `Diagnostic` is
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    /// The docs are wrong, or can't be parsed
+    Error,
+    /// The docs are fine, but could be better
+    Warning,
+    /// Just for information
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        })
+    }
+}
+
+/// A part of the docs a `Diagnostic` is about
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Label {
+    /// The marked text, empty if only the position is known
+    pub span: Span,
+    /// What is wrong with it, may be empty
+    pub message: String,
+    /// Whether this is where the problem is, rather than context
+    pub primary: bool,
+}
+
+/// A fix for a `Diagnostic` that can be applied without asking
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Suggestion {
+    /// The text to replace, empty to insert
+    pub span: Span,
+    /// What to replace it with
+    pub replacement: String,
+    /// What the fix does, e.g. "use a colon"
+    pub message: String,
+}
+
+impl Suggestion {
+    /// Apply the fix to the text the diagnostic is about
+    ///
+    /// # Parameters
+    ///
+    /// - `source`: The docs (or source file) the spans refer to
+    ///
+    /// # Returns
+    ///
+    /// `source` with the replacement in place, or unchanged if the span does
+    /// not fit into it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use self::docstrings::*;
+    /// let md = "Lorem ipsum\n\n# Parameters\n\n- `a` - A\n";
+    /// let error = parse_md_docblock(md).unwrap_err();
+    /// let diagnostic = error.to_diagnostic(md);
+    ///
+    /// assert_eq!(diagnostic.code, "DS0007");
+    /// assert_eq!(diagnostic.suggestions[0].apply(md), "Lorem ipsum\n\n# Parameters\n\n- `a`: A\n");
+    /// ```
+    pub fn apply(&self, source: &str) -> String {
+        let range = self.span.range();
+        if range.end > source.len() || range.start > range.end ||
+           !source.is_char_boundary(range.start) || !source.is_char_boundary(range.end) {
+            return source.into();
+        }
+
+        format!("{}{}{}", &source[..range.start], self.replacement, &source[range.end..])
+    }
+}
+
+/// A problem with documentation, with everything an editor or CI needs
+///
+/// Created from `ParseError`s and `ValidationError`s with their
+/// `to_diagnostic` methods, or by `parse_md_docblock_diagnostics` and
+/// `SourceItem::diagnostics`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use self::docstrings::*;
+/// let md = "Lorem ipsum\n\n# Parameters\n\n- param: Foo\n";
+/// let (doc, diagnostics) = parse_md_docblock_diagnostics(md, &ParseOptions::default());
+///
+/// assert!(doc.is_none());
+/// assert_eq!(diagnostics[0].to_string(), "error[DS0004]: No identifier in list item \"param: Foo\" of section `Parameters`");
+/// assert_eq!(diagnostics[0].suggestions[0].replacement, "`param`");
+/// ```
+///
+/// # Codes
+///
+/// Codes never change their meaning, so tools can look them up or suppress
+/// them:
+///
+/// | Code   | Severity | Problem                                              |
+/// |--------|----------|------------------------------------------------------|
+/// | DS0001 | error    | `ParseError::NoTeaser`                               |
+/// | DS0002 | error    | `ParseError::UnexpectedMarkdown`                     |
+/// | DS0003 | warning  | `ParseError::UnknownSection`                         |
+/// | DS0004 | error    | `ParseError::NoIdent`                                |
+/// | DS0005 | error    | `ParseError::InvalidIdent`                           |
+/// | DS0006 | error    | `ParseError::InvalidPattern`                         |
+/// | DS0007 | error    | `ParseError::WrongIdentDocsSeparator`                |
+/// | DS0101 | error    | `ValidationError::UndocumentedParameter`             |
+/// | DS0102 | error    | `ValidationError::UnknownParameter`                  |
+/// | DS0103 | warning  | `ValidationError::MisorderedParameter`               |
+/// | DS0104 | error    | `ValidationError::DuplicateParameter`                |
+/// | DS0105 | error    | `ValidationError::UndocumentedTypeParameter`         |
+/// | DS0106 | error    | `ValidationError::UnknownTypeParameter`              |
+/// | DS0107 | error    | `ValidationError::UndocumentedLifetime`              |
+/// | DS0108 | error    | `ValidationError::UnknownLifetime`                   |
+/// | DS0109 | error    | `ValidationError::UndocumentedReturnVariant`         |
+/// | DS0110 | error    | `ValidationError::UnknownReturnVariant`              |
+/// | DS0201 | error    | `SourceError::Io`                                    |
+/// | DS0202 | error    | `SourceError::Syntax`                                |
+///
+/// `DS0003` is an error when `ParseOptions::unknown_sections` is
+/// `UnknownSections::Error`.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Diagnostic {
+    /// Stable code, e.g. `DS0004`
+    pub code: String,
+    /// How serious the problem is
+    pub severity: Severity,
+    /// What the problem is, as a sentence
+    pub message: String,
+    /// The parts of the docs the problem is about, primary ones first
+    pub labels: Vec<Label>,
+    /// Additional information, e.g. which item the docs belong to
+    pub notes: Vec<String>,
+    /// How to fix the problem, if that can be explained in general
+    pub help: Option<String>,
+    /// Fixes that can be applied automatically
+    pub suggestions: Vec<Suggestion>,
+}
+
+impl Diagnostic {
+    /// A diagnostic without labels, notes, help or suggestions
+    pub fn new<C: Into<String>, M: Into<String>>(code: C, severity: Severity, message: M) -> Diagnostic {
+        Diagnostic {
+            code: code.into(),
+            severity,
+            message: message.into(),
+            labels: vec![],
+            notes: vec![],
+            help: None,
+            suggestions: vec![],
+        }
+    }
+
+    /// Add a label marking where the problem is
+    pub fn with_label<M: Into<String>>(mut self, span: Span, message: M) -> Diagnostic {
+        self.labels.push(Label { span, message: message.into(), primary: true });
+        self
+    }
+
+    /// Add a label marking context of the problem
+    pub fn with_secondary_label<M: Into<String>>(mut self, span: Span, message: M) -> Diagnostic {
+        self.labels.push(Label { span, message: message.into(), primary: false });
+        self
+    }
+
+    /// Add a note
+    pub fn with_note<M: Into<String>>(mut self, note: M) -> Diagnostic {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Set the help text
+    pub fn with_help<M: Into<String>>(mut self, help: M) -> Diagnostic {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Add a fix replacing the text at `span`
+    pub fn with_suggestion<R: Into<String>, M: Into<String>>(mut self, span: Span, replacement: R, message: M) -> Diagnostic {
+        self.suggestions.push(Suggestion { span, replacement: replacement.into(), message: message.into() });
+        self
+    }
+
+    /// Span of the first primary label
+    pub fn primary_span(&self) -> Option<Span> {
+        self.labels.iter().find(|label| label.primary).map(|label| label.span)
+    }
+
+    /// Move all spans, e.g. from the docs of an item to its source file (see
+    /// `SourceItem::source_position`)
+    pub fn map_positions<F: Fn(Position) -> Position>(mut self, f: F) -> Diagnostic {
+        let map = |span: Span| Span { start: f(span.start), end: f(span.end) };
+
+        for label in &mut self.labels {
+            label.span = map(label.span);
+        }
+        for suggestion in &mut self.suggestions {
+            suggestion.span = map(suggestion.span);
+        }
+        self
+    }
+
+    /// Format the diagnostic like rustc does, with the lines of `source` the
+    /// labels are on
+    ///
+    /// # Parameters
+    ///
+    /// - `file`: Name of the file to show
+    /// - `source`: Content of the file the spans refer to
+    ///
+    /// # Returns
+    ///
+    /// Several lines of text, each ending with a newline.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use self::docstrings::*;
+    /// let md = "Lorem ipsum\n\n# Parameters\n\n- `a` - A\n";
+    /// let error = parse_md_docblock(md).unwrap_err();
+    ///
+    /// assert_eq!(error.to_diagnostic(md).render("docs.md", md), concat!(
+    ///     "error[DS0007]: List item \"`a` - A\" of section `Parameters` must be written like this: `ident`: Docs\n",
+    ///     " --> docs.md:5:6\n",
+    ///     "  |\n",
+    ///     "5 | - `a` - A\n",
+    ///     "  |    - identifier\n",
+    ///     "  |      ^^^ expected `: `\n",
+    ///     "  = help: write list items like this: `ident`: Docs\n",
+    ///     "help: use a colon\n",
+    ///     "  |\n",
+    ///     "5 | - `a`: A\n",
+    /// ));
+    /// ```
+    pub fn render(&self, file: &str, source: &str) -> String {
+        let lines: Vec<&str> = source.lines().collect();
+        let shown = |position: Position| position.line > 0 && position.line <= lines.len();
+
+        let mut labels: Vec<&Label> = self.labels.iter().filter(|label| shown(label.span.start)).collect();
+        labels.sort_by_key(|label| (label.span.start.line, label.span.start.column));
+        let suggestions: Vec<&Suggestion> = self.suggestions.iter()
+            .filter(|suggestion| shown(suggestion.span.start) && suggestion.span.start.line == suggestion.span.end.line)
+            .collect();
+
+        let width = labels.iter().map(|label| label.span.start.line)
+            .chain(suggestions.iter().map(|suggestion| suggestion.span.start.line))
+            .max()
+            .unwrap_or(0)
+            .to_string()
+            .len();
+        let gutter: String = (0..width).map(|_| ' ').collect();
+
+        let mut rendered = format!("{}\n", self);
+        match self.primary_span() {
+            Some(span) if span.start.line > 0 =>
+                rendered.push_str(&format!("{}--> {}:{}\n", gutter, file, span.start)),
+            _ => rendered.push_str(&format!("{}--> {}\n", gutter, file)),
+        }
+
+        if !labels.is_empty() {
+            rendered.push_str(&format!("{} |\n", gutter));
+        }
+        let mut last_line = 0;
+        for label in labels {
+            let (start, end) = (label.span.start, label.span.end);
+            let line = lines[start.line - 1];
+            if start.line != last_line {
+                rendered.push_str(&format!("{:>width$} | {}\n", start.line, line, width = width));
+                last_line = start.line;
+            }
+
+            let marks = if end.line == start.line && end.column > start.column {
+                end.column - start.column
+            } else if end.line > start.line {
+                cmp::max(1, (line.chars().count() + 1).saturating_sub(start.column))
+            } else {
+                1
+            };
+            let mark = if label.primary { "^" } else { "-" };
+            let underline = format!("{}{}", indent(line, start.column),
+                                    (0..marks).map(|_| mark).collect::<String>());
+            if label.message.is_empty() {
+                rendered.push_str(&format!("{} | {}\n", gutter, underline));
+            } else {
+                rendered.push_str(&format!("{} | {} {}\n", gutter, underline, label.message));
+            }
+        }
+
+        for note in &self.notes {
+            rendered.push_str(&format!("{} = note: {}\n", gutter, note));
+        }
+        if let Some(ref help) = self.help {
+            rendered.push_str(&format!("{} = help: {}\n", gutter, help));
+        }
+
+        for suggestion in suggestions {
+            let start = suggestion.span.start;
+            let line = lines[start.line - 1];
+            let from = byte_index(line, start.column);
+            let to = cmp::max(from, byte_index(line, suggestion.span.end.column));
+
+            rendered.push_str(&format!("help: {}\n{} |\n", suggestion.message, gutter));
+            rendered.push_str(&format!("{:>width$} | {}{}{}\n", start.line,
+                                       &line[..from], suggestion.replacement, &line[to..], width = width));
+        }
+
+        rendered
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.code.is_empty() {
+            write!(f, "{}: {}", self.severity, self.message)
+        } else {
+            write!(f, "{}[{}]: {}", self.severity, self.code, self.message)
+        }
+    }
+}
+
+/// Whitespace up to `column` of `line`, keeping tabs so marks line up
+fn indent(line: &str, column: usize) -> String {
+    line.chars()
+        .take(column.saturating_sub(1))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect()
+}
+
+/// Byte index of a column (counted in characters, starting at 1) in a line
+fn byte_index(line: &str, column: usize) -> usize {
+    line.char_indices()
+        .nth(column.saturating_sub(1))
+        .map(|(index, _)| index)
+        .unwrap_or(line.len())
+}
+
+/// The position right after `text`, if it starts at `position`
+pub fn advance(mut position: Position, text: &str) -> Position {
+    for c in text.chars() {
+        position.offset += c.len_utf8();
+        if c == '\n' {
+            position.line += 1;
+            position.column = 1;
+        } else {
+            position.column += 1;
+        }
+    }
+    position
+}
+
+/// The rest of the line of `source` starting at `position`
+pub fn rest_of_line(source: &str, position: Position) -> Option<(&str, &str)> {
+    if position.line == 0 || position.offset > source.len() || !source.is_char_boundary(position.offset) {
+        return None;
+    }
+
+    let rest = &source[position.offset..];
+    let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+    Some((rest, line))
+}
+
+/// Span of `text` in `source`, looking for it on the line `position` is on
+///
+/// Falls back to an empty span at `position` if `text` isn't there (e.g.
+/// because it was rendered from Markdown events).
+pub fn text_span(source: &str, position: Position, text: &str) -> Span {
+    let empty = Span { start: position, end: position };
+    let first_line = text.lines().next().unwrap_or("");
+    let (rest, line) = match rest_of_line(source, position) {
+        Some(found) if !first_line.is_empty() => found,
+        _ => return empty,
+    };
+
+    match line.find(first_line) {
+        Some(index) => {
+            let start = advance(position, &rest[..index]);
+            let matched = if rest[index..].starts_with(text) { text } else { first_line };
+            Span { start, end: advance(start, matched) }
+        }
+        None => empty,
+    }
+}
+
+/// Span from `position` to the end of its line in `source`
+pub fn line_span(source: &str, position: Position) -> Span {
+    match rest_of_line(source, position) {
+        Some((_, line)) => Span { start: position, end: advance(position, line.trim_end()) },
+        None => Span { start: position, end: position },
+    }
+}
+
+/// The candidate `name` is most likely a misspelling of, if any
+pub fn closest<'a, I: IntoIterator<Item=&'a str>>(name: &str, candidates: I) -> Option<&'a str> {
+    let lowercase = name.to_lowercase();
+    let length = name.chars().count();
+    let limit = cmp::max(1, length / 3);
+
+    candidates.into_iter()
+        .filter(|&candidate| candidate != name)
+        .map(|candidate| (distance(&lowercase, &candidate.to_lowercase()), candidate))
+        // Replacing every character is no misspelling
+        .filter(|&(distance, _)| distance <= limit && distance < length)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Edit distance of two strings, counted in characters, where swapping two
+/// neighboring characters is a single edit
+fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..a.len() + 1 {
+        for j in 1..b.len() + 1 {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            d[i][j] = cmp::min(d[i - 1][j - 1] + cost, cmp::min(d[i - 1][j], d[i][j - 1]) + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = cmp::min(d[i][j], d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
@@ -1,24 +1,539 @@
+use ::diagnostic::{Diagnostic, Severity, advance, closest, line_span, rest_of_line, text_span};
+use ::options::{ParseOptions, Separator, UnknownSections};
+use ::span::{Position, Span};
+
 quick_error! {
     /// Errors while parsing documention
     #[derive(Debug, PartialEq, Eq, Clone, Hash)]
     pub enum ParseError {
         /// Missing teaser
-        NoTeaser {
+        NoTeaser(position: Position) {
             description("Doc comment did not start with a teaser.")
+            display(me) -> ("{}: {}", me.position(), me.message())
         }
         /// Unexpected markdown text
-        UnexpectedMarkdown(section: String, event: String) {
+        UnexpectedMarkdown(section: String, event: String, position: Position) {
             description("Unexpected Markdown")
-            display("Unexpected Markdown in section `{sec}`: {event}",
-                sec=section, event=event)
+            display(me) -> ("{}: {}", me.position(), me.message())
+        }
+        /// Section with a headline that is not known (only reported when
+        /// `ParseOptions::unknown_sections` asks for it)
+        UnknownSection(section: String, position: Position) {
+            description("Unknown section")
+            display(me) -> ("{}: {}", me.position(), me.message())
         }
         /// List not starting with an identifier (inline code)
-        NoIdent {
+        NoIdent(section: String, item: String, position: Position) {
             description("No identifier in list mapping ident -> docs")
+            display(me) -> ("{}: {}", me.position(), me.message())
+        }
+        /// Something that should be an identifier (e.g. of a type parameter)
+        /// is not
+        InvalidIdent(section: String, ident: String, position: Position) {
+            description("Invalid identifier")
+            display(me) -> ("{}: {}", me.position(), me.message())
+        }
+        /// Something that should be a pattern (e.g. of a parameter or return
+        /// value) is not
+        InvalidPattern(section: String, pattern: String, position: Position) {
+            description("Invalid pattern")
+            display(me) -> ("{}: {}", me.position(), me.message())
         }
         /// Invalid list formatting with identifier/docs
-        WrongIdentDocsSeparator {
+        WrongIdentDocsSeparator(section: String, item: String, position: Position) {
             description("List identifier and doc string must be written like this: `itend`: Docs")
+            display(me) -> ("{}: {}", me.position(), me.message())
+        }
+    }
+}
+
+impl ParseError {
+    /// Where in the documentation string the error occurred
+    pub fn position(&self) -> Position {
+        match *self {
+            ParseError::NoTeaser(position) |
+            ParseError::UnexpectedMarkdown(_, _, position) |
+            ParseError::UnknownSection(_, position) |
+            ParseError::NoIdent(_, _, position) |
+            ParseError::InvalidIdent(_, _, position) |
+            ParseError::InvalidPattern(_, _, position) |
+            ParseError::WrongIdentDocsSeparator(_, _, position) => position,
+        }
+    }
+
+    /// Headline of the section the error occurred in
+    pub fn section(&self) -> Option<&str> {
+        match *self {
+            ParseError::NoTeaser(_) => None,
+            ParseError::UnexpectedMarkdown(ref section, _, _) |
+            ParseError::UnknownSection(ref section, _) |
+            ParseError::NoIdent(ref section, _, _) |
+            ParseError::InvalidIdent(ref section, _, _) |
+            ParseError::InvalidPattern(ref section, _, _) |
+            ParseError::WrongIdentDocsSeparator(ref section, _, _) => Some(section),
+        }
+    }
+
+    /// Markdown of the offending list item (or just its identifier or
+    /// pattern)
+    pub fn item(&self) -> Option<&str> {
+        match *self {
+            ParseError::NoTeaser(_) |
+            ParseError::UnexpectedMarkdown(..) |
+            ParseError::UnknownSection(..) => None,
+            ParseError::NoIdent(_, ref item, _) |
+            ParseError::InvalidIdent(_, ref item, _) |
+            ParseError::InvalidPattern(_, ref item, _) |
+            ParseError::WrongIdentDocsSeparator(_, ref item, _) => Some(item),
+        }
+    }
+
+    /// Stable code of this kind of error, e.g. `DS0004` (see `Diagnostic`
+    /// for all codes)
+    pub fn code(&self) -> &'static str {
+        match *self {
+            ParseError::NoTeaser(..) => "DS0001",
+            ParseError::UnexpectedMarkdown(..) => "DS0002",
+            ParseError::UnknownSection(..) => "DS0003",
+            ParseError::NoIdent(..) => "DS0004",
+            ParseError::InvalidIdent(..) => "DS0005",
+            ParseError::InvalidPattern(..) => "DS0006",
+            ParseError::WrongIdentDocsSeparator(..) => "DS0007",
+        }
+    }
+
+    /// What went wrong, without the position `Display` starts with
+    pub fn message(&self) -> String {
+        self.message_with(&ParseOptions::default())
+    }
+
+    /// What went wrong, for docs parsed with the given options
+    fn message_with(&self, options: &ParseOptions) -> String {
+        match *self {
+            ParseError::NoTeaser(_) => "Doc comment did not start with a teaser.".into(),
+            ParseError::UnexpectedMarkdown(ref section, ref event, _) =>
+                format!("Unexpected Markdown in section `{}`: {}", section, event),
+            ParseError::UnknownSection(ref section, _) => format!("Unknown section `{}`", section),
+            ParseError::NoIdent(ref section, ref item, _) =>
+                format!("No identifier in list item \"{}\" of section `{}`", item, section),
+            ParseError::InvalidIdent(ref section, ref ident, _) =>
+                format!("`{}` in section `{}` is not a valid identifier", ident, section),
+            ParseError::InvalidPattern(ref section, ref pattern, _) =>
+                format!("`{}` in section `{}` is not a valid pattern", pattern, section),
+            ParseError::WrongIdentDocsSeparator(ref section, ref item, _) =>
+                format!("List item \"{}\" of section `{}` must be written like this: {}",
+                        item, section, list_items(&options.separators)),
+        }
+    }
+
+    /// Describe the error as a `Diagnostic`, with labels, help and suggested
+    /// fixes
+    ///
+    /// # Parameters
+    ///
+    /// - `md`: The documentation string the error occurred in
+    ///
+    /// # Returns
+    ///
+    /// The diagnostic, with spans relative to `md`. Labels are empty spans if
+    /// the offending text can't be found in `md`, and there are no
+    /// suggestions then.
+    pub fn to_diagnostic(&self, md: &str) -> Diagnostic {
+        self.to_diagnostic_with(md, &ParseOptions::default())
+    }
+
+    /// Describe the error as a `Diagnostic`, taking into account how `md` was
+    /// parsed
+    ///
+    /// Unknown sections are errors if `options.unknown_sections` asks for it
+    /// (and warnings otherwise), and only headlines in `options.headlines`
+    /// are suggested for them. List items are expected to be written with one
+    /// of `options.separators`.
+    ///
+    /// # Parameters
+    ///
+    /// - `md`: The documentation string the error occurred in
+    /// - `options`: The options `md` was parsed with
+    pub fn to_diagnostic_with(&self, md: &str, options: &ParseOptions) -> Diagnostic {
+        let diagnostic = Diagnostic::new(self.code(), Severity::Error, self.message_with(options));
+        let list_item_help = format!("write list items like this: {}", list_items(&options.separators));
+
+        match *self {
+            ParseError::NoTeaser(position) => diagnostic
+                .with_label(line_span(md, position), "expected a teaser")
+                .with_help("start the docs with a one-line summary, followed by an empty line"),
+            ParseError::UnexpectedMarkdown(_, _, position) => diagnostic
+                .with_label(line_span(md, position), "not expected here"),
+            ParseError::UnknownSection(ref section, position) => {
+                let span = text_span(md, position, section);
+                let mut diagnostic = diagnostic.with_label(span, "unknown headline");
+                if options.unknown_sections != UnknownSections::Error {
+                    diagnostic.severity = Severity::Warning;
+                }
+
+                match closest(section, options.headlines.all()) {
+                    Some(headline) if !span.is_empty() =>
+                        diagnostic.with_suggestion(span, headline, format!("did you mean `{}`?", headline)),
+                    _ => diagnostic,
+                }
+            }
+            ParseError::NoIdent(_, ref item, position) => {
+                let span = text_span(md, position, item);
+                let diagnostic = diagnostic
+                    .with_label(span, "expected an identifier in backticks")
+                    .with_help(list_item_help);
+
+                match bare_ident(item) {
+                    Some(ident) if !span.is_empty() => {
+                        let ident_span = Span { start: span.start, end: advance(span.start, ident) };
+                        diagnostic.with_suggestion(ident_span, format!("`{}`", ident), "put the identifier in backticks")
+                    }
+                    _ => diagnostic,
+                }
+            }
+            ParseError::InvalidIdent(_, ref ident, position) => diagnostic
+                .with_label(text_span(md, position, ident), "not an identifier"),
+            ParseError::InvalidPattern(_, ref pattern, position) => diagnostic
+                .with_label(text_span(md, position, pattern), "not a pattern"),
+            ParseError::WrongIdentDocsSeparator(_, _, position) => {
+                let expected = options.separators.iter()
+                    .map(|separator| match *separator {
+                        Separator::Newline => "a line break".to_string(),
+                        _ => format!("`{}`", separator.as_str()),
+                    })
+                    .collect::<Vec<_>>();
+                let expected = format!("expected {}", one_of(&expected));
+                // Only separators on the same line can be suggested
+                let suggestion = options.separators.iter()
+                    .filter_map(|separator| match *separator {
+                        Separator::Colon => Some((separator.as_str(), "use a colon")),
+                        Separator::Dash => Some((separator.as_str(), "use a dash")),
+                        Separator::EmDash => Some((separator.as_str(), "use an em dash")),
+                        Separator::Newline => None,
+                    })
+                    .next();
+
+                match separator_spans(md, position) {
+                    Some((ident, separator)) => {
+                        let diagnostic = diagnostic
+                            .with_label(separator, expected)
+                            .with_secondary_label(ident, "identifier")
+                            .with_help(list_item_help);
+                        match suggestion {
+                            Some((replacement, message)) => diagnostic.with_suggestion(separator, replacement, message),
+                            None => diagnostic,
+                        }
+                    }
+                    None => diagnostic
+                        .with_label(Span { start: position, end: position }, expected)
+                        .with_help(list_item_help),
+                }
+            }
+        }
+    }
+}
+
+/// How list items are written with any of the `separators`, e.g.
+/// `` `ident`: Docs or `ident` - Docs ``
+fn list_items(separators: &[Separator]) -> String {
+    let items: Vec<String> = separators.iter()
+        .map(|separator| match *separator {
+            Separator::Newline => "`ident` with the docs on the next line".to_string(),
+            _ => format!("`ident`{}Docs", separator.as_str()),
+        })
+        .collect();
+
+    one_of(&items)
+}
+
+/// The alternatives in a sentence, e.g. `a, b or c`
+fn one_of(alternatives: &[String]) -> String {
+    match alternatives.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, others)) => format!("{} or {}", others.join(", "), last),
+        None => String::new(),
+    }
+}
+
+/// The identifier a list item like `ident: Docs` starts with, if it only
+/// lacks backticks
+fn bare_ident(item: &str) -> Option<&str> {
+    let ident = &item[..try_opt!(item.find(':'))];
+
+    if !ident.is_empty() && ident.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        Some(ident)
+    } else {
+        None
+    }
+}
+
+/// Spans of the identifier ending at `position` (in front of its closing
+/// backticks) and of the separator following it, if the docs start on the
+/// same line
+fn separator_spans(md: &str, position: Position) -> Option<(Span, Span)> {
+    let (_, line) = try_opt!(rest_of_line(md, position));
+    let before = &md[..position.offset];
+    let ident = &before[try_opt!(before.rfind('`')) + 1..];
+    if ident.contains('\n') {
+        return None;
+    }
+
+    let ticks = line.len() - line.trim_start_matches('`').len();
+    let after_ticks = &line[ticks..];
+    let separator = &after_ticks[..after_ticks.len() - after_ticks.trim_start_matches(|c| " \t:-\u{2013}\u{2014}".contains(c)).len()];
+    if ticks == 0 || separator.len() == after_ticks.len() {
+        return None;
+    }
+
+    let ident_span = Span {
+        start: Position {
+            offset: position.offset - ident.len(),
+            line: position.line,
+            column: position.column.saturating_sub(ident.chars().count()),
+        },
+        end: position,
+    };
+    let separator_start = advance(position, &line[..ticks]);
+    Some((ident_span, Span { start: separator_start, end: advance(separator_start, separator) }))
+}
+
+#[cfg(feature = "source")]
+quick_error! {
+    /// Errors while extracting doc comments from Rust source code
+    #[derive(Debug)]
+    pub enum SourceError {
+        /// Reading the source file failed
+        Io(err: ::std::io::Error) {
+            from()
+            cause(err)
+            description("Could not read source file")
+            display("Could not read source file: {}", err)
         }
+        /// The source code is not valid Rust
+        Syntax(message: String, position: Position) {
+            description("Invalid Rust source code")
+            display("{pos}: Invalid Rust source code: {msg}", pos=position, msg=message)
+        }
+    }
+}
+
+#[cfg(feature = "source")]
+impl SourceError {
+    /// Stable code of this kind of error, `DS0201` or `DS0202` (see
+    /// `Diagnostic` for all codes)
+    pub fn code(&self) -> &'static str {
+        match *self {
+            SourceError::Io(..) => "DS0201",
+            SourceError::Syntax(..) => "DS0202",
+        }
+    }
+
+    /// Describe the error as a `Diagnostic`
+    ///
+    /// # Parameters
+    ///
+    /// - `source`: The Rust source code the error occurred in, empty for IO
+    ///   errors
+    pub fn to_diagnostic(&self, source: &str) -> Diagnostic {
+        match *self {
+            SourceError::Io(ref err) =>
+                Diagnostic::new(self.code(), Severity::Error, format!("Could not read source file: {}", err)),
+            SourceError::Syntax(ref message, position) =>
+                Diagnostic::new(self.code(), Severity::Error, format!("Invalid Rust source code: {}", message))
+                    .with_label(line_span(source, position), ""),
+        }
+    }
+}
+
+#[cfg(feature = "source")]
+quick_error! {
+    /// Mismatches between documentation and the code it documents
+    #[derive(Debug, PartialEq, Eq, Clone, Hash)]
+    pub enum ValidationError {
+        /// A parameter of the function is missing from the `Parameters`
+        /// section
+        UndocumentedParameter(name: String, position: Position) {
+            description("Undocumented parameter")
+            display(me) -> ("{}: {}", me.position(), me.message())
+        }
+        /// The `Parameters` section documents a parameter the function does
+        /// not have
+        UnknownParameter(name: String, position: Position) {
+            description("Documented parameter does not exist")
+            display(me) -> ("{}: {}", me.position(), me.message())
+        }
+        /// Parameters are documented in a different order than they are
+        /// declared in
+        MisorderedParameter(name: String, position: Position) {
+            description("Parameters documented out of order")
+            display(me) -> ("{}: {}", me.position(), me.message())
+        }
+        /// A parameter is documented more than once
+        DuplicateParameter(name: String, position: Position) {
+            description("Parameter documented more than once")
+            display(me) -> ("{}: {}", me.position(), me.message())
+        }
+        /// A type (or const) parameter is missing from the `Type Parameters`
+        /// section
+        UndocumentedTypeParameter(name: String, position: Position) {
+            description("Undocumented type parameter")
+            display(me) -> ("{}: {}", me.position(), me.message())
+        }
+        /// The `Type Parameters` section documents a parameter the item does
+        /// not have
+        UnknownTypeParameter(name: String, position: Position) {
+            description("Documented type parameter does not exist")
+            display(me) -> ("{}: {}", me.position(), me.message())
+        }
+        /// A lifetime is missing from the `Lifetime Parameters` section
+        UndocumentedLifetime(name: String, position: Position) {
+            description("Undocumented lifetime")
+            display(me) -> ("{}: {}", me.position(), me.message())
+        }
+        /// The `Lifetime Parameters` section documents a lifetime the item
+        /// does not have
+        UnknownLifetime(name: String, position: Position) {
+            description("Documented lifetime does not exist")
+            display(me) -> ("{}: {}", me.position(), me.message())
+        }
+        /// A variant of the return type is missing from the `Returns` section
+        UndocumentedReturnVariant(name: String, position: Position) {
+            description("Undocumented variant of the return type")
+            display(me) -> ("{}: {}", me.position(), me.message())
+        }
+        /// The `Returns` section documents a variant the return type does not
+        /// have
+        UnknownReturnVariant(name: String, position: Position) {
+            description("Documented variant of the return type does not exist")
+            display(me) -> ("{}: {}", me.position(), me.message())
+        }
+    }
+}
+
+#[cfg(feature = "source")]
+impl ValidationError {
+    /// Where in the documentation string the problem is
+    ///
+    /// For things that are missing from the docs, this is the section they
+    /// should be added to (or an unknown position if there is none).
+    pub fn position(&self) -> Position {
+        match *self {
+            ValidationError::UndocumentedParameter(_, position) |
+            ValidationError::UnknownParameter(_, position) |
+            ValidationError::MisorderedParameter(_, position) |
+            ValidationError::DuplicateParameter(_, position) |
+            ValidationError::UndocumentedTypeParameter(_, position) |
+            ValidationError::UnknownTypeParameter(_, position) |
+            ValidationError::UndocumentedLifetime(_, position) |
+            ValidationError::UnknownLifetime(_, position) |
+            ValidationError::UndocumentedReturnVariant(_, position) |
+            ValidationError::UnknownReturnVariant(_, position) => position,
+        }
+    }
+
+    /// Name of the parameter, lifetime, or return value variant the problem is
+    /// about
+    pub fn name(&self) -> &str {
+        match *self {
+            ValidationError::UndocumentedParameter(ref name, _) |
+            ValidationError::UnknownParameter(ref name, _) |
+            ValidationError::MisorderedParameter(ref name, _) |
+            ValidationError::DuplicateParameter(ref name, _) |
+            ValidationError::UndocumentedTypeParameter(ref name, _) |
+            ValidationError::UnknownTypeParameter(ref name, _) |
+            ValidationError::UndocumentedLifetime(ref name, _) |
+            ValidationError::UnknownLifetime(ref name, _) |
+            ValidationError::UndocumentedReturnVariant(ref name, _) |
+            ValidationError::UnknownReturnVariant(ref name, _) => name,
+        }
+    }
+
+    /// Stable code of this kind of problem, e.g. `DS0102` (see `Diagnostic`
+    /// for all codes)
+    pub fn code(&self) -> &'static str {
+        match *self {
+            ValidationError::UndocumentedParameter(..) => "DS0101",
+            ValidationError::UnknownParameter(..) => "DS0102",
+            ValidationError::MisorderedParameter(..) => "DS0103",
+            ValidationError::DuplicateParameter(..) => "DS0104",
+            ValidationError::UndocumentedTypeParameter(..) => "DS0105",
+            ValidationError::UnknownTypeParameter(..) => "DS0106",
+            ValidationError::UndocumentedLifetime(..) => "DS0107",
+            ValidationError::UnknownLifetime(..) => "DS0108",
+            ValidationError::UndocumentedReturnVariant(..) => "DS0109",
+            ValidationError::UnknownReturnVariant(..) => "DS0110",
+        }
+    }
+
+    /// What is wrong, without the position `Display` starts with
+    pub fn message(&self) -> String {
+        match *self {
+            ValidationError::UndocumentedParameter(ref name, _) => format!("Parameter `{}` is not documented", name),
+            ValidationError::UnknownParameter(ref name, _) => format!("Documented parameter `{}` does not exist", name),
+            ValidationError::MisorderedParameter(ref name, _) =>
+                format!("Parameter `{}` is documented out of order", name),
+            ValidationError::DuplicateParameter(ref name, _) =>
+                format!("Parameter `{}` is documented more than once", name),
+            ValidationError::UndocumentedTypeParameter(ref name, _) =>
+                format!("Type parameter `{}` is not documented", name),
+            ValidationError::UnknownTypeParameter(ref name, _) =>
+                format!("Documented type parameter `{}` does not exist", name),
+            ValidationError::UndocumentedLifetime(ref name, _) => format!("Lifetime `{}` is not documented", name),
+            ValidationError::UnknownLifetime(ref name, _) => format!("Documented lifetime `{}` does not exist", name),
+            ValidationError::UndocumentedReturnVariant(ref name, _) =>
+                format!("Return value `{}` is not documented", name),
+            ValidationError::UnknownReturnVariant(ref name, _) =>
+                format!("Documented return value `{}` is not a variant of the return type", name),
+        }
+    }
+
+    /// Describe the problem as a `Diagnostic`, with labels and help
+    ///
+    /// Misordered parameters are warnings, everything else is an error.
+    ///
+    /// # Parameters
+    ///
+    /// - `docs`: The documentation string the problem is in
+    ///
+    /// # Returns
+    ///
+    /// The diagnostic, with spans relative to `docs`. See
+    /// `SourceItem::diagnostics` for diagnostics of an item, which also
+    /// suggests fixes for misspelled names.
+    pub fn to_diagnostic(&self, docs: &str) -> Diagnostic {
+        let position = self.position();
+        let missing = format!("`{}` is missing from this section", self.name());
+        let unknown = "remove it, or fix its name".to_string();
+
+        let (severity, span, label, help) = match *self {
+            ValidationError::UndocumentedParameter(..) =>
+                (Severity::Error, None, &missing[..], "document it in the `Parameters` section".to_string()),
+            ValidationError::UndocumentedTypeParameter(..) =>
+                (Severity::Error, None, &missing[..], "document it in the `Type Parameters` section".to_string()),
+            ValidationError::UndocumentedLifetime(..) =>
+                (Severity::Error, None, &missing[..], "document it in the `Lifetime Parameters` section".to_string()),
+            ValidationError::UndocumentedReturnVariant(..) =>
+                (Severity::Error, None, &missing[..], "document it in the `Returns` section".to_string()),
+            ValidationError::UnknownParameter(..) => (Severity::Error, Some(self.name()), "not a parameter", unknown),
+            ValidationError::UnknownTypeParameter(..) =>
+                (Severity::Error, Some(self.name()), "not a type parameter", unknown),
+            ValidationError::UnknownLifetime(..) => (Severity::Error, Some(self.name()), "not a lifetime", unknown),
+            ValidationError::UnknownReturnVariant(..) =>
+                (Severity::Error, Some(self.name()), "not a variant of the return type", unknown),
+            ValidationError::MisorderedParameter(..) => (Severity::Warning, Some(self.name()), "documented out of order",
+                "document parameters in the order they are declared in".to_string()),
+            ValidationError::DuplicateParameter(..) =>
+                (Severity::Error, Some(self.name()), "documented again here", "remove one of the entries".to_string()),
+        };
+
+        // Missing things are labelled at the section they should be added to
+        let span = match span {
+            Some(name) => text_span(docs, position, name),
+            None => line_span(docs, position),
+        };
+
+        Diagnostic::new(self.code(), severity, self.message())
+            .with_label(span, label)
+            .with_help(help)
     }
 }
@@ -1,16 +1,21 @@
 use ::std::iter::Peekable;
+use ::std::mem;
+use ::std::rc::Rc;
 use ::pulldown_cmark::{Event, Tag};
 
 use ::types::*;
+use ::patterns::{Identifier, Pattern};
 use ::errors::ParseError;
-use ::to_md::md;
+use ::options::{ParseOptions, UnknownSections, Separator};
+use ::span::{LineIndex, Position, Span, SpannedEvent, SpannedDocBlock, SectionSpans, ItemSpans, span_of};
+use ::to_md::{link_definitions, md, md_from_source, unescape};
 
 macro_rules! not {
     (start $tag:pat) => (not!(Event::Start($tag)));
     (end $tag:pat) => (not!(Event::End($tag)));
     ($tag:pat) => (
-        |event| {
-            if let $tag = *event {
+        |item| {
+            if let ($tag, _) = *item {
                 false
             } else {
                 true
@@ -19,132 +24,1116 @@ macro_rules! not {
     );
 }
 
-pub fn teaser<'a, I>(events: &mut I) -> Result<String, ParseError> where
-    I: Iterator<Item=Event<'a>>,
+macro_rules! is {
+    (start $tag:pat) => (is!(Event::Start($tag)));
+    (end $tag:pat) => (is!(Event::End($tag)));
+    ($tag:pat) => (
+        |item| {
+            if let ($tag, _) = *item {
+                true
+            } else {
+                false
+            }
+        }
+    );
+}
+
+/// Consume events up to (but not including) the first one matching `stop`
+fn until<'a, I, F>(events: &mut Peekable<I>, stop: F) -> Vec<SpannedEvent<'a>> where
+    I: Iterator<Item=SpannedEvent<'a>>,
+    F: Fn(&SpannedEvent<'a>) -> bool,
 {
-    if let Some(Event::Start(Tag::Paragraph)) = events.next() {
-        Ok(md(events.take_while(not!(end Tag::Paragraph))))
-    } else {
-        Err(ParseError::NoTeaser)
+    let mut taken = vec![];
+
+    loop {
+        let done = match events.peek() {
+            Some(item) => stop(item),
+            None => true,
+        };
+        if done {
+            break;
+        }
+
+        taken.extend(events.next());
     }
+
+    taken
+}
+
+/// Serialize events to Markdown, also returning the span they cover
+///
+/// `source` is the documentation string the events were parsed from (or empty
+/// if unknown), see `md_from_source`.
+fn md_spanned<'a>(events: Vec<SpannedEvent<'a>>, source: &str) -> (String, Span) {
+    let span = span_of(&events);
+    (md_from_source(events.into_iter(), source), span)
 }
 
-pub fn description<'a, I>(events: &mut I) -> Result<Option<String>, ParseError> where
-    I: Iterator<Item=Event<'a>>,
+/// The text of inline events, without any formatting
+///
+/// Line breaks become spaces, and the text of inline code, emphasis or links
+/// is kept as it is.
+fn plain_text(events: &[SpannedEvent]) -> String {
+    let text: String = events.iter()
+        .filter_map(|(event, _)| match *event {
+            Event::Text(ref text) => Some(&text[..]),
+            Event::SoftBreak | Event::HardBreak => Some(" "),
+            _ => None,
+        })
+        .collect();
+
+    text.trim().to_string()
+}
+
+/// Consume the rest of an element whose start was already consumed, returning
+/// its content (including all nested elements) without the end
+fn rest_of_element<'a, I>(events: &mut I) -> Vec<SpannedEvent<'a>> where
+    I: Iterator<Item=SpannedEvent<'a>>,
 {
-    let description = md(events.take_while(not!(start Tag::Header(1))));
+    let mut content = vec![];
+    let mut depth = 1;
 
-    if description.is_empty() {
-        return Ok(None);
+    for (event, span) in events {
+        match event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            break;
+        }
+        content.push((event, span));
     }
 
-    Ok(Some(description))
+    content
 }
 
-pub fn sections<'a, I>(events: &mut Peekable<I>) -> Result<Vec<DocSection>, ParseError> where
-    I: Iterator<Item=Event<'a>>,
+/// Skip the rest of the element starting with `first`, including all nested
+/// elements
+fn skip_element<'a, I>(events: &mut I, first: &Event<'a>) where
+    I: Iterator<Item=SpannedEvent<'a>>,
 {
-    let mut sections: Vec<DocSection> = vec![];
+    if let Event::Start(_) = *first {
+        rest_of_element(events);
+    }
+}
 
-    loop {
-        // Assume the previous item was the start of an `Header(1)`, so a
-        // section begins witht he text content of that header.
-        //
-        // TODO: Handle that more nicely (see note on `take_while` above)
-        // TODO: Deal with headlines not starting with text (i.e., those
-        // starting with inline markdown).
-        let go_on = {
-            // Peek next item without consuming
-            //
-            // This is inside a block to ahve the handle on events is dropped
-            // sooner (so we can use it in `events.next()` below).
-            if let Some(&Event::Text(_)) = events.peek() {
-                true
-            } else {
-                false
-            }
-        };
+/// State shared by all extractors while parsing one doc block
+#[derive(Debug, Default)]
+pub struct Context<'s> {
+    /// How to parse
+    pub options: ParseOptions,
+    /// The documentation string being parsed, empty when parsing events
+    pub source: &'s str,
+    /// Lines of `source`, to turn offsets into positions
+    lines: Rc<LineIndex<'s>>,
+    /// Warnings, and in lenient mode errors, encountered so far
+    pub diagnostics: Vec<ParseError>,
+}
 
-        if go_on {
-            sections.push(try!(section(events)));
-            continue;
+impl<'s> Context<'s> {
+    pub fn new(options: ParseOptions, source: &'s str) -> Context<'s> {
+        Context {
+            options,
+            source,
+            lines: Rc::new(LineIndex::new(source)),
+            diagnostics: vec![],
         }
+    }
 
+    /// Report an error
+    ///
+    /// In strict mode, this just returns the error so it can be passed on with
+    /// `?`. In lenient mode, it is recorded and the caller is expected to
+    /// skip the offending markdown.
+    fn report(&mut self, error: ParseError) -> Result<(), ParseError> {
+        if self.options.lenient {
+            self.diagnostics.push(error);
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
+    /// Report a problem that never stops parsing
+    fn warn(&mut self, warning: ParseError) {
+        self.diagnostics.push(warning);
+    }
+
+    /// A context for items written as a term on a line of its own followed by
+    /// docs, like table rows and definitions
+    fn term_context(&self) -> Context<'s> {
+        Context {
+            options: ParseOptions {
+                separators: vec![Separator::Newline],
+                ..self.options.clone()
+            },
+            source: self.source,
+            lines: self.lines.clone(),
+            diagnostics: vec![],
+        }
+    }
+
+    /// Does this event start a new section?
+    fn is_section_start(&self, item: &SpannedEvent) -> bool {
+        if let (Event::Start(Tag::Header(level)), _) = *item {
+            level == self.options.heading_level
+        } else {
+            false
+        }
+    }
+}
+
+pub fn docblock<'a, I>(events: &mut Peekable<I>, ctx: &mut Context) -> Result<SpannedDocBlock, ParseError> where
+    I: Iterator<Item=SpannedEvent<'a>>,
+{
+    let events: Vec<_> = events.collect();
+    let definitions = link_definitions(events.iter().cloned(), ctx.source);
+    let mut events = events.into_iter().peekable();
+
+    let (teaser, teaser_span) = teaser(&mut events, ctx)?;
+    let (description, (subsections, subsection_spans)) = description(&mut events, ctx)?;
+    let (sections, section_spans) = sections(&mut events, ctx)?.into_iter().unzip();
+
+    // The definitions of all reference-style links go at the end of the
+    // description, so they are written once and keep the teaser a single line
+    let description = match (description, definitions) {
+        (Some((text, span)), Some(definitions)) => Some((format!("{}\n\n{}", text, definitions), span)),
+        (None, Some(definitions)) => Some((definitions, Span::default())),
+        (description, None) => description,
+    };
+
+    Ok(SpannedDocBlock {
+        doc: DocBlock {
+            teaser,
+            description: description.as_ref().map(|(text, _)| text.clone()),
+            subsections,
+            sections,
+        },
+        teaser: teaser_span,
+        description: description.map(|(_, span)| span),
+        subsections: subsection_spans,
+        sections: section_spans,
+    })
+}
+
+pub fn teaser<'a, I>(events: &mut Peekable<I>, ctx: &mut Context) -> Result<(String, Span), ParseError> where
+    I: Iterator<Item=SpannedEvent<'a>>,
+{
+    let position = match events.peek() {
+        Some(&(Event::Start(Tag::Paragraph), _)) => None,
+        Some(&(_, span)) => Some(span.start),
+        None => Some(Position::default()),
+    };
+
+    if let Some(position) = position {
+        // Leave whatever is there for the description or sections
+        ctx.report(ParseError::NoTeaser(position))?;
+        return Ok((String::new(), Span::default()));
+    }
+
+    events.next();
+    let teaser = until(events, is!(end Tag::Paragraph));
+    events.next();
+    Ok(md_spanned(teaser, ctx.source))
+}
+
+/// The description, and its subsections
+pub fn description<'a, I>(events: &mut Peekable<I>, ctx: &mut Context) -> Result<(Option<(String, Span)>, SpannedSubsections), ParseError> where
+    I: Iterator<Item=SpannedEvent<'a>>,
+{
+    let body = until(events, |item| ctx.is_section_start(item));
+    let (body, subsections) = split_subsections(body, ctx.options.heading_level);
+    let (description, span) = md_spanned(body, ctx.source);
+    let subsections = parse_subsections(None, subsections, ctx)?;
+
+    if description.is_empty() {
+        return Ok((None, subsections));
+    }
+
+    Ok((Some((description, span)), subsections))
+}
+
+pub fn sections<'a, I>(events: &mut Peekable<I>, ctx: &mut Context) -> Result<Vec<(DocSection, SectionSpans)>, ParseError> where
+    I: Iterator<Item=SpannedEvent<'a>>,
+{
+    let mut sections = vec![];
+
+    loop {
         match events.next() {
             None => break,
             // Next section
-            Some(Event::Start(Tag::Header(1))) =>
-                sections.push(try!(section(events))),
-            Some(unexpected) =>
-                return Err(ParseError::UnexpectedMarkdown(
-                    "Sections".into(), format!("{:?}", unexpected))),
+            Some(ref item) if ctx.is_section_start(item) =>
+                sections.push(section(events, ctx, item.1)?),
+            Some((unexpected, span)) => {
+                ctx.report(ParseError::UnexpectedMarkdown(
+                    "Sections".into(), format!("{:?}", unexpected), span.start))?;
+                skip_element(events, &unexpected);
+            }
         }
     }
 
     Ok(sections)
 }
 
-fn section<'a, I>(events: &mut I) -> Result<DocSection, ParseError> where
-    I: Iterator<Item=Event<'a>>,
+fn section<'a, I>(events: &mut Peekable<I>, ctx: &mut Context, start: Span) -> Result<(DocSection, SectionSpans), ParseError> where
+    I: Iterator<Item=SpannedEvent<'a>>,
 {
-    // the next item is the value after a `Event::Start(Tag::Header(1))`
-    let headline = md(events.take_while(not!(end Tag::Header(1))));
-
-    // What kind of headline are we dealing with?
-    let section = match &headline.trim().to_lowercase()[..] {
-        "parameters" =>
-            DocSection::Parameters(try!(list(events, &headline))),
-        "type parameters" =>
-            DocSection::TypeParameters(try!(list(events, &headline))),
-        "lifetime parameters" | "lifetimes" =>
-            DocSection::LifetimeParameters(try!(list(events, &headline))),
-        "returns" =>
+    // the next item is the value after a `Event::Start(Tag::Header(_))`
+    let headline_events = until(events, is!(end Tag::Header(_)));
+    let plain_headline = plain_text(&headline_events);
+    let (headline, headline_span) = md_spanned(headline_events, ctx.source);
+    events.next();
+
+    // Everything up to the next headline belongs to this section
+    let body = until(events, |item| ctx.is_section_start(item));
+    let span = start.to(span_of(&body));
+
+    // What kind of headline are we dealing with? Formatting (like in
+    // `` # `Parameters` `` or `# *Returns*`) doesn't matter, but custom
+    // sections keep it in their headline.
+    let kind = ctx.options.headlines.kind(&plain_headline, ctx.options.case_sensitive);
+    let headline = if kind.is_some() { plain_headline } else { headline };
+    if kind.is_none() {
+        let unknown = ParseError::UnknownSection(headline.clone(), headline_span.start);
+        match ctx.options.unknown_sections {
+            UnknownSections::Keep => {}
+            UnknownSections::Warn => ctx.warn(unknown),
+            UnknownSections::Error => ctx.report(unknown)?,
+        }
+    }
+
+    let heading = Heading { kind, headline, level: ctx.options.heading_level };
+    let (section, mut spans) = section_content(heading, body, ctx)?;
+    spans.span = span;
+    spans.headline = headline_span;
+    Ok((section, spans))
+}
+
+/// What a section (or subsection) is, as told by its headline
+struct Heading {
+    /// Kind of the section, `None` for custom sections
+    kind: Option<SectionKind>,
+    /// The headline as stored in the `DocSection`
+    headline: String,
+    /// Level of the headline, `1` for `# Headline`
+    level: i32,
+}
+
+/// Parse the content of a section (or subsection) with the given heading
+///
+/// The returned spans of section and headline are empty.
+fn section_content<'a>(heading: Heading, body: Vec<SpannedEvent<'a>>, ctx: &mut Context) -> Result<(DocSection, SectionSpans), ParseError> {
+    let Heading { kind, headline, level } = heading;
+    let (body, subsections) = split_subsections(body, level);
+    let mut body = body.into_iter().peekable();
+    let mut items = vec![];
+
+    let section = match kind {
+        Some(SectionKind::Parameters) =>
+            DocSection::Parameters(list(&mut body, ctx, &headline, &mut items, parameter_item)?, vec![]),
+        Some(SectionKind::TypeParameters) =>
+            DocSection::TypeParameters(list(&mut body, ctx, &headline, &mut items, ident_item)?, vec![]),
+        Some(SectionKind::LifetimeParameters) =>
+            DocSection::LifetimeParameters(list(&mut body, ctx, &headline, &mut items, ident_item)?, vec![]),
+        Some(SectionKind::Returns) => {
+            let body: Vec<_> = body.collect();
+            let (text, list_events) = body.split_at(list_start(&body, ctx));
             DocSection::Returns(
-                md(events.take_while(not!(start Tag::List(_)))),
-                try!(list(events, &headline))),
-        _ =>
+                md_spanned(text.to_vec(), ctx.source).0,
+                list(&mut list_events.iter().cloned(), ctx, &headline, &mut items, pattern_item)?, vec![])
+        }
+        Some(SectionKind::Examples) =>
+            DocSection::Examples(examples(&mut body, ctx.source), vec![]),
+        Some(SectionKind::Panics) => {
+            let (text, list_events) = text_around_lists(&mut body);
+            DocSection::Panics(
+                md_spanned(text, ctx.source).0,
+                list(&mut list_events.into_iter(), ctx, &headline, &mut items, condition)?, vec![])
+        }
+        Some(SectionKind::Errors) => {
+            let (text, list_events) = text_around_lists(&mut body);
+            DocSection::Errors(
+                md_spanned(text, ctx.source).0,
+                list(&mut list_events.into_iter(), ctx, &headline, &mut items, error_condition)?, vec![])
+        }
+        Some(SectionKind::Safety) =>
+            DocSection::Safety(md_spanned(body.collect(), ctx.source).0, vec![]),
+        None =>
             DocSection::Custom(
                 headline,
-                md(events.take_while(not!(start Tag::Header(1))))),
+                md_spanned(body.collect(), ctx.source).0, vec![]),
     };
 
-    Ok(section)
+    let (subsections, subsection_spans) = parse_subsections(kind, subsections, ctx)?;
+
+    Ok((with_subsections(section, subsections), SectionSpans {
+        span: Span::default(),
+        headline: Span::default(),
+        items,
+        subsections: subsection_spans,
+    }))
 }
 
-fn list<'a, I>(events: &mut I, section: &str) -> Result<Vec<(Identifier, Documentation)>, ParseError> where
-    I: Iterator<Item=Event<'a>>,
+/// Split the body of a `Panics` or `Errors` section into its text and the
+/// list of conditions
+///
+/// The text is what comes before the list as well as what follows it.
+fn text_around_lists<'a, I>(body: &mut Peekable<I>) -> (Vec<SpannedEvent<'a>>, Vec<SpannedEvent<'a>>) where
+    I: Iterator<Item=SpannedEvent<'a>>,
+{
+    let mut text = until(body, is!(start Tag::List(_)));
+    let mut list = vec![];
+
+    while body.peek().map_or(false, is!(start Tag::List(_))) {
+        list.extend(body.next());
+        list.extend(rest_of_element(body));
+    }
+
+    text.extend(body);
+    (text, list)
+}
+
+/// Subsections and their spans
+type SpannedSubsections = (Vec<Subsection>, Vec<SectionSpans>);
+
+/// The events of a subsection: Where its headline starts, the events of the
+/// headline, its level, and the events of its content
+type SubsectionEvents<'a> = (Span, Vec<SpannedEvent<'a>>, i32, Vec<SpannedEvent<'a>>);
+
+/// Split the content of a section whose headline has the given level into the
+/// content before its first subsection, and its subsections
+///
+/// Subsections start with headlines deeper than `level`, but not deeper than
+/// the first of them. Even deeper headlines start subsections of these.
+fn split_subsections<'a>(events: Vec<SpannedEvent<'a>>, level: i32) -> (Vec<SpannedEvent<'a>>, Vec<SubsectionEvents<'a>>) {
+    let mut content = vec![];
+    let mut subsections: Vec<SubsectionEvents<'a>> = vec![];
+    let mut subsection_level = None;
+    let mut in_headline = false;
+    let mut depth = 0;
+
+    for (event, span) in events {
+        if let Event::Start(Tag::Header(n)) = event {
+            if depth == 0 && n > level && subsection_level.is_none_or(|first| n <= first) {
+                subsection_level = subsection_level.or(Some(n));
+                subsections.push((span, vec![], n, vec![]));
+                in_headline = true;
+                depth += 1;
+                continue;
+            }
+        }
+
+        match event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            _ => {}
+        }
+
+        if in_headline {
+            if depth == 0 {
+                in_headline = false;
+            } else if let Some(subsection) = subsections.last_mut() {
+                subsection.1.push((event, span));
+            }
+            continue;
+        }
+
+        match subsections.last_mut() {
+            Some(subsection) => subsection.3.push((event, span)),
+            None => content.push((event, span)),
+        }
+    }
+
+    (content, subsections)
+}
+
+/// Parse subsections of a section of the given kind
+fn parse_subsections<'a>(kind: Option<SectionKind>, subsections: Vec<SubsectionEvents<'a>>, ctx: &mut Context) -> Result<SpannedSubsections, ParseError> {
+    let mut parsed = vec![];
+    let mut spans = vec![];
+
+    for (start, headline, level, body) in subsections {
+        let (headline, headline_span) = md_spanned(headline, ctx.source);
+        let span = start.to(span_of(&body));
+        // The headline of a custom subsection is kept in the `Subsection`
+        // only, not again in its `DocSection::Custom`
+        let section_headline = if kind.is_some() { headline.clone() } else { String::new() };
+        let heading = Heading { kind, headline: section_headline, level };
+        let (section, mut section_spans) = section_content(heading, body, ctx)?;
+        section_spans.span = span;
+        section_spans.headline = headline_span;
+
+        parsed.push((headline, section));
+        spans.push(section_spans);
+    }
+
+    Ok((parsed, spans))
+}
+
+/// Replace the subsections of a section
+fn with_subsections(section: DocSection, subsections: Vec<Subsection>) -> DocSection {
+    match section {
+        DocSection::Parameters(list, _) => DocSection::Parameters(list, subsections),
+        DocSection::TypeParameters(list, _) => DocSection::TypeParameters(list, subsections),
+        DocSection::LifetimeParameters(list, _) => DocSection::LifetimeParameters(list, subsections),
+        DocSection::Returns(text, list, _) => DocSection::Returns(text, list, subsections),
+        DocSection::Examples(examples, _) => DocSection::Examples(examples, subsections),
+        DocSection::Panics(text, list, _) => DocSection::Panics(text, list, subsections),
+        DocSection::Errors(text, list, _) => DocSection::Errors(text, list, subsections),
+        DocSection::Safety(docs, _) => DocSection::Safety(docs, subsections),
+        DocSection::Custom(headline, docs, _) => DocSection::Custom(headline, docs, subsections),
+    }
+}
+
+/// The events of a list item, where it starts, and for table rows the spans
+/// of row and docs cell
+type ItemEvents<'a> = (Vec<SpannedEvent<'a>>, Span, Option<(Span, Span)>);
+
+/// Items of a section mapping identifiers to docs
+///
+/// Besides bullet lists, these can be written as tables (if the `tables`
+/// extension is enabled) or definition lists, see `table_rows` and
+/// `definitions`.
+fn list<'a, I, T, F>(events: &mut I, ctx: &mut Context, section: &str, spans: &mut Vec<ItemSpans>, parse_item: F) -> Result<Vec<T>, ParseError> where
+    I: Iterator<Item=SpannedEvent<'a>>,
+    F: Fn(&[SpannedEvent<'a>], &Context, &str, Span) -> Result<(T, ItemSpans), ParseError>,
 {
     let mut list = vec![];
 
     loop {
-        match events.next() {
-            Some(Event::Start(Tag::List(_))) => continue,
-            None | Some(Event::End(Tag::List(_))) => break,
-            Some(Event::Start(Tag::Item)) => list.push(try!(list_item(events))),
-            Some(unexpected) => return Err(ParseError::UnexpectedMarkdown(
-                section.into(), format!("{:?}", unexpected))),
+        let (items, terms): (Vec<ItemEvents<'a>>, bool) = match events.next() {
+            None => break,
+            Some((Event::Start(Tag::List(_)), _)) |
+            Some((Event::End(Tag::List(_)), _)) => continue,
+            Some((Event::Start(Tag::Item), start)) => (vec![(rest_of_element(events), start, None)], false),
+            Some((Event::Start(Tag::Table(alignments)), start)) => {
+                match table_rows(&rest_of_element(events)) {
+                    Some(rows) => (rows, true),
+                    None => {
+                        ctx.report(ParseError::UnexpectedMarkdown(
+                            section.into(), format!("{:?}", Event::Start(Tag::Table(alignments))), start.start))?;
+                        continue;
+                    }
+                }
+            }
+            Some((Event::Start(Tag::Paragraph), start)) => {
+                match definitions(&rest_of_element(events), ctx) {
+                    Some(definitions) => (definitions, true),
+                    None => {
+                        ctx.report(ParseError::UnexpectedMarkdown(
+                            section.into(), format!("{:?}", Event::Start(Tag::Paragraph)), start.start))?;
+                        continue;
+                    }
+                }
+            }
+            Some((unexpected, span)) => {
+                ctx.report(ParseError::UnexpectedMarkdown(
+                    section.into(), format!("{:?}", unexpected), span.start))?;
+                skip_element(events, &unexpected);
+                continue;
+            }
+        };
+
+        for (item, start, cell_spans) in items {
+            let parsed = if terms {
+                parse_item(&item, &ctx.term_context(), section, start)
+            } else {
+                parse_item(&item, ctx, section, start)
+            };
+
+            match parsed {
+                Ok((item, mut item_spans)) => {
+                    if let Some((row, docs)) = cell_spans {
+                        item_spans.span = row;
+                        item_spans.docs = docs;
+                    }
+                    list.push(item);
+                    spans.push(item_spans);
+                }
+                Err(error) => ctx.report(error)?,
+            }
         }
     }
 
     Ok(list)
 }
 
-fn list_item<'a, I>(events: &mut I) -> Result<(Identifier, Documentation), ParseError> where
-    I: Iterator<Item=Event<'a>>,
+/// The rows of a table with two columns, identifier and docs, as items
+/// written like `` `ident` `` followed by a line break and the docs
+///
+/// The identifier does not need to be in backticks here. Returns `None` for
+/// tables with any other number of columns.
+fn table_rows<'a>(table: &[SpannedEvent<'a>]) -> Option<Vec<ItemEvents<'a>>> {
+    let mut rows = vec![];
+    let mut events = table.iter().cloned();
+
+    while let Some((event, start)) = events.next() {
+        let is_row = matches!(event, Event::Start(Tag::TableRow));
+        let row = rest_of_element(&mut events);
+        let mut cells = vec![];
+        let mut row_events = row.iter().cloned();
+        while let Some((event, _)) = row_events.next() {
+            if let Event::Start(Tag::TableCell) = event {
+                cells.push(trim_cell(rest_of_element(&mut row_events)));
+            }
+        }
+        if cells.len() != 2 {
+            return None;
+        }
+        // The header only names the columns
+        if !is_row {
+            continue;
+        }
+
+        let docs = cells.pop().unwrap_or_default();
+        let mut item = cells.pop().unwrap_or_default();
+        match item.first() {
+            Some(&(Event::Start(Tag::Code), _)) |
+            Some(&(Event::Start(Tag::Strong), _)) => {}
+            _ => {
+                item.insert(0, (Event::Start(Tag::Code), Span::default()));
+                item.push((Event::End(Tag::Code), Span::default()));
+            }
+        }
+        let docs_span = span_of(&docs);
+        item.push((Event::SoftBreak, Span::default()));
+        item.extend(docs);
+
+        let row_span = if start.is_empty() { span_of(&row) } else { start.to(span_of(&row)) };
+        rows.push((item, start, Some((row_span, docs_span))));
+    }
+
+    Some(rows)
+}
+
+/// Remove the whitespace around the content of a table cell
+fn trim_cell<'a>(mut cell: Vec<SpannedEvent<'a>>) -> Vec<SpannedEvent<'a>> {
+    if let Some(&mut (Event::Text(ref mut text), _)) = cell.first_mut() {
+        let trimmed = text.trim_start().to_string();
+        *text = trimmed.into();
+    }
+    if let Some(&mut (Event::Text(ref mut text), _)) = cell.last_mut() {
+        let trimmed = text.trim_end().to_string();
+        *text = trimmed.into();
+    }
+
+    cell.into_iter()
+        .filter(|(event, _)| match *event {
+            Event::Text(ref text) => !text.is_empty(),
+            _ => true,
+        })
+        .collect()
+}
+
+/// Split a paragraph written like a definition list into its items, e.g.
+///
+/// ```markdown
+/// `foo`
+///     Docs of foo
+/// `bar` (u32)
+///     Docs of bar
+/// ```
+///
+/// Returns `None` if the paragraph does not start with such a term line.
+fn definitions<'a>(paragraph: &[SpannedEvent<'a>], ctx: &Context) -> Option<Vec<ItemEvents<'a>>> {
+    let mut term_end = try_opt!(term_line_end(paragraph, ctx));
+    let mut start = 0;
+    let mut definitions = vec![];
+
+    for (i, (event, _)) in paragraph.iter().enumerate() {
+        if i <= term_end {
+            continue;
+        }
+        if let Event::SoftBreak = *event {
+            if let Some(end) = term_line_end(&paragraph[i + 1..], ctx) {
+                definitions.push((paragraph[start..i].to_vec(), span_of(&paragraph[start..i]), None));
+                start = i + 1;
+                term_end = i + 1 + end;
+            }
+        }
+    }
+    definitions.push((paragraph[start..].to_vec(), span_of(&paragraph[start..]), None));
+
+    Some(definitions)
+}
+
+/// Where the line holding the term of a definition (an identifier in
+/// backticks, maybe followed by an annotation in parentheses) at the start of
+/// `events` ends, if there is one
+fn term_line_end<'a>(events: &[SpannedEvent<'a>], ctx: &Context) -> Option<usize> {
+    let ident_end = match events.first() {
+        Some(&(Event::Start(Tag::Code), _)) => events.iter().position(is!(end Tag::Code)),
+        Some(&(Event::Start(Tag::Strong), _)) if ctx.options.bold_idents =>
+            events.iter().position(is!(end Tag::Strong)),
+        _ => None,
+    };
+    let ident_end = try_opt!(ident_end);
+
+    let line_end = ident_end + 1 + try_opt!(events[ident_end + 1..].iter().position(|(event, _)| {
+        matches!(*event, Event::SoftBreak | Event::HardBreak | Event::End(_))
+    }));
+    if let Event::SoftBreak = events[line_end].0 {} else {
+        return None;
+    }
+
+    let annotation = md(events[ident_end + 1..line_end].iter().map(|(event, _)| event.clone()));
+    let annotation = annotation.trim();
+    if annotation.is_empty() || (annotation.starts_with('(') && annotation.ends_with(')')) {
+        Some(line_end)
+    } else {
+        None
+    }
+}
+
+/// Where the items of a section that starts with some text begin: At the
+/// first list, table or definition
+fn list_start<'a>(events: &[SpannedEvent<'a>], ctx: &Context) -> usize {
+    let mut depth = 0;
+
+    for (i, (event, _)) in events.iter().enumerate() {
+        if depth == 0 {
+            match *event {
+                Event::Start(Tag::List(_)) |
+                Event::Start(Tag::Table(_)) => return i,
+                Event::Start(Tag::Paragraph) if term_line_end(&events[i + 1..], ctx).is_some() => return i,
+                _ => {}
+            }
+        }
+        match *event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            _ => {}
+        }
+    }
+
+    events.len()
+}
+
+/// Split a list item into the identifier (or pattern) in backticks (or, if
+/// `bold_idents` is set, in bold) it starts with and the events following it
+///
+/// In loose lists, the identifier is at the start of the item's first
+/// paragraph.
+fn split_item<'i, 'a>(item: &'i [SpannedEvent<'a>], ctx: &Context, section: &str, start: Span) -> Result<(String, Span, &'i [SpannedEvent<'a>]), ParseError> {
+    let item_text = || md(item.iter().map(|(event, _)| event.clone()));
+
+    let ident_start = match item.first() {
+        Some(&(Event::Start(Tag::Paragraph), _)) => 1,
+        _ => 0,
+    };
+    let ident_end = match item.get(ident_start) {
+        Some(&(Event::Start(Tag::Code), _)) =>
+            item[ident_start..].iter().position(is!(end Tag::Code)),
+        Some(&(Event::Start(Tag::Strong), _)) if ctx.options.bold_idents =>
+            item[ident_start..].iter().position(is!(end Tag::Strong)),
+        _ => None,
+    };
+    let ident_end = match ident_end {
+        Some(ident_end) => ident_start + ident_end,
+        None => return Err(ParseError::NoIdent(section.into(), item_text(), start.start)),
+    };
+
+    // Identifiers are taken as written, not as Markdown
+    let ident = &item[ident_start + 1..ident_end];
+    Ok((plain_text(ident), span_of(ident), &item[ident_end + 1..]))
+}
+
+/// The docs following the identifier of a list item, see `split_item`
+fn item_docs<'a>(events: &[SpannedEvent<'a>], item: &[SpannedEvent<'a>], ctx: &Context, section: &str, ident_span: Span) -> Result<(Documentation, Span), ParseError> {
+    let (docs, docs_span) = md_spanned(events.to_vec(), ctx.source);
+    let line_break = matches!(events.first(), Some(&(Event::SoftBreak, _)) | Some(&(Event::End(Tag::Paragraph), _)));
+
+    separate_docs(&docs, docs_span, line_break, ctx)
+        .ok_or_else(|| wrong_separator(item, section, ident_span))
+}
+
+/// Remove the separator between identifier and docs from the start of `docs`
+///
+/// The separator needs to be one of those in the `ParseOptions`. Only a line
+/// break at the very start of `docs` counts as a `Newline` separator, which
+/// is either `line_break` or part of `docs`.
+fn separate_docs(docs: &str, docs_span: Span, line_break: bool, ctx: &Context) -> Option<(Documentation, Span)> {
+    for separator in &ctx.options.separators {
+        if let Separator::Newline = *separator {
+            if (line_break || docs.starts_with('\n')) && !docs.trim().is_empty() {
+                return Some((docs.trim_start().into(), skip_text(docs_span, "", ctx)));
+            }
+        } else if docs.starts_with(separator.as_str()) {
+            let separator = separator.as_str();
+            return Some((docs[separator.len()..].into(), skip_text(docs_span, separator.trim_start(), ctx)));
+        }
+    }
+
+    None
+}
+
+fn wrong_separator<'a>(item: &[SpannedEvent<'a>], section: &str, ident_span: Span) -> ParseError {
+    ParseError::WrongIdentDocsSeparator(
+        section.into(), md(item.iter().map(|(event, _)| event.clone())), ident_span.end)
+}
+
+/// A list item mapping an identifier (or pattern) to docs
+fn list_item<'a>(item: &[SpannedEvent<'a>], ctx: &Context, section: &str, start: Span) -> Result<((String, Documentation), ItemSpans), ParseError> {
+    let (ident, ident_span, rest) = split_item(item, ctx, section, start)?;
+    let (docs, docs_span) = item_docs(rest, item, ctx, section, ident_span)?;
+
+    Ok(((ident, docs), ItemSpans {
+        span: start.to(docs_span),
+        ident: ident_span,
+        docs: docs_span,
+        fields: vec![],
+    }))
+}
+
+/// A list item mapping an identifier to docs
+fn ident_item<'a>(item: &[SpannedEvent<'a>], ctx: &Context, section: &str, start: Span) -> Result<((Identifier, Documentation), ItemSpans), ParseError> {
+    let ((ident, docs), spans) = list_item(item, ctx, section, start)?;
+    match Identifier::new(&ident) {
+        Ok(ident) => Ok(((ident, docs), spans)),
+        Err(_) => Err(ParseError::InvalidIdent(section.into(), ident, spans.ident.start)),
+    }
+}
+
+/// A list item mapping a pattern to docs
+fn pattern_item<'a>(item: &[SpannedEvent<'a>], ctx: &Context, section: &str, start: Span) -> Result<((Pattern, Documentation), ItemSpans), ParseError> {
+    let ((pattern, docs), spans) = list_item(item, ctx, section, start)?;
+    match Pattern::new(&pattern) {
+        Ok(pattern) => Ok(((pattern, docs), spans)),
+        Err(_) => Err(ParseError::InvalidPattern(section.into(), pattern, spans.ident.start)),
+    }
+}
+
+/// A list item documenting a parameter, whose docs may end with a nested list
+/// of fields
+fn parameter_item<'a>(item: &[SpannedEvent<'a>], ctx: &Context, section: &str, start: Span) -> Result<((Pattern, ParameterDoc), ItemSpans), ParseError> {
+    let (pattern, ident_span, rest) = split_item(item, ctx, section, start)?;
+    let pattern = match Pattern::new(&pattern) {
+        Ok(pattern) => pattern,
+        Err(_) => return Err(ParseError::InvalidPattern(section.into(), pattern, ident_span.start)),
+    };
+
+    let (rest, fields, field_spans) = match fields(rest, ctx, section) {
+        Some((list_start, fields, field_spans)) => (&rest[..list_start], fields, field_spans),
+        None => (rest, vec![], vec![]),
+    };
+    let (docs, docs_span) = md_spanned(rest.to_vec(), ctx.source);
+    let mut doc = ParameterDoc::default();
+    let (docs, docs_span) = match annotation(&docs) {
+        Some((annotation, end)) => {
+            parse_annotation(&unescape(annotation), &mut doc);
+            // The span starts at the parenthesis, not the space before it
+            (&docs[end..], skip_text(docs_span, &docs[1..end], ctx))
+        }
+        None => (&docs[..], docs_span),
+    };
+    let line_break = matches!(rest.first(), Some(&(Event::SoftBreak, _)) | Some(&(Event::End(Tag::Paragraph), _)));
+    let (docs, docs_span) = separate_docs(docs, docs_span, line_break, ctx)
+        .ok_or_else(|| wrong_separator(item, section, ident_span))?;
+
+    doc.docs = docs;
+    doc.fields = fields;
+    Ok(((pattern, doc), ItemSpans {
+        span: start.to(span_of(item)),
+        ident: ident_span,
+        docs: docs_span,
+        fields: field_spans,
+    }))
+}
+
+/// The annotation in parentheses at the start of the docs of a parameter, and
+/// where it ends in `docs`
+fn annotation(docs: &str) -> Option<(&str, usize)> {
+    if !docs.starts_with(" (") {
+        return None;
+    }
+
+    let mut depth = 0;
+    for (i, c) in docs.char_indices().skip(1) {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return Some((&docs[2..i], i + 1));
+        }
+    }
+
+    None
+}
+
+/// Fill in the type, optionality, default and constraints of a parameter from
+/// its annotation, see `ParameterDoc`
+fn parse_annotation(annotation: &str, doc: &mut ParameterDoc) {
+    let unquote = |text: &str| text.trim().trim_matches('`').trim().to_string();
+
+    for (i, part) in split_annotation(annotation).into_iter().enumerate() {
+        let lowercase = part.to_lowercase();
+        let default = ["default:", "default =", "defaults to "].iter()
+            .find(|prefix| lowercase.starts_with(*prefix))
+            .map(|prefix| unquote(&part[prefix.len()..]));
+
+        if lowercase == "optional" {
+            doc.optional = true;
+        } else if lowercase == "required" {
+            doc.optional = false;
+        } else if default.is_some() {
+            doc.default = default;
+        } else if i == 0 && looks_like_type(part) {
+            doc.ty = Some(unquote(part));
+        } else {
+            doc.constraints.push(part.into());
+        }
+    }
+}
+
+/// Whether the first part of an annotation is a type rather than a constraint
+///
+/// Anything in backticks is a type. Otherwise, this is conservative: types are
+/// written without spaces (e.g. `u32` or `Vec<u8>`), except after commas,
+/// around `+`, `=` and `->`, and after lifetimes and keywords like `mut` or
+/// `dyn`, so `&'a mut str` is a type and `at least 1s` is not.
+pub fn looks_like_type(text: &str) -> bool {
+    let text = text.trim();
+    if text.len() > 1 && text.starts_with('`') && text.ends_with('`') {
+        return true;
+    }
+
+    let starts_like_type = text.chars().next()
+        .map(|c| c.is_alphabetic() || "_&*([".contains(c))
+        .unwrap_or(false);
+    let type_chars = text.chars().all(|c| c.is_alphanumeric() || "_:<>&'()[];,*+=- ".contains(c));
+    let arrows_only = text.match_indices('-').all(|(i, _)| text[i + 1..].starts_with('>'));
+    if !starts_like_type || !type_chars || !arrows_only {
+        return false;
+    }
+
+    let words: Vec<&str> = text.split_whitespace().collect();
+    words.windows(2).all(|pair| {
+        let (word, next) = (pair[0], pair[1]);
+        let last_token = word.rsplit(|c: char| !(c.is_alphanumeric() || c == '_' || c == '\'')).next().unwrap_or("");
+
+        word.ends_with(|c| ",;+=>".contains(c)) ||
+        next.starts_with(|c| "+=-".contains(c)) ||
+        last_token.starts_with('\'') ||
+        ["mut", "const", "dyn", "impl", "unsafe", "extern"].contains(&last_token)
+    })
+}
+
+/// Split an annotation at the commas that are not part of a type like
+/// `HashMap<K, V>` or `(u32, u32)`
+fn split_annotation(annotation: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut angles = 0;
+    let mut part_start = 0;
+    let mut previous = ' ';
+
+    for (i, c) in annotation.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' if depth > 0 => depth -= 1,
+            // Generic arguments follow their type, comparisons are spaced
+            '<' if previous.is_alphanumeric() || previous == ':' => angles += 1,
+            '>' if angles > 0 => angles -= 1,
+            ',' if depth == 0 && angles == 0 => {
+                parts.push(annotation[part_start..i].trim());
+                part_start = i + 1;
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    parts.push(annotation[part_start..].trim());
+
+    parts.into_iter().filter(|part| !part.is_empty()).collect()
+}
+
+/// A field of a parameter and its docs
+type Field = (Identifier, Documentation);
+
+/// The bullet list of `` `field`: docs `` items ending the docs of a list
+/// item, and where it starts
+///
+/// Lists containing anything else are just part of the docs.
+fn fields<'a>(events: &[SpannedEvent<'a>], ctx: &Context, section: &str) -> Option<(usize, Vec<Field>, Vec<ItemSpans>)> {
+    // Find the start of the last element
+    let mut depth = 0;
+    let mut last_start = None;
+    for (i, (event, _)) in events.iter().enumerate() {
+        match *event {
+            Event::Start(_) => {
+                if depth == 0 {
+                    last_start = Some(i);
+                }
+                depth += 1;
+            }
+            Event::End(_) => depth -= 1,
+            _ => {}
+        }
+    }
+
+    let list_start = try_opt!(last_start);
+    match (&events[list_start].0, events.last()) {
+        (&Event::Start(Tag::List(None)), Some(&(Event::End(Tag::List(None)), _))) => {}
+        _ => return None,
+    }
+
+    let mut fields = vec![];
+    let mut spans = vec![];
+    let mut items = events[list_start + 1..events.len() - 1].iter().cloned();
+    while let Some((event, start)) = items.next() {
+        if let Event::Start(Tag::Item) = event {} else {
+            return None;
+        }
+        let item = rest_of_element(&mut items);
+        let (field, item_spans) = try_opt!(ident_item(&item, ctx, section, start).ok());
+        fields.push(field);
+        spans.push(item_spans);
+    }
+
+    Some((list_start, fields, spans))
+}
+
+/// A list item that is just docs, e.g. a condition in a `Panics` section
+fn condition<'a>(item: &[SpannedEvent<'a>], ctx: &Context, _section: &str, start: Span) -> Result<(Documentation, ItemSpans), ParseError> {
+    let (docs, docs_span) = md_spanned(item.to_vec(), ctx.source);
+
+    Ok((docs, ItemSpans {
+        span: start.to(docs_span),
+        ident: Span::default(),
+        docs: docs_span,
+        fields: vec![],
+    }))
+}
+
+/// A list item in an `Errors` section, which may start with the pattern of an
+/// error variant
+fn error_condition<'a>(item: &[SpannedEvent<'a>], ctx: &Context, section: &str, start: Span) -> Result<((Option<Pattern>, Documentation), ItemSpans), ParseError> {
+    match pattern_item(item, ctx, section, start) {
+        Ok(((pattern, docs), spans)) => Ok(((Some(pattern), docs), spans)),
+        Err(_) => condition(item, ctx, section, start).map(|(docs, spans)| ((None, docs), spans)),
+    }
+}
+
+fn examples<'a, I>(events: &mut I, source: &str) -> Vec<Example> where
+    I: Iterator<Item=SpannedEvent<'a>>,
 {
-    let ident = if let Some(Event::Start(Tag::Code)) = events.next() {
-        md(events.take_while(not!(end Tag::Code)))
+    let mut examples = vec![];
+    let mut description = vec![];
+    let mut code_blocks = vec![];
+
+    while let Some((event, span)) = events.next() {
+        if let Event::Start(Tag::CodeBlock(info)) = event {
+            let code: String = events.take_while(not!(end Tag::CodeBlock(_)))
+                .filter_map(|(event, _)| match event {
+                    Event::Text(text) => Some(text),
+                    _ => None,
+                })
+                .collect();
+            code_blocks.push(code_block(&info, &code));
+            continue;
+        }
+
+        // Text after code starts the next example
+        if !code_blocks.is_empty() {
+            examples.push(Example {
+                description: md_spanned(mem::take(&mut description), source).0,
+                code_blocks: mem::take(&mut code_blocks),
+            });
+        }
+        description.push((event, span));
+    }
+
+    if !description.is_empty() || !code_blocks.is_empty() {
+        examples.push(Example {
+            description: md_spanned(description, source).0,
+            code_blocks,
+        });
+    }
+
+    examples
+}
+
+/// Parse a code block, given the info string after the opening fence and its
+/// content
+fn code_block(info: &str, code: &str) -> CodeBlock {
+    let mut language = None;
+    let mut attributes = vec![];
+
+    for token in info.split(|c: char| c == ',' || c.is_whitespace()).filter(|t| !t.is_empty()) {
+        if language.is_none() && !is_rustdoc_attribute(token) {
+            language = Some(token.to_string());
+        } else {
+            attributes.push(token.to_string());
+        }
+    }
+
+    let mut block = CodeBlock {
+        language,
+        attributes,
+        lines: vec![],
+    };
+
+    block.lines = if block.is_rust() {
+        code.lines().map(code_line).collect()
     } else {
-        return Err(ParseError::NoIdent);
+        code.lines().map(|line| CodeLine::Visible(line.into())).collect()
     };
 
-    let mut docs = md(events.take_while(not!(end Tag::Item)));
+    block
+}
 
-    if docs.starts_with(": ") {
-        docs = docs.trim_left_matches(": ").into();
+fn is_rustdoc_attribute(token: &str) -> bool {
+    match token {
+        "ignore" | "no_run" | "should_panic" | "compile_fail" | "test_harness" |
+        "allow_fail" | "standalone_crate" => true,
+        _ => {
+            token.starts_with("edition") ||
+            token.starts_with("ignore-") ||
+            // Error codes for `compile_fail` tests, e.g. `E0277`
+            (token.len() == 5 && token.starts_with('E') &&
+                token[1..].chars().all(|c| c.is_ascii_digit()))
+        }
+    }
+}
+
+/// Split off rustdoc's hidden lines, following the same rules as rustdoc
+///
+/// Hidden lines keep their indentation, only the `#` marker is removed.
+fn code_line(line: &str) -> CodeLine {
+    let trimmed = line.trim();
+    let indent = &line[..line.len() - line.trim_start().len()];
+
+    if trimmed == "#" {
+        CodeLine::Hidden(indent.into())
+    } else if trimmed.starts_with("##") {
+        // Escaped `#`, remove one of them
+        CodeLine::Visible(line.replacen("##", "#", 1))
+    } else if let Some(hidden) = trimmed.strip_prefix("# ") {
+        CodeLine::Hidden(format!("{}{}", indent, hidden))
     } else {
-        return Err(ParseError::WrongIdentDocsSeparator);
+        CodeLine::Visible(line.into())
+    }
+}
+
+/// Move the start of a span of docs past `text` (e.g. the separator after the
+/// identifier, which the span starts with) and any whitespace following it
+fn skip_text(span: Span, text: &str, ctx: &Context) -> Span {
+    if span.is_empty() {
+        return span;
     }
 
-    Ok((ident, docs))
+    let mut offset = span.start.offset + text.len();
+    match ctx.source.get(offset..span.end.offset) {
+        Some(rest) => {
+            offset += rest.len() - rest.trim_start().len();
+            Span {
+                start: ctx.lines.position(offset),
+                end: span.end,
+            }
+        }
+        None => span,
+    }
 }
@@ -10,17 +10,54 @@
 
 extern crate pulldown_cmark;
 #[macro_use] extern crate quick_error;
+#[cfg(feature = "source")] extern crate syn;
+#[cfg(feature = "source")] extern crate proc_macro2;
+#[cfg(feature = "source")] extern crate quote;
+#[cfg(feature = "serde")] #[macro_use] extern crate serde;
 
-use pulldown_cmark::Parser;
+use pulldown_cmark::{Parser, Options, OPTION_ENABLE_TABLES, OPTION_ENABLE_FOOTNOTES};
 use pulldown_cmark::Event;
 
+/// Like `try!`, for `Option`s
+macro_rules! try_opt {
+    ($e:expr) => (match $e {
+        Some(x) => x,
+        None => return None,
+    })
+}
+
 mod types;
+mod patterns;
 mod errors;
+mod diagnostic;
+mod span;
+mod options;
 mod to_md;
 mod extractors;
+mod render;
+#[cfg(feature = "source")] mod source;
+#[cfg(feature = "source")] mod validate;
+#[cfg(feature = "source")] mod coverage;
+#[cfg(feature = "serde")] mod serialize;
 
 pub use errors::ParseError;
+pub use diagnostic::{Diagnostic, Severity, Label, Suggestion};
 pub use types::*;
+pub use patterns::{Identifier, Pattern, FieldPattern};
+pub use render::{render_md_docblock, render_md_docblock_with, render_md_toc};
+pub use options::{ParseOptions, RenderOptions, UnknownSections, SectionHeadlines, Separator};
+pub use span::{Position, Span, SpannedDocBlock, SectionSpans, ItemSpans};
+#[cfg(feature = "source")] pub use errors::{SourceError, ValidationError};
+#[cfg(feature = "source")]
+pub use source::{ItemKind, SourceItem, SourceFile, parse_rust_source, parse_rust_source_with, parse_rust_file,
+                 parse_rust_crate, parse_rust_crate_with};
+#[cfg(feature = "source")]
+pub use validate::{validate_parameters, validate_parameters_spanned, validate_generics, validate_generics_spanned,
+                   validate_returns, validate_returns_spanned, local_enums, enums_in_scope};
+#[cfg(feature = "source")]
+pub use coverage::{Coverage, CoverageSummary, FunctionCoverage};
+
+use span::SpannedEvents;
 
 use ::std::iter::Peekable;
 
@@ -35,9 +72,9 @@ use ::std::iter::Peekable;
 /// A `Result`, which is either
 ///
 /// - `Ok(DocBlock)`: A type that contains all extracted information (including
-///     all unknown sections as `Custom` sections).
+///   all unknown sections as `Custom` sections).
 /// - `Err(ParseError)`: The first encountered error while parsing the
-///     documentation string.
+///   documentation string.
 ///
 /// # Examples
 ///
@@ -56,18 +93,177 @@ use ::std::iter::Peekable;
 ///     DocBlock {
 ///         teaser: "Lorem ipsum".into(),
 ///         description: Some("Dolor sit amet.".into()),
+///         subsections: vec![],
 ///         sections: vec![
 ///             DocSection::Parameters(vec![
-///                 ("param1".into(), "Foo".into()),
-///                 ("param2".into(), "Bar".into())
-///             ])
+///                 ("param1".parse().unwrap(), "Foo".into()),
+///                 ("param2".parse().unwrap(), "Bar".into())
+///             ], vec![])
 ///         ]
 ///     }
 /// );
 /// ```
 pub fn parse_md_docblock(md: &str) -> Result<DocBlock, ParseError> {
-    let mut md_events = Parser::new(md).peekable();
-    parse_md_docblock_events(&mut md_events)
+    parse_md_docblock_spanned(md).map(|spanned| spanned.doc)
+}
+
+/// Parse documentation and extract data, keeping track of source locations
+///
+/// # Parameters
+///
+/// - `md`: Markdown string, needs to be parseable by `pulldown-cmark`
+///
+/// # Returns
+///
+/// A `Result`, which is either
+///
+/// - `Ok(SpannedDocBlock)`: The same `DocBlock` `parse_md_docblock` returns,
+///   together with the locations of the teaser, description, section
+///   headlines and list items in `md`.
+/// - `Err(ParseError)`: The first encountered error while parsing the
+///   documentation string.
+///
+/// # Examples
+///
+/// ```rust
+/// # use self::docstrings::*;
+/// let md = "Lorem ipsum\n\n# Parameters\n\n- `param1`: Foo\n";
+/// let spanned = parse_md_docblock_spanned(md).unwrap();
+///
+/// let item = &spanned.sections[0].items[0];
+/// assert_eq!(&md[item.ident.range()], "param1");
+/// assert_eq!(&md[item.docs.range()], "Foo");
+/// assert_eq!((item.docs.start.line, item.docs.start.column), (5, 13));
+/// ```
+pub fn parse_md_docblock_spanned(md: &str) -> Result<SpannedDocBlock, ParseError> {
+    let mut md_events = SpannedEvents::new(Parser::new(md), md).peekable();
+    extractors::docblock(&mut md_events, &mut extractors::Context::new(ParseOptions::default(), md))
+}
+
+/// Parse documentation and extract data, configured by `ParseOptions`
+///
+/// # Parameters
+///
+/// - `md`: Markdown string, needs to be parseable by `pulldown-cmark`
+/// - `options`: How to parse `md`, see `ParseOptions` for details
+///
+/// # Returns
+///
+/// A `Result`, which is either
+///
+/// - `Ok((DocBlock, Vec<ParseError>))`: The extracted information and all
+///   problems that did not stop parsing: Warnings as well as, in lenient
+///   mode, all errors.
+/// - `Err(ParseError)`: In strict mode, the first encountered error while
+///   parsing the documentation string.
+pub fn parse_md_docblock_with(md: &str, options: &ParseOptions) -> Result<(DocBlock, Vec<ParseError>), ParseError> {
+    parse_md_docblock_spanned_with(md, options).map(|(spanned, diagnostics)| (spanned.doc, diagnostics))
+}
+
+/// Parse documentation and extract data with source locations, configured by
+/// `ParseOptions`
+///
+/// # Parameters
+///
+/// - `md`: Markdown string, needs to be parseable by `pulldown-cmark`
+/// - `options`: How to parse `md`, see `ParseOptions` for details
+///
+/// # Returns
+///
+/// Same as `parse_md_docblock_with`, but with a `SpannedDocBlock` like the
+/// one `parse_md_docblock_spanned` returns.
+pub fn parse_md_docblock_spanned_with(md: &str, options: &ParseOptions) -> Result<(SpannedDocBlock, Vec<ParseError>), ParseError> {
+    let mut md_extensions = Options::empty();
+    if options.tables {
+        md_extensions.insert(OPTION_ENABLE_TABLES);
+    }
+    if options.footnotes {
+        md_extensions.insert(OPTION_ENABLE_FOOTNOTES);
+    }
+
+    let mut md_events = SpannedEvents::new(Parser::new_ext(md, md_extensions), md).peekable();
+    let mut ctx = extractors::Context::new(options.clone(), md);
+
+    let doc = extractors::docblock(&mut md_events, &mut ctx)?;
+    Ok((doc, ctx.diagnostics))
+}
+
+/// Parse documentation and describe all problems as `Diagnostic`s
+///
+/// This is `parse_md_docblock_spanned_with` for tools like editors, that
+/// want to show problems with stable codes and suggested fixes.
+///
+/// # Parameters
+///
+/// - `md`: Markdown string, needs to be parseable by `pulldown-cmark`
+/// - `options`: How to parse `md`, see `ParseOptions` for details
+///
+/// # Returns
+///
+/// The parsed documentation (`None` if parsing stopped at an error in strict
+/// mode) and the diagnostics, with spans relative to `md`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use self::docstrings::*;
+/// let options = ParseOptions {
+///     lenient: true,
+///     unknown_sections: UnknownSections::Warn,
+///     ..ParseOptions::default()
+/// };
+/// let md = "Lorem ipsum\n\n# Paramters\n\n- `a`: A\n";
+/// let (doc, diagnostics) = parse_md_docblock_diagnostics(md, &options);
+///
+/// assert!(doc.is_some());
+/// assert_eq!(diagnostics[0].code, "DS0003");
+/// assert_eq!(diagnostics[0].severity, Severity::Warning);
+/// assert_eq!(diagnostics[0].suggestions[0].replacement, "Parameters");
+/// ```
+pub fn parse_md_docblock_diagnostics(md: &str, options: &ParseOptions) -> (Option<SpannedDocBlock>, Vec<Diagnostic>) {
+    match parse_md_docblock_spanned_with(md, options) {
+        Ok((doc, problems)) =>
+            (Some(doc), problems.iter().map(|problem| problem.to_diagnostic_with(md, options)).collect()),
+        Err(error) => (None, vec![error.to_diagnostic_with(md, options)]),
+    }
+}
+
+/// Parse documentation and extract as much data as possible
+///
+/// Instead of stopping at the first error like `parse_md_docblock`, this skips
+/// list items it can't understand as well as unexpected Markdown and carries
+/// on.
+///
+/// # Parameters
+///
+/// - `md`: Markdown string, needs to be parseable by `pulldown-cmark`
+///
+/// # Returns
+///
+/// A best-effort `DocBlock` (without the parts that could not be parsed) and
+/// all errors encountered on the way, in the order they occurred. A missing
+/// teaser is reported as well and results in an empty `teaser`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use self::docstrings::*;
+/// let (doc, errors) = parse_md_docblock_lenient(
+///     "Lorem ipsum\n\n# Parameters\n\n- `param1`: Foo\n- param2: Bar\n- `param3` Baz\n"
+/// );
+///
+/// assert_eq!(doc.sections, vec![
+///     DocSection::Parameters(vec![("param1".parse().unwrap(), "Foo".into())], vec![])
+/// ]);
+/// assert_eq!(errors.len(), 2);
+/// ```
+pub fn parse_md_docblock_lenient(md: &str) -> (DocBlock, Vec<ParseError>) {
+    let options = ParseOptions {
+        lenient: true,
+        ..ParseOptions::default()
+    };
+
+    parse_md_docblock_with(md, &options).expect("lenient parsing does not fail")
 }
 
 /// Parse documentation and extract data
@@ -81,15 +277,13 @@ pub fn parse_md_docblock(md: &str) -> Result<DocBlock, ParseError> {
 /// A `Result`, which is either
 ///
 /// - `Ok(DocBlock)`: A type that contains all extracted information (including
-///     all unknown sections as `Custom` sections).
+///   all unknown sections as `Custom` sections).
 /// - `Err(ParseError)`: The first encountered error while parsing the
-///     documentation string.
+///   documentation string.
 pub fn parse_md_docblock_events<'a, I>(events: &mut Peekable<I>) -> Result<DocBlock, ParseError> where
     I: Iterator<Item=Event<'a>>,
 {
-    Ok(DocBlock {
-        teaser: try!(extractors::teaser(events)),
-        description: try!(extractors::description(events)),
-        sections: try!(extractors::sections(events)),
-    })
+    let mut events = events.map(|event| (event, Span::default())).peekable();
+    extractors::docblock(&mut events, &mut extractors::Context::default())
+        .map(|spanned| spanned.doc)
 }
@@ -0,0 +1,271 @@
+use ::types::SectionKind;
+
+/// What to do with sections that have an unknown headline
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum UnknownSections {
+    /// Keep them as `DocSection::Custom`
+    Keep,
+    /// Keep them as `DocSection::Custom`, but also report an
+    /// `UnknownSection` warning
+    Warn,
+    /// Treat them as an error
+    Error,
+}
+
+/// How the identifier of a list item is separated from its docs
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Separator {
+    /// `` `ident`: Docs ``, the canonical form
+    Colon,
+    /// `` `ident` - Docs ``
+    Dash,
+    /// `` `ident` — Docs `` (with an em dash)
+    EmDash,
+    /// `` `ident` `` on a line of its own, with the docs starting on the next
+    /// line
+    Newline,
+}
+
+impl Separator {
+    /// The text between identifier and docs
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Separator::Colon => ": ",
+            Separator::Dash => " - ",
+            Separator::EmDash => " \u{2014} ",
+            Separator::Newline => "\n",
+        }
+    }
+}
+
+/// Options to configure the parser
+///
+/// The `Default` is what `parse_md_docblock` uses.
+///
+/// # Examples
+///
+/// ```rust
+/// # use self::docstrings::*;
+/// let options = ParseOptions {
+///     heading_level: 2,
+///     unknown_sections: UnknownSections::Error,
+///     ..ParseOptions::default()
+/// };
+///
+/// let (doc, _) = parse_md_docblock_with(
+///     "Lorem ipsum\n\n## Parameters\n\n- `foo`: Bar", &options
+/// ).unwrap();
+///
+/// assert_eq!(doc.sections, vec![
+///     DocSection::Parameters(vec![("foo".parse().unwrap(), "Bar".into())], vec![])
+/// ]);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct ParseOptions {
+    /// Level of the headlines that start a section (`1` for `# Headline`)
+    ///
+    /// Headlines of other levels are treated as regular content.
+    pub heading_level: i32,
+    /// Collect errors and carry on instead of stopping at the first one
+    pub lenient: bool,
+    /// What to do with sections that have an unknown headline
+    pub unknown_sections: UnknownSections,
+    /// Which headlines start which kind of section
+    pub headlines: SectionHeadlines,
+    /// Whether section headlines need to match the ones in `headlines`
+    /// exactly, or if any capitalization is fine
+    pub case_sensitive: bool,
+    /// Enable the `pulldown-cmark` extension for tables
+    ///
+    /// This is also needed to write `Parameters`, `Type Parameters` and
+    /// `Returns` as tables with an identifier and a description column.
+    pub tables: bool,
+    /// Enable the `pulldown-cmark` extension for footnotes
+    pub footnotes: bool,
+    /// Separators accepted between the identifier of a list item and its
+    /// docs
+    pub separators: Vec<Separator>,
+    /// Also accept identifiers written in bold instead of as code, e.g.
+    /// `**ident**: Docs`
+    pub bold_idents: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> ParseOptions {
+        ParseOptions {
+            heading_level: 1,
+            lenient: false,
+            unknown_sections: UnknownSections::Keep,
+            headlines: SectionHeadlines::default(),
+            case_sensitive: false,
+            tables: false,
+            footnotes: false,
+            separators: vec![Separator::Colon],
+            bold_idents: false,
+        }
+    }
+}
+
+/// Options to configure `render_md_docblock_with`
+///
+/// The `Default` is what `render_md_docblock` uses.
+///
+/// # Examples
+///
+/// Docs written with other separators are normalized when rendered with the
+/// default options:
+///
+/// ```rust
+/// # use self::docstrings::*;
+/// let options = ParseOptions {
+///     separators: vec![Separator::Colon, Separator::Dash],
+///     ..ParseOptions::default()
+/// };
+/// let (doc, _) = parse_md_docblock_with(
+///     "Lorem ipsum\n\n# Parameters\n\n- `foo` - Bar", &options
+/// ).unwrap();
+///
+/// assert_eq!(render_md_docblock(&doc), "Lorem ipsum\n\n# Parameters\n\n- `foo`: Bar\n");
+///
+/// let legacy = RenderOptions { separator: Separator::Dash, ..RenderOptions::default() };
+/// assert_eq!(render_md_docblock_with(&doc, &legacy), "Lorem ipsum\n\n# Parameters\n\n- `foo` - Bar\n");
+/// ```
+///
+/// Sections keep their order unless `canonical_order` is set:
+///
+/// ```rust
+/// # use self::docstrings::*;
+/// let doc = parse_md_docblock("Lorem ipsum\n\n# Examples\n\nFoo\n\n# Panics\n\nNever\n").unwrap();
+///
+/// let sorted = RenderOptions { canonical_order: true, ..RenderOptions::default() };
+/// assert_eq!(render_md_docblock_with(&doc, &sorted), "Lorem ipsum\n\n# Panics\n\nNever\n\n# Examples\n\nFoo\n");
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct RenderOptions {
+    /// Separator written between the identifier of a list item and its docs
+    pub separator: Separator,
+    /// Write the sections in a fixed order instead of the order they are in:
+    /// `Parameters`, `Type Parameters`, `Lifetimes`, `Returns`, `Errors`,
+    /// `Panics`, `Safety`, then all custom sections in their original order,
+    /// and finally `Examples`
+    pub canonical_order: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions {
+            separator: Separator::Colon,
+            canonical_order: false,
+        }
+    }
+}
+
+/// Headlines (and aliases) of the sections that have a dedicated `DocSection`
+/// variant
+///
+/// The `Default` registry knows these headlines:
+///
+/// - `Parameters`, `Arguments`, `Args` and `Params` for
+///   `DocSection::Parameters`
+/// - `Type Parameters` and `Generics` for `DocSection::TypeParameters`
+/// - `Lifetime Parameters` and `Lifetimes` for
+///   `DocSection::LifetimeParameters`
+/// - `Returns`, `Return value` and `Return` for `DocSection::Returns`
+/// - `Examples` and `Example` for `DocSection::Examples`
+/// - `Panics`, `Errors` and `Safety` for the sections of the same name
+///
+/// # Examples
+///
+/// ```rust
+/// # use self::docstrings::*;
+/// let mut options = ParseOptions::default();
+/// options.headlines.insert("Inputs", SectionKind::Parameters);
+/// options.headlines.remove("Args");
+///
+/// assert_eq!(options.headlines.kind("inputs", false), Some(SectionKind::Parameters));
+/// assert_eq!(options.headlines.kind("Args", false), None);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct SectionHeadlines {
+    headlines: Vec<(String, SectionKind)>,
+}
+
+impl SectionHeadlines {
+    /// A registry that knows no headlines at all
+    pub fn empty() -> SectionHeadlines {
+        SectionHeadlines {
+            headlines: vec![],
+        }
+    }
+
+    /// Treat sections with the given headline as `kind`, replacing any
+    /// previous kind of that headline
+    pub fn insert(&mut self, headline: &str, kind: SectionKind) {
+        self.remove(headline);
+        self.headlines.push((headline.trim().into(), kind));
+    }
+
+    /// Forget about a headline, regardless of capitalization
+    pub fn remove(&mut self, headline: &str) {
+        let headline = headline.trim().to_lowercase();
+        self.headlines.retain(|(known, _)| known.to_lowercase() != headline);
+    }
+
+    /// Which kind of section starts with this headline
+    ///
+    /// # Parameters
+    ///
+    /// - `headline`: The headline, surrounding whitespace is ignored
+    /// - `case_sensitive`: Whether capitalization needs to match as well
+    pub fn kind(&self, headline: &str, case_sensitive: bool) -> Option<SectionKind> {
+        let headline = headline.trim();
+
+        self.headlines.iter()
+            .find(|&(known, _)| if case_sensitive {
+                known == headline
+            } else {
+                known.to_lowercase() == headline.to_lowercase()
+            })
+            .map(|&(_, kind)| kind)
+    }
+
+    /// All known headlines of the given kind
+    pub fn headlines(&self, kind: SectionKind) -> Vec<&str> {
+        self.headlines.iter()
+            .filter(|&&(_, k)| k == kind)
+            .map(|(headline, _)| &headline[..])
+            .collect()
+    }
+
+    /// All known headlines, of any kind
+    pub fn all(&self) -> Vec<&str> {
+        self.headlines.iter().map(|(headline, _)| &headline[..]).collect()
+    }
+}
+
+impl Default for SectionHeadlines {
+    fn default() -> SectionHeadlines {
+        let mut headlines = SectionHeadlines::empty();
+
+        for headline in &["Parameters", "Arguments", "Args", "Params"] {
+            headlines.insert(headline, SectionKind::Parameters);
+        }
+        for headline in &["Type Parameters", "Generics"] {
+            headlines.insert(headline, SectionKind::TypeParameters);
+        }
+        for headline in &["Lifetime Parameters", "Lifetimes"] {
+            headlines.insert(headline, SectionKind::LifetimeParameters);
+        }
+        for headline in &["Returns", "Return value", "Return"] {
+            headlines.insert(headline, SectionKind::Returns);
+        }
+        for headline in &["Examples", "Example"] {
+            headlines.insert(headline, SectionKind::Examples);
+        }
+        headlines.insert("Panics", SectionKind::Panics);
+        headlines.insert("Errors", SectionKind::Errors);
+        headlines.insert("Safety", SectionKind::Safety);
+
+        headlines
+    }
+}
@@ -0,0 +1,500 @@
+use ::std::fmt;
+use ::std::ops::Deref;
+use ::std::str::FromStr;
+
+use ::errors::ParseError;
+use ::span::Position;
+
+/// A Rust identifier or lifetime
+///
+/// Raw identifiers (`r#type`), keywords (`self`) and lifetimes (`'a`) are all
+/// valid identifiers.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, PartialOrd, Ord)]
+pub struct Identifier(String);
+
+impl Identifier {
+    /// Check that `ident` is a valid identifier
+    ///
+    /// # Parameters
+    ///
+    /// - `ident`: The identifier as written, surrounding whitespace is ignored
+    ///
+    /// # Returns
+    ///
+    /// A `Result`, which is either
+    ///
+    /// - `Ok(Identifier)`: The identifier
+    /// - `Err(ParseError::InvalidIdent)`: `ident` is not an identifier. The
+    ///   error has neither section nor position.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use self::docstrings::*;
+    /// let ident = Identifier::new("r#type").unwrap();
+    /// assert_eq!(ident.name(), "type");
+    ///
+    /// assert!(Identifier::new("'a").unwrap().is_lifetime());
+    /// assert!(Identifier::new("foo bar").is_err());
+    /// ```
+    pub fn new(ident: &str) -> Result<Identifier, ParseError> {
+        let ident = ident.trim();
+        if parse::is_identifier(ident) {
+            Ok(Identifier(ident.into()))
+        } else {
+            Err(ParseError::InvalidIdent(String::new(), ident.into(), Position::default()))
+        }
+    }
+
+    /// The identifier as written
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The identifier without `r#` or `'`
+    pub fn name(&self) -> &str {
+        self.0.trim_start_matches("r#").trim_start_matches('\'')
+    }
+
+    /// Whether this is a raw identifier like `r#type`
+    pub fn is_raw(&self) -> bool {
+        self.0.starts_with("r#")
+    }
+
+    /// Whether this is a lifetime like `'a`
+    pub fn is_lifetime(&self) -> bool {
+        self.0.starts_with('\'')
+    }
+}
+
+impl FromStr for Identifier {
+    type Err = ParseError;
+
+    fn from_str(ident: &str) -> Result<Identifier, ParseError> {
+        Identifier::new(ident)
+    }
+}
+
+impl Deref for Identifier {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Identifier {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'a> PartialEq<&'a str> for Identifier {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A Rust pattern, as used in pattern matching and function parameters
+///
+/// `Display` writes the pattern in a canonical form, e.g. `Foo { a, b: c, .. }`
+/// no matter how it was spaced.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum Pattern {
+    /// A binding, e.g. `x`, `mut x`, `ref x`, `self`, `r#type`, `x @ 1..=5`
+    ///
+    /// Single identifiers like `None` are bindings as well, Rust only knows
+    /// whether they are unit variants once it resolves the name.
+    Ident {
+        /// Whether the binding is declared with `ref`
+        by_ref: bool,
+        /// Whether the binding is declared with `mut`
+        mutable: bool,
+        /// The name
+        ident: Identifier,
+        /// Pattern after `@`
+        subpattern: Option<Box<Pattern>>,
+    },
+    /// The wildcard `_`
+    Wild,
+    /// `..` in a tuple or slice
+    Rest,
+    /// A path with at least two segments, e.g. `Foo::Bar` or `::std::f64::NAN`
+    Path(String),
+    /// A tuple struct or tuple variant, e.g. `Some(x)`
+    TupleStruct(String, Vec<Pattern>),
+    /// A struct or struct variant, e.g. `Foo { a, b: c, .. }`
+    Struct(String, Vec<FieldPattern>, bool),
+    /// A tuple, e.g. `(a, b)`
+    Tuple(Vec<Pattern>),
+    /// A slice, e.g. `[first, ..]`
+    Slice(Vec<Pattern>),
+    /// A reference, e.g. `&x` or `&mut x`
+    Reference(bool, Box<Pattern>),
+    /// Alternatives, e.g. `Some(_) | None`
+    Or(Vec<Pattern>),
+    /// A literal or range, e.g. `42`, `"foo"` or `'a'..='z'`, as written
+    /// without whitespace
+    Literal(String),
+    /// Anything else that looks like Rust but can't be taken apart here, as
+    /// written
+    ///
+    /// Docs often write types where patterns go, like `Ok(Vec<u8>)`, and
+    /// patterns may use syntax like macros or qualified paths.
+    Raw(String),
+}
+
+/// A field in a struct pattern, e.g. `b: c` in `Foo { a, b: c }`
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct FieldPattern {
+    /// Name of the field, or its index for tuple structs
+    pub member: String,
+    /// The pattern the field is matched against. For shorthands like `a` or
+    /// `ref mut a`, this is the binding.
+    pub pattern: Pattern,
+    /// Whether the field is written as shorthand, without `: pattern`
+    pub shorthand: bool,
+}
+
+impl Pattern {
+    /// Parse a pattern with `syn`, which needs the `source` feature
+    ///
+    /// Identifiers `syn` doesn't take as a pattern, like the keyword `type`,
+    /// are bindings as well. Without the `source` feature, identifiers are
+    /// the only patterns that are taken apart. Anything else `syn` can't parse or that uses
+    /// syntax not modelled by `Pattern` is kept as `Pattern::Raw`, as long as
+    /// its brackets are balanced and it isn't cut off after an operator.
+    ///
+    /// # Parameters
+    ///
+    /// - `pattern`: The pattern as written, surrounding whitespace is ignored
+    ///
+    /// # Returns
+    ///
+    /// A `Result`, which is either
+    ///
+    /// - `Ok(Pattern)`: The parsed pattern
+    /// - `Err(ParseError::InvalidPattern)`: `pattern` is not a pattern, not
+    ///   even a raw one. The error has neither section nor position.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use self::docstrings::*;
+    /// assert_eq!(Pattern::new("r#type").unwrap().binding().unwrap().name(), "type");
+    /// assert_eq!(Pattern::new("Ok(Vec<u8>)").unwrap(), Pattern::Raw("Ok(Vec<u8>)".into()));
+    /// assert!(Pattern::new("Some(").is_err());
+    /// ```
+    ///
+    /// With the `source` feature:
+    ///
+    /// ```rust
+    /// # use self::docstrings::*;
+    /// # #[cfg(feature = "source")] {
+    /// let pattern = Pattern::new("Foo{a,ref mut b , ..}").unwrap();
+    /// assert_eq!(pattern.to_string(), "Foo { a, ref mut b, .. }");
+    ///
+    /// assert_eq!(Pattern::new("mut x").unwrap().binding().unwrap().as_str(), "x");
+    /// # }
+    /// ```
+    pub fn new(pattern: &str) -> Result<Pattern, ParseError> {
+        let pattern = pattern.trim();
+        if let Some(pattern) = parse::pattern(pattern) {
+            return Ok(pattern);
+        }
+
+        // Docs may call `r#type` just `type`
+        if let Ok(ident) = Identifier::new(pattern) {
+            return Ok(Pattern::Ident { by_ref: false, mutable: false, ident, subpattern: None });
+        }
+
+        if is_raw(pattern) {
+            Ok(Pattern::Raw(pattern.into()))
+        } else {
+            Err(ParseError::InvalidPattern(String::new(), pattern.into(), Position::default()))
+        }
+    }
+
+    /// The identifier bound by a simple binding like `x` or `mut x`, `None`
+    /// for all other patterns
+    pub fn binding(&self) -> Option<&Identifier> {
+        match *self {
+            Pattern::Ident { ref ident, subpattern: None, .. } => Some(ident),
+            _ => None,
+        }
+    }
+
+    /// The path of the variant (or struct) this pattern matches, e.g.
+    /// `Some` for `Some(x)` or `Foo::Bar` for `Foo::Bar { .. }`
+    ///
+    /// For bindings like `None`, this is the identifier. For raw patterns,
+    /// it is the path they start with, if that is all there is or it is
+    /// followed by parentheses or braces, e.g. `Ok` for `Ok(Vec<u8>)`.
+    pub fn path(&self) -> Option<&str> {
+        match *self {
+            Pattern::Ident { ref ident, subpattern: None, .. } => Some(ident),
+            Pattern::Path(ref path) |
+            Pattern::TupleStruct(ref path, _) |
+            Pattern::Struct(ref path, _, _) => Some(path),
+            Pattern::Raw(ref raw) => {
+                let end = raw.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':')).unwrap_or(raw.len());
+                let (path, rest) = raw.split_at(end);
+                let rest = rest.trim_start();
+                let is_path = path.trim_start_matches("::").split("::").all(parse::is_identifier);
+                if is_path && (rest.is_empty() || rest.starts_with('(') || rest.starts_with('{')) {
+                    Some(path)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = ParseError;
+
+    fn from_str(pattern: &str) -> Result<Pattern, ParseError> {
+        Pattern::new(pattern)
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Pattern::Ident { by_ref, mutable, ref ident, ref subpattern } => {
+                if by_ref {
+                    f.write_str("ref ")?;
+                }
+                if mutable {
+                    f.write_str("mut ")?;
+                }
+                f.write_str(ident)?;
+                if let Some(ref subpattern) = *subpattern {
+                    write!(f, " @ {}", subpattern)?;
+                }
+                Ok(())
+            }
+            Pattern::Wild => f.write_str("_"),
+            Pattern::Rest => f.write_str(".."),
+            Pattern::Path(ref path) |
+            Pattern::Literal(ref path) |
+            Pattern::Raw(ref path) => f.write_str(path),
+            Pattern::TupleStruct(ref path, ref elems) => write!(f, "{}({})", path, join(elems, ", ")),
+            Pattern::Struct(ref path, ref fields, rest) => {
+                let mut fields = fields.iter().map(|field| field.to_string()).collect::<Vec<_>>();
+                if rest {
+                    fields.push("..".into());
+                }
+                if fields.is_empty() {
+                    write!(f, "{} {{}}", path)
+                } else {
+                    write!(f, "{} {{ {} }}", path, fields.join(", "))
+                }
+            }
+            Pattern::Tuple(ref elems) if elems.len() == 1 => write!(f, "({},)", elems[0]),
+            Pattern::Tuple(ref elems) => write!(f, "({})", join(elems, ", ")),
+            Pattern::Slice(ref elems) => write!(f, "[{}]", join(elems, ", ")),
+            Pattern::Reference(mutable, ref pattern) => {
+                write!(f, "&{}{}", if mutable { "mut " } else { "" }, pattern)
+            }
+            Pattern::Or(ref cases) => f.write_str(&join(cases, " | ")),
+        }
+    }
+}
+
+impl fmt::Display for FieldPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.shorthand {
+            write!(f, "{}", self.pattern)
+        } else {
+            write!(f, "{}: {}", self.member, self.pattern)
+        }
+    }
+}
+
+/// Whether `text` could be Rust syntax: Its brackets are balanced, and it is
+/// neither empty nor cut off after an operator (like `1 +` or `Some(1 +)`)
+fn is_raw(text: &str) -> bool {
+    let cut_off = |previous: char| "+-*/%^!&|=:".contains(previous);
+    let mut open = vec![];
+    let mut previous = ' ';
+
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        let expected = match c {
+            '(' | '[' | '{' => {
+                open.push(c);
+                None
+            }
+            ')' => Some('('),
+            ']' => Some('['),
+            '}' => Some('{'),
+            _ => None,
+        };
+        if expected.is_some() && (open.pop() != expected || cut_off(previous)) {
+            return false;
+        }
+        previous = c;
+    }
+
+    open.is_empty() && !text.is_empty() && !cut_off(previous) && previous != '`'
+}
+
+fn join(patterns: &[Pattern], separator: &str) -> String {
+    patterns.iter().map(|pattern| pattern.to_string()).collect::<Vec<_>>().join(separator)
+}
+
+/// Without `syn`, only identifiers are recognized: All other patterns are
+/// `Pattern::Raw`
+#[cfg(not(feature = "source"))]
+mod parse {
+    use super::Pattern;
+
+    pub fn is_identifier(ident: &str) -> bool {
+        let word = if let Some(lifetime) = ident.strip_prefix('\'') {
+            lifetime
+        } else if let Some(raw) = ident.strip_prefix("r#") {
+            if ["crate", "self", "super", "Self"].contains(&raw) {
+                return false;
+            }
+            raw
+        } else {
+            ident
+        };
+
+        let mut chars = word.chars();
+        match chars.next() {
+            Some(c) if c.is_alphabetic() || c == '_' => {}
+            _ => return false,
+        }
+        word != "_" && chars.all(|c| c.is_alphanumeric() || c == '_')
+    }
+
+    pub fn pattern(_: &str) -> Option<Pattern> {
+        None
+    }
+}
+
+#[cfg(feature = "source")]
+mod parse {
+    use ::proc_macro2::{Delimiter, TokenStream, TokenTree};
+    use ::quote::ToTokens;
+    use ::syn;
+    use ::syn::ext::IdentExt;
+    use ::syn::parse::Parser;
+
+    use super::{Identifier, Pattern, FieldPattern};
+
+    pub fn is_identifier(ident: &str) -> bool {
+        if ident.starts_with('\'') {
+            syn::parse_str::<syn::Lifetime>(ident).is_ok()
+        } else {
+            syn::Ident::parse_any.parse_str(ident).is_ok() && ident != "_"
+        }
+    }
+
+    pub fn pattern(pattern: &str) -> Option<Pattern> {
+        syn::Pat::parse_multi_with_leading_vert.parse_str(pattern).ok()
+            .and_then(|pat| from_syn(&pat))
+    }
+
+    /// Convert a pattern parsed by `syn`, `None` if it uses syntax that is not
+    /// supported
+    pub fn from_syn(pat: &syn::Pat) -> Option<Pattern> {
+        match *pat {
+            syn::Pat::Ident(ref pat) => {
+                let subpattern = match pat.subpat {
+                    Some((_, ref subpattern)) => Some(Box::new(try_opt!(from_syn(subpattern)))),
+                    None => None,
+                };
+                Some(Pattern::Ident {
+                    by_ref: pat.by_ref.is_some(),
+                    mutable: pat.mutability.is_some(),
+                    ident: Identifier(pat.ident.to_string()),
+                    subpattern,
+                })
+            }
+            syn::Pat::Wild(_) => Some(Pattern::Wild),
+            syn::Pat::Rest(_) => Some(Pattern::Rest),
+            syn::Pat::Path(ref path) if path.qself.is_none() => {
+                Some(Pattern::Path(try_opt!(path_string(&path.path))))
+            }
+            syn::Pat::TupleStruct(ref tuple) if tuple.qself.is_none() => {
+                Some(Pattern::TupleStruct(try_opt!(path_string(&tuple.path)), try_opt!(list(&tuple.elems))))
+            }
+            syn::Pat::Struct(ref strukt) if strukt.qself.is_none() => {
+                let mut fields = vec![];
+                for field in &strukt.fields {
+                    fields.push(FieldPattern {
+                        member: match field.member {
+                            syn::Member::Named(ref ident) => ident.unraw().to_string(),
+                            syn::Member::Unnamed(ref index) => index.index.to_string(),
+                        },
+                        pattern: try_opt!(from_syn(&field.pat)),
+                        shorthand: field.colon_token.is_none(),
+                    });
+                }
+                Some(Pattern::Struct(try_opt!(path_string(&strukt.path)), fields, strukt.rest.is_some()))
+            }
+            syn::Pat::Tuple(ref tuple) => Some(Pattern::Tuple(try_opt!(list(&tuple.elems)))),
+            syn::Pat::Slice(ref slice) => Some(Pattern::Slice(try_opt!(list(&slice.elems)))),
+            syn::Pat::Paren(ref paren) => from_syn(&paren.pat),
+            syn::Pat::Reference(ref reference) => {
+                Some(Pattern::Reference(reference.mutability.is_some(), Box::new(try_opt!(from_syn(&reference.pat)))))
+            }
+            syn::Pat::Or(ref or) => Some(Pattern::Or(try_opt!(list(&or.cases)))),
+            syn::Pat::Lit(_) | syn::Pat::Range(_) => Some(Pattern::Literal(compact(pat.to_token_stream()))),
+            _ => None,
+        }
+    }
+
+    fn list<'a, I: IntoIterator<Item = &'a syn::Pat>>(pats: I) -> Option<Vec<Pattern>> {
+        let mut patterns = vec![];
+        for pat in pats {
+            patterns.push(try_opt!(from_syn(pat)));
+        }
+        Some(patterns)
+    }
+
+    /// A path without generic arguments, e.g. `Foo::Bar`
+    fn path_string(path: &syn::Path) -> Option<String> {
+        let mut segments = vec![];
+        for segment in &path.segments {
+            if !segment.arguments.is_empty() {
+                return None;
+            }
+            segments.push(segment.ident.to_string());
+        }
+
+        let leading = if path.leading_colon.is_some() { "::" } else { "" };
+        Some(format!("{}{}", leading, segments.join("::")))
+    }
+
+    /// Tokens written without whitespace between them
+    fn compact(tokens: TokenStream) -> String {
+        tokens.into_iter()
+            .map(|token| match token {
+                TokenTree::Group(group) => {
+                    let (open, close) = match group.delimiter() {
+                        Delimiter::Parenthesis => ("(", ")"),
+                        Delimiter::Bracket => ("[", "]"),
+                        Delimiter::Brace => ("{", "}"),
+                        Delimiter::None => ("", ""),
+                    };
+                    format!("{}{}{}", open, compact(group.stream()), close)
+                }
+                token => token.to_string(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "source")]
+pub use self::parse::from_syn;
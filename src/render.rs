@@ -0,0 +1,324 @@
+use ::std::fmt;
+
+use ::types::*;
+use ::options::RenderOptions;
+use ::extractors::looks_like_type;
+
+/// Render a `DocBlock` as canonical Markdown
+///
+/// Sections are written in the order they are in, with `-` bullets and list
+/// items written like `` `ident`: Docs ``. Continuation lines of list items
+/// are indented by four spaces. See `RenderOptions::canonical_order` to sort
+/// the sections instead.
+///
+/// # Parameters
+///
+/// - `doc`: The information to render
+///
+/// # Returns
+///
+/// A Markdown `String` that `parse_md_docblock` turns back into `doc`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use self::docstrings::*;
+/// let doc = DocBlock {
+///     teaser: "Lorem ipsum".into(),
+///     description: None,
+///     subsections: vec![],
+///     sections: vec![
+///         DocSection::Returns("Nothing".into(), vec![], vec![]),
+///         DocSection::Parameters(vec![("foo".parse().unwrap(), "Bar".into())], vec![]),
+///     ],
+/// };
+///
+/// let md = render_md_docblock(&doc);
+/// assert_eq!(md, "Lorem ipsum\n\n# Returns\n\nNothing\n\n# Parameters\n\n- `foo`: Bar\n");
+/// assert_eq!(parse_md_docblock(&md).unwrap(), doc);
+/// ```
+pub fn render_md_docblock(doc: &DocBlock) -> String {
+    render_md_docblock_with(doc, &RenderOptions::default())
+}
+
+/// Render a `DocBlock` as Markdown, configured by `RenderOptions`
+///
+/// This works like `render_md_docblock`, but writes list items with the
+/// separator given in `options`, and sorts the sections if `options` asks for
+/// it.
+///
+/// # Parameters
+///
+/// - `doc`: The information to render
+/// - `options`: How to render it
+pub fn render_md_docblock_with(doc: &DocBlock, options: &RenderOptions) -> String {
+    let mut blocks = vec![doc.teaser.clone()];
+
+    if let Some(ref description) = doc.description {
+        blocks.push(description.clone());
+    }
+    subsection_blocks(&mut blocks, &doc.subsections, 2, options);
+
+    let sections = if options.canonical_order {
+        sorted(&doc.sections)
+    } else {
+        doc.sections.iter().collect()
+    };
+    for section in sections {
+        blocks.push(format!("# {}", headline(section)));
+        blocks.extend(section_blocks(section, options).into_iter().filter(|block| !block.is_empty()));
+        subsection_blocks(&mut blocks, section.subsections(), 2, options);
+    }
+
+    let mut md = blocks.join("\n\n");
+    md.push('\n');
+    md
+}
+
+/// Render the headlines of a `DocBlock` as a table of contents
+///
+/// The table of contents is a nested Markdown list, with sections in their
+/// order, and the subsections of each section (or of the description) nested
+/// below it.
+///
+/// # Parameters
+///
+/// - `doc`: The information whose headlines to list
+///
+/// # Returns
+///
+/// A Markdown list of headlines, or an empty `String` if `doc` has no
+/// sections at all.
+///
+/// # Examples
+///
+/// ```rust
+/// # use self::docstrings::*;
+/// let doc = parse_md_docblock(
+///     "Lorem ipsum\n\n# Examples\n\n## Basic usage\n\nFoo\n\n## Advanced\n\nBar\n"
+/// ).unwrap();
+///
+/// assert_eq!(render_md_toc(&doc), "- Examples\n    - Basic usage\n    - Advanced\n");
+/// ```
+pub fn render_md_toc(doc: &DocBlock) -> String {
+    let mut lines = vec![];
+    toc_lines(&mut lines, &doc.subsections, 0);
+
+    for section in &doc.sections {
+        lines.push(format!("- {}", headline(section)));
+        toc_lines(&mut lines, section.subsections(), 1);
+    }
+
+    lines.into_iter().map(|line| line + "\n").collect()
+}
+
+fn toc_lines(lines: &mut Vec<String>, subsections: &[Subsection], depth: usize) {
+    for (headline, section) in subsections {
+        lines.push(format!("{}- {}", "    ".repeat(depth), headline));
+        toc_lines(lines, section.subsections(), depth + 1);
+    }
+}
+
+impl fmt::Display for DocBlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&render_md_docblock(self))
+    }
+}
+
+/// Sections in the canonical order, see `RenderOptions::canonical_order`
+fn sorted(sections: &[DocSection]) -> Vec<&DocSection> {
+    let mut sections: Vec<&DocSection> = sections.iter().collect();
+    sections.sort_by_key(|section| rank(section));
+    sections
+}
+
+/// Headlines and blocks of subsections, with headlines of the given level
+fn subsection_blocks(blocks: &mut Vec<String>, subsections: &[Subsection], level: usize, options: &RenderOptions) {
+    for (headline, section) in subsections {
+        blocks.push(format!("{} {}", "#".repeat(level), headline));
+        blocks.extend(section_blocks(section, options).into_iter().filter(|block| !block.is_empty()));
+        subsection_blocks(blocks, section.subsections(), level + 1, options);
+    }
+}
+
+/// Position of a section in the canonical order
+fn rank(section: &DocSection) -> u8 {
+    match *section {
+        DocSection::Parameters(..) => 0,
+        DocSection::TypeParameters(..) => 1,
+        DocSection::LifetimeParameters(..) => 2,
+        DocSection::Returns(..) => 3,
+        DocSection::Errors(..) => 4,
+        DocSection::Panics(..) => 5,
+        DocSection::Safety(..) => 6,
+        DocSection::Custom(..) => 7,
+        DocSection::Examples(..) => 8,
+    }
+}
+
+fn headline(section: &DocSection) -> &str {
+    match *section {
+        DocSection::Parameters(..) => "Parameters",
+        DocSection::TypeParameters(..) => "Type Parameters",
+        DocSection::LifetimeParameters(..) => "Lifetimes",
+        DocSection::Returns(..) => "Returns",
+        DocSection::Errors(..) => "Errors",
+        DocSection::Panics(..) => "Panics",
+        DocSection::Safety(..) => "Safety",
+        DocSection::Custom(ref headline, ..) => headline,
+        DocSection::Examples(..) => "Examples",
+    }
+}
+
+/// The blocks (paragraphs, lists, code blocks) making up a section, without
+/// its headline
+fn section_blocks(section: &DocSection, options: &RenderOptions) -> Vec<String> {
+    match *section {
+        DocSection::Parameters(ref list, _) => {
+            let items = list.iter()
+                .map(|(pattern, doc)| annotated_item(&pattern.to_string(), &annotation(doc), &parameter_docs(doc, options), options))
+                .collect::<Vec<_>>();
+            vec![items.join("\n")]
+        }
+        DocSection::TypeParameters(ref list, _) |
+        DocSection::LifetimeParameters(ref list, _) => vec![ident_list(list, options)],
+        DocSection::Returns(ref text, ref list, _) => vec![text.clone(), ident_list(list, options)],
+        DocSection::Errors(ref text, ref list, _) => {
+            let items = list.iter()
+                .map(|(pattern, docs)| match *pattern {
+                    Some(ref pattern) => ident_item(&pattern.to_string(), docs, options),
+                    None => item(docs),
+                })
+                .collect::<Vec<_>>();
+            vec![text.clone(), items.join("\n")]
+        }
+        DocSection::Panics(ref text, ref list, _) => {
+            let items = list.iter().map(|docs| item(docs)).collect::<Vec<_>>();
+            vec![text.clone(), items.join("\n")]
+        }
+        DocSection::Safety(ref docs, _) |
+        DocSection::Custom(_, ref docs, _) => vec![docs.clone()],
+        DocSection::Examples(ref examples, _) => {
+            let mut blocks = vec![];
+            for example in examples {
+                blocks.push(example.description.clone());
+                blocks.extend(example.code_blocks.iter().map(code_block));
+            }
+            blocks
+        }
+    }
+}
+
+fn ident_list<T: fmt::Display>(list: &[(T, Documentation)], options: &RenderOptions) -> String {
+    list.iter()
+        .map(|(ident, docs)| ident_item(&ident.to_string(), docs, options))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The annotation of a parameter, with a leading space, or nothing
+fn annotation(doc: &ParameterDoc) -> String {
+    if !doc.is_annotated() {
+        return String::new();
+    }
+
+    let mut parts = vec![];
+    match doc.ty {
+        Some(ref ty) if looks_like_type(ty) => parts.push(ty.clone()),
+        Some(ref ty) => parts.push(format!("`{}`", ty)),
+        // Keep the first constraint from being read as the type
+        None if !doc.optional && doc.default.is_none() &&
+            doc.constraints.first().map(|constraint| looks_like_type(constraint)).unwrap_or(false) =>
+            parts.push("required".to_string()),
+        None => {}
+    }
+    if doc.optional {
+        parts.push("optional".to_string());
+    }
+    parts.extend(doc.default.iter().map(|default| format!("default: {}", default)));
+    parts.extend(doc.constraints.iter().cloned());
+
+    format!(" ({})", parts.join(", "))
+}
+
+/// Docs of a parameter, followed by the nested list of its fields
+fn parameter_docs(doc: &ParameterDoc, options: &RenderOptions) -> String {
+    if doc.fields.is_empty() {
+        doc.docs.clone()
+    } else {
+        format!("{}\n{}", doc.docs, ident_list(&doc.fields, options))
+    }
+}
+
+fn ident_item(ident: &str, docs: &str, options: &RenderOptions) -> String {
+    annotated_item(ident, "", docs, options)
+}
+
+fn annotated_item(ident: &str, annotation: &str, docs: &str, options: &RenderOptions) -> String {
+    item(&format!("`{}`{}{}{}", ident, annotation, options.separator.as_str(), docs))
+}
+
+/// A bullet point, with continuation lines indented
+fn item(text: &str) -> String {
+    let mut lines = text.lines();
+    let mut item = format!("- {}", lines.next().unwrap_or(""));
+
+    for line in lines {
+        item.push('\n');
+        if !line.is_empty() {
+            item.push_str("    ");
+            item.push_str(line);
+        }
+    }
+
+    item
+}
+
+fn code_block(block: &CodeBlock) -> String {
+    let info = block.language.iter()
+        .chain(block.attributes.iter())
+        .map(|s| &s[..])
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let lines = block.lines.iter()
+        .map(|line| match *line {
+            CodeLine::Hidden(ref line) => {
+                // The marker goes after the indentation
+                let code = line.trim_start();
+                let indent = &line[..line.len() - code.len()];
+                if code.is_empty() {
+                    format!("{}#", indent)
+                } else {
+                    format!("{}# {}", indent, code)
+                }
+            }
+            CodeLine::Visible(ref line) if block.is_rust() && needs_escape(line) => {
+                line.replacen("#", "##", 1)
+            }
+            CodeLine::Visible(ref line) => line.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    // The fence needs to be longer than any run of backticks in the code
+    let longest_run = lines.iter()
+        .flat_map(|line| line.split(|c| c != '`'))
+        .map(|run| run.len())
+        .max()
+        .unwrap_or(0);
+    let fence = std::iter::repeat_n("`", ::std::cmp::max(3, longest_run + 1)).collect::<String>();
+
+    let mut md = format!("{}{}\n", fence, info);
+    for line in lines {
+        md.push_str(&line);
+        md.push('\n');
+    }
+    md.push_str(&fence);
+    md
+}
+
+/// Would rustdoc treat this visible line as hidden (or unescape it)?
+fn needs_escape(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed == "#" || trimmed.starts_with("# ") || trimmed.starts_with("##")
+}
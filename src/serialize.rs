@@ -0,0 +1,252 @@
+//! Serde support, see the docs of `DocSection` for the resulting shape
+//!
+//! Types with named fields simply derive `Serialize` and `Deserialize`. Tuples
+//! (like the lists of sections) are converted to the structs in this module,
+//! and identifiers and patterns are written as strings.
+
+use ::std::fmt;
+use ::std::str::FromStr;
+
+use ::serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use ::types::*;
+use ::patterns::{Identifier, Pattern};
+
+/// Serialize as a string using `Display`
+fn serialize_display<T: fmt::Display, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+/// Deserialize from a string using `FromStr`
+fn deserialize_from_str<'de, T, D>(deserializer: D) -> Result<T, D::Error> where
+    T: FromStr,
+    T::Err: fmt::Display,
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    s.parse().map_err(de::Error::custom)
+}
+
+impl Serialize for Identifier {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_display(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Identifier {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Identifier, D::Error> {
+        deserialize_from_str(deserializer)
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_display(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Pattern, D::Error> {
+        deserialize_from_str(deserializer)
+    }
+}
+
+/// An item of a list mapping identifiers to docs
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NamedItem {
+    name: Identifier,
+    docs: Documentation,
+}
+
+/// An item of a list mapping patterns to docs
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PatternItem<P> {
+    pattern: P,
+    docs: Documentation,
+}
+
+/// A documented parameter
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParameterItem {
+    pattern: Pattern,
+    #[serde(flatten)]
+    doc: ParameterDoc,
+}
+
+/// A subsection
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubsectionItem {
+    headline: SectionHeadline,
+    section: DocSection,
+}
+
+/// A `DocSection`, with its kind as tag and named fields
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SectionRepr {
+    Parameters {
+        parameters: Vec<ParameterItem>,
+        subsections: Vec<SubsectionItem>,
+    },
+    TypeParameters {
+        parameters: Vec<NamedItem>,
+        subsections: Vec<SubsectionItem>,
+    },
+    LifetimeParameters {
+        parameters: Vec<NamedItem>,
+        subsections: Vec<SubsectionItem>,
+    },
+    Returns {
+        docs: Documentation,
+        variants: Vec<PatternItem<Pattern>>,
+        subsections: Vec<SubsectionItem>,
+    },
+    Examples {
+        examples: Vec<Example>,
+        subsections: Vec<SubsectionItem>,
+    },
+    Panics {
+        docs: Documentation,
+        conditions: Vec<Documentation>,
+        subsections: Vec<SubsectionItem>,
+    },
+    Errors {
+        docs: Documentation,
+        conditions: Vec<PatternItem<Option<Pattern>>>,
+        subsections: Vec<SubsectionItem>,
+    },
+    Safety {
+        docs: Documentation,
+        subsections: Vec<SubsectionItem>,
+    },
+    Custom {
+        headline: SectionHeadline,
+        docs: Documentation,
+        subsections: Vec<SubsectionItem>,
+    },
+}
+
+fn named_items(list: Vec<(Identifier, Documentation)>) -> Vec<NamedItem> {
+    list.into_iter().map(|(name, docs)| NamedItem { name, docs }).collect()
+}
+
+fn from_named_items(list: Vec<NamedItem>) -> Vec<(Identifier, Documentation)> {
+    list.into_iter().map(|item| (item.name, item.docs)).collect()
+}
+
+fn pattern_items<P>(list: Vec<(P, Documentation)>) -> Vec<PatternItem<P>> {
+    list.into_iter().map(|(pattern, docs)| PatternItem { pattern, docs }).collect()
+}
+
+fn from_pattern_items<P>(list: Vec<PatternItem<P>>) -> Vec<(P, Documentation)> {
+    list.into_iter().map(|item| (item.pattern, item.docs)).collect()
+}
+
+fn subsection_items(list: Vec<Subsection>) -> Vec<SubsectionItem> {
+    list.into_iter().map(|(headline, section)| SubsectionItem { headline, section }).collect()
+}
+
+fn from_subsection_items(list: Vec<SubsectionItem>) -> Vec<Subsection> {
+    list.into_iter().map(|item| (item.headline, item.section)).collect()
+}
+
+impl From<DocSection> for SectionRepr {
+    fn from(section: DocSection) -> SectionRepr {
+        match section {
+            DocSection::Parameters(list, subsections) => SectionRepr::Parameters {
+                parameters: list.into_iter()
+                    .map(|(pattern, doc)| ParameterItem { pattern, doc })
+                    .collect(),
+                subsections: subsection_items(subsections),
+            },
+            DocSection::TypeParameters(list, subsections) => SectionRepr::TypeParameters {
+                parameters: named_items(list),
+                subsections: subsection_items(subsections),
+            },
+            DocSection::LifetimeParameters(list, subsections) => SectionRepr::LifetimeParameters {
+                parameters: named_items(list),
+                subsections: subsection_items(subsections),
+            },
+            DocSection::Returns(docs, list, subsections) => SectionRepr::Returns {
+                docs,
+                variants: pattern_items(list),
+                subsections: subsection_items(subsections),
+            },
+            DocSection::Examples(examples, subsections) => SectionRepr::Examples {
+                examples,
+                subsections: subsection_items(subsections),
+            },
+            DocSection::Panics(docs, conditions, subsections) => SectionRepr::Panics {
+                docs,
+                conditions,
+                subsections: subsection_items(subsections),
+            },
+            DocSection::Errors(docs, list, subsections) => SectionRepr::Errors {
+                docs,
+                conditions: pattern_items(list),
+                subsections: subsection_items(subsections),
+            },
+            DocSection::Safety(docs, subsections) => SectionRepr::Safety {
+                docs,
+                subsections: subsection_items(subsections),
+            },
+            DocSection::Custom(headline, docs, subsections) => SectionRepr::Custom {
+                headline,
+                docs,
+                subsections: subsection_items(subsections),
+            },
+        }
+    }
+}
+
+impl From<SectionRepr> for DocSection {
+    fn from(repr: SectionRepr) -> DocSection {
+        match repr {
+            SectionRepr::Parameters { parameters, subsections } => DocSection::Parameters(
+                parameters.into_iter().map(|item| (item.pattern, item.doc)).collect(),
+                from_subsection_items(subsections)),
+            SectionRepr::TypeParameters { parameters, subsections } =>
+                DocSection::TypeParameters(from_named_items(parameters), from_subsection_items(subsections)),
+            SectionRepr::LifetimeParameters { parameters, subsections } =>
+                DocSection::LifetimeParameters(from_named_items(parameters), from_subsection_items(subsections)),
+            SectionRepr::Returns { docs, variants, subsections } =>
+                DocSection::Returns(docs, from_pattern_items(variants), from_subsection_items(subsections)),
+            SectionRepr::Examples { examples, subsections } =>
+                DocSection::Examples(examples, from_subsection_items(subsections)),
+            SectionRepr::Panics { docs, conditions, subsections } =>
+                DocSection::Panics(docs, conditions, from_subsection_items(subsections)),
+            SectionRepr::Errors { docs, conditions, subsections } =>
+                DocSection::Errors(docs, from_pattern_items(conditions), from_subsection_items(subsections)),
+            SectionRepr::Safety { docs, subsections } =>
+                DocSection::Safety(docs, from_subsection_items(subsections)),
+            SectionRepr::Custom { headline, docs, subsections } =>
+                DocSection::Custom(headline, docs, from_subsection_items(subsections)),
+        }
+    }
+}
+
+/// `serde(with)` module for lists of subsections
+pub mod subsections {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(list: &[Subsection], serializer: S) -> Result<S::Ok, S::Error> {
+        subsection_items(list.to_vec()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Subsection>, D::Error> {
+        Vec::<SubsectionItem>::deserialize(deserializer).map(from_subsection_items)
+    }
+}
+
+/// `serde(with)` module for lists mapping identifiers to docs
+pub mod named_list {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(list: &[(Identifier, Documentation)], serializer: S) -> Result<S::Ok, S::Error> {
+        named_items(list.to_vec()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<(Identifier, Documentation)>, D::Error> {
+        Vec::<NamedItem>::deserialize(deserializer).map(from_named_items)
+    }
+}
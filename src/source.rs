@@ -0,0 +1,718 @@
+use ::std::collections::HashMap;
+use ::std::fs::File;
+use ::std::io::Read;
+use ::std::path::{Path, PathBuf};
+use ::proc_macro2;
+use ::syn;
+use ::syn::spanned::Spanned;
+
+use ::diagnostic::{Diagnostic, closest};
+use ::errors::{ParseError, SourceError, ValidationError};
+use ::options::ParseOptions;
+use ::span::{LineIndex, Position, Span, SpannedDocBlock};
+use ::validate;
+
+/// Clone a syntax tree node without its attributes and return its span, which
+/// then starts at the actual item
+macro_rules! without_attrs {
+    ($node:expr) => ({
+        let mut node = $node.clone();
+        node.attrs.clear();
+        node.span()
+    })
+}
+
+/// What kind of item a doc comment belongs to
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ItemKind {
+    /// A module, either inline (`mod foo { }`), declared (`mod foo;`), or the
+    /// file itself (documented with `//!`)
+    Module,
+    /// A free function
+    Function,
+    /// A struct
+    Struct,
+    /// An enum
+    Enum,
+    /// A trait
+    Trait,
+    /// A function in an `impl` block or a trait
+    Method,
+}
+
+/// A documentable item found in Rust source code
+#[derive(Debug, Clone)]
+pub struct SourceItem {
+    /// What kind of item this is
+    pub kind: ItemKind,
+    /// Path of the item relative to the source file, e.g. `foo::Bar::new`
+    /// (empty for the file itself)
+    pub path: String,
+    /// The item's declaration up to its body, with whitespace collapsed, e.g.
+    /// `pub fn new(name: &str) -> Bar`
+    pub signature: String,
+    /// Whether the item is visible outside of its crate (as far as can be told
+    /// from this file alone)
+    pub public: bool,
+    /// Location of the item in the source file, without attributes
+    pub span: Span,
+    /// The doc comment as Markdown, with comment markers and common
+    /// indentation removed, or `None` if the item has no docs
+    pub docs: Option<String>,
+    /// Location in the source file of each line of `docs`
+    pub doc_lines: Vec<Position>,
+    /// The parsed doc comment, or `None` if the item has no docs
+    pub doc: Option<Result<SpannedDocBlock, ParseError>>,
+    /// Signature of functions and methods
+    pub sig: Option<syn::Signature>,
+    /// Generics of functions, methods, structs, enums and traits (empty for
+    /// modules)
+    pub generics: syn::Generics,
+    /// Names of the variants of enums
+    pub variants: Vec<String>,
+}
+
+impl SourceItem {
+    /// Translate a position in `docs` (e.g. of a `ParseError`) to a position
+    /// in the source file
+    ///
+    /// Positions without a known line are returned unchanged.
+    pub fn source_position(&self, position: Position) -> Position {
+        match self.doc_lines.get(position.line.wrapping_sub(1)) {
+            Some(line_start) => Position {
+                offset: line_start.offset + (position.offset - self.line_offset(position.line)),
+                line: line_start.line,
+                column: line_start.column + position.column - 1,
+            },
+            None => position,
+        }
+    }
+
+    /// Check the docs against the item's code, see `validate_parameters`,
+    /// `validate_generics` and `validate_returns`
+    ///
+    /// Items without (valid) docs have nothing to check. Positions of the
+    /// errors are relative to `docs`, use `source_position` to find them in
+    /// the source file.
+    ///
+    /// # Parameters
+    ///
+    /// - `enums`: Enums the item may return, by their path, usually
+    ///   `local_enums` of all items of the crate
+    pub fn validate(&self, enums: &HashMap<String, Vec<String>>) -> Vec<ValidationError> {
+        let doc = match self.doc {
+            Some(Ok(ref doc)) => doc,
+            _ => return vec![],
+        };
+
+        let mut errors = vec![];
+        if let Some(ref sig) = self.sig {
+            let enums = validate::enums_in_scope(enums, self.module());
+            errors.extend(validate::validate_parameters_spanned(sig, doc));
+            errors.extend(validate::validate_returns_spanned(sig, doc, &enums));
+        }
+        errors.extend(validate::validate_generics_spanned(&self.generics, doc));
+        errors
+    }
+
+    /// All problems with the docs as `Diagnostic`s: The parse error, or
+    /// whatever `validate` finds
+    ///
+    /// Documented names that are probably misspelled (because an undocumented
+    /// one is similar) come with a suggestion to rename them.
+    ///
+    /// # Parameters
+    ///
+    /// - `enums`: Enums the item may return, see `validate`
+    ///
+    /// # Returns
+    ///
+    /// The diagnostics, with spans in the source file (see
+    /// `source_position`).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use self::docstrings::*;
+    /// let source = "/// Lorem ipsum\n///\n/// # Parameters\n///\n/// - `nmae`: Foo\npub fn foo(name: &str) {}\n";
+    /// let items = parse_rust_source(source).unwrap();
+    /// let diagnostics = items[0].diagnostics(&local_enums(&items));
+    ///
+    /// let unknown = diagnostics.iter().find(|diagnostic| diagnostic.code == "DS0102").unwrap();
+    /// assert_eq!(unknown.suggestions[0].apply(source), source.replace("`nmae`", "`name`"));
+    /// ```
+    pub fn diagnostics(&self, enums: &HashMap<String, Vec<String>>) -> Vec<Diagnostic> {
+        let docs = match self.docs {
+            Some(ref docs) => docs,
+            None => return vec![],
+        };
+
+        let diagnostics = match self.doc {
+            Some(Err(ref error)) => vec![error.to_diagnostic(docs)],
+            Some(Ok(_)) => {
+                let errors = self.validate(enums);
+                errors.iter()
+                    .map(|error| {
+                        let diagnostic = error.to_diagnostic(docs);
+                        let span = diagnostic.primary_span().unwrap_or_default();
+
+                        match closest(error.name(), undocumented_counterparts(error, &errors)) {
+                            Some(name) if !span.is_empty() =>
+                                diagnostic.with_suggestion(span, name, format!("rename it to `{}`", name)),
+                            _ => diagnostic,
+                        }
+                    })
+                    .collect()
+            }
+            None => vec![],
+        };
+
+        diagnostics.into_iter()
+            .map(|diagnostic| diagnostic.map_positions(|position| self.source_position(position)))
+            .collect()
+    }
+
+    /// Path of the module the item is in, e.g. `foo` for `foo::Bar::new`
+    fn module(&self) -> &str {
+        let mut path = &self.path[..];
+        let levels = if self.kind == ItemKind::Method { 2 } else { 1 };
+        for _ in 0..levels {
+            path = path.rfind("::").map(|end| &path[..end]).unwrap_or("");
+        }
+        path
+    }
+
+    fn set_sig(&mut self, sig: &syn::Signature) {
+        self.sig = Some(sig.clone());
+        self.generics = sig.generics.clone();
+    }
+
+    /// Byte offset in `docs` where the given (1-based) line starts
+    fn line_offset(&self, line: usize) -> usize {
+        self.docs.as_ref()
+            .map(|docs| docs.split('\n').take(line - 1).map(|l| l.len() + 1).sum())
+            .unwrap_or(0)
+    }
+}
+
+/// Names of whatever `error` documents that are not documented, if it is
+/// about something unknown
+fn undocumented_counterparts<'a>(error: &ValidationError, errors: &'a [ValidationError]) -> Vec<&'a str> {
+    errors.iter()
+        .filter(|other| matches!((error, *other),
+            (&ValidationError::UnknownParameter(..), &ValidationError::UndocumentedParameter(..)) |
+            (&ValidationError::UnknownTypeParameter(..), &ValidationError::UndocumentedTypeParameter(..)) |
+            (&ValidationError::UnknownLifetime(..), &ValidationError::UndocumentedLifetime(..)) |
+            (&ValidationError::UnknownReturnVariant(..), &ValidationError::UndocumentedReturnVariant(..))))
+        .map(ValidationError::name)
+        .collect()
+}
+
+/// Extract all documentable items from Rust source code
+///
+/// Doc comments are gathered from `///`, `//!`, `/** */`, `/*! */` comments
+/// and `#[doc = "..."]` attributes, just like rustdoc would see them.
+///
+/// # Parameters
+///
+/// - `source`: Contents of a Rust source file
+///
+/// # Returns
+///
+/// A `Result`, which is either
+///
+/// - `Ok(Vec<SourceItem>)`: All modules, functions, structs, enums, traits and
+///   methods in the order they appear in, with their docs parsed by
+///   `parse_md_docblock`.
+/// - `Err(SourceError)`: `source` is not valid Rust.
+///
+/// # Examples
+///
+/// ```rust
+/// # use self::docstrings::*;
+/// let items = parse_rust_source("
+/// /// Lorem ipsum
+/// ///
+/// /// # Parameters
+/// ///
+/// /// - `x`: Foo
+/// pub fn foo(x: u32) -> u32 { x }
+/// ").unwrap();
+///
+/// assert_eq!(items[0].path, "foo");
+/// assert_eq!(items[0].signature, "pub fn foo(x: u32) -> u32");
+/// assert_eq!(items[0].doc.as_ref().unwrap().as_ref().unwrap().doc.teaser, "Lorem ipsum");
+/// ```
+pub fn parse_rust_source(source: &str) -> Result<Vec<SourceItem>, SourceError> {
+    parse_rust_source_with(source, &ParseOptions::default())
+}
+
+/// Extract all documentable items from Rust source code, parsing their docs
+/// as configured by `ParseOptions`
+///
+/// # Parameters
+///
+/// - `source`: Contents of a Rust source file
+/// - `options`: How to parse the doc comments
+///
+/// # Returns
+///
+/// Same as `parse_rust_source`. In lenient mode, the docs of each item are
+/// always parsed successfully.
+pub fn parse_rust_source_with(source: &str, options: &ParseOptions) -> Result<Vec<SourceItem>, SourceError> {
+    extract(source, options, &[], true).map(|(items, _)| items)
+}
+
+/// Extract all documentable items from a Rust source file
+///
+/// # Parameters
+///
+/// - `path`: The file to read
+///
+/// # Returns
+///
+/// Same as `parse_rust_source`, or a `SourceError::Io` if the file can't be
+/// read.
+pub fn parse_rust_file<P: AsRef<Path>>(path: P) -> Result<Vec<SourceItem>, SourceError> {
+    parse_rust_source(&read_source(path.as_ref())?)
+}
+
+/// A Rust source file of a crate, see `parse_rust_crate`
+#[derive(Debug)]
+pub struct SourceFile {
+    /// Location of the file
+    pub path: PathBuf,
+    /// Path of the module the file contains, e.g. `foo::bar` (empty for the
+    /// crate root)
+    pub module: String,
+    /// Items in the file, with paths relative to the crate root, or why they
+    /// could not be extracted
+    pub items: Result<Vec<SourceItem>, SourceError>,
+}
+
+/// Extract all documentable items from the files of a crate
+///
+/// Starting at the crate root, this follows module declarations (`mod foo;`)
+/// to the files of these modules (`foo.rs` or `foo/mod.rs`, or the file given
+/// in a `#[path]` attribute), like rustc does. Items are public if they (and
+/// all modules they are in) are `pub`; re-exports (`pub use`) are not taken
+/// into account. Modules whose file can't be found (e.g. because it is
+/// generated by a build script) are skipped.
+///
+/// # Parameters
+///
+/// - `root`: The root file of the crate, e.g. `src/lib.rs`
+///
+/// # Returns
+///
+/// All files of the crate, starting with `root`, with the items in them (see
+/// `parse_rust_source`). Files that can't be read or parsed are included with
+/// the error, and modules declared in them are missing.
+pub fn parse_rust_crate<P: AsRef<Path>>(root: P) -> Vec<SourceFile> {
+    parse_rust_crate_with(root, &ParseOptions::default())
+}
+
+/// Extract all documentable items from the files of a crate, parsing their
+/// docs as configured by `ParseOptions`
+///
+/// # Parameters
+///
+/// - `root`: The root file of the crate, e.g. `src/lib.rs`
+/// - `options`: How to parse the doc comments
+///
+/// # Returns
+///
+/// Same as `parse_rust_crate`.
+pub fn parse_rust_crate_with<P: AsRef<Path>>(root: P, options: &ParseOptions) -> Vec<SourceFile> {
+    let root = root.as_ref();
+    let dir = root.parent().map(Path::to_path_buf).unwrap_or_default();
+    let mut files = vec![];
+
+    crate_file(root.to_path_buf(), dir, vec![], true, options, &mut files);
+    files
+}
+
+/// A module declared like `mod foo;`, whose items are in another file
+#[derive(Debug)]
+struct DeclaredModule {
+    /// Path of the module, e.g. `foo::bar`
+    path: Vec<String>,
+    /// Whether the module and all modules it is in are `pub`
+    public: bool,
+    /// File given in a `#[path = "..."]` attribute
+    file: Option<String>,
+}
+
+/// Extract the items of a file of a crate, and of the files of all modules
+/// declared in it
+///
+/// `dir` is the directory containing the files of modules declared in this
+/// file, and `module` the path of the module in it.
+fn crate_file(path: PathBuf, dir: PathBuf, module: Vec<String>, public: bool, options: &ParseOptions, files: &mut Vec<SourceFile>) {
+    let extracted = read_source(&path).and_then(|source| extract(&source, options, &module, public));
+    let (items, declared) = match extracted {
+        Ok((items, declared)) => (Ok(items), declared),
+        Err(error) => (Err(error), vec![]),
+    };
+    files.push(SourceFile {
+        path: path.clone(),
+        module: module.join("::"),
+        items,
+    });
+
+    for declared in declared {
+        // Modules declared in inline modules are in subdirectories
+        let inline = &declared.path[module.len()..declared.path.len() - 1];
+        let name = &declared.path[declared.path.len() - 1];
+        let base = inline.iter().fold(dir.clone(), |dir, name| dir.join(name));
+
+        let file = match declared.file {
+            Some(ref file) if inline.is_empty() => path.parent().map(|parent| parent.join(file)),
+            Some(ref file) => Some(base.join(file)),
+            None => vec![base.join(format!("{}.rs", name)), base.join(name).join("mod.rs")]
+                .into_iter()
+                .find(|file| file.is_file()),
+        };
+        let file = match file {
+            Some(ref file) if file.is_file() => file.clone(),
+            _ => continue,
+        };
+
+        let dir = match declared.file {
+            Some(_) => file.parent().map(Path::to_path_buf).unwrap_or_default(),
+            None => base.join(name),
+        };
+        crate_file(file, dir, declared.path, declared.public, options, files);
+    }
+}
+
+fn read_source(path: &Path) -> Result<String, SourceError> {
+    let mut source = String::new();
+    File::open(path)?.read_to_string(&mut source)?;
+    Ok(source)
+}
+
+/// Extract the items of a source file containing the module at `path`, and
+/// the modules declared in it
+fn extract(source: &str, options: &ParseOptions, path: &[String], public: bool) -> Result<(Vec<SourceItem>, Vec<DeclaredModule>), SourceError> {
+    let lines = LineIndex::new(source);
+    let file = syn::parse_file(source).map_err(|error| {
+        let start = error.span().start();
+        SourceError::Syntax(error.to_string(), Position {
+            offset: error.span().byte_range().start,
+            line: start.line,
+            column: start.column + 1,
+        })
+    })?;
+
+    let mut extractor = Extractor {
+        source,
+        lines,
+        options,
+        items: vec![],
+        declared: vec![],
+    };
+
+    if has_docs(&file.attrs) {
+        extractor.push(ItemKind::Module, path, String::new(), (String::new(), Span::default()), public,
+                       &file.attrs);
+    }
+    extractor.items(&file.items, path, public);
+
+    Ok((extractor.items, extractor.declared))
+}
+
+struct Extractor<'s, 'o> {
+    source: &'s str,
+    lines: LineIndex<'s>,
+    options: &'o ParseOptions,
+    items: Vec<SourceItem>,
+    declared: Vec<DeclaredModule>,
+}
+
+impl<'s, 'o> Extractor<'s, 'o> {
+    fn items(&mut self, items: &[syn::Item], path: &[String], public: bool) {
+        for item in items {
+            match *item {
+                syn::Item::Fn(ref f) => {
+                    let declaration = self.declaration(without_attrs!(f), f.block.brace_token.span.open());
+                    self.push(ItemKind::Function, path, f.sig.ident.to_string(), declaration,
+                              public && is_pub(&f.vis), &f.attrs)
+                        .set_sig(&f.sig);
+                }
+                syn::Item::Struct(ref s) => {
+                    let end = match s.fields {
+                        syn::Fields::Named(ref fields) => fields.brace_token.span.open(),
+                        _ => s.semi_token.map(|semi| semi.span()).unwrap_or_else(|| s.span()),
+                    };
+                    let declaration = self.declaration(without_attrs!(s), end);
+                    self.push(ItemKind::Struct, path, s.ident.to_string(), declaration,
+                              public && is_pub(&s.vis), &s.attrs)
+                        .generics = s.generics.clone();
+                }
+                syn::Item::Enum(ref e) => {
+                    let declaration = self.declaration(without_attrs!(e), e.brace_token.span.open());
+                    let item = self.push(ItemKind::Enum, path, e.ident.to_string(), declaration,
+                                         public && is_pub(&e.vis), &e.attrs);
+                    item.generics = e.generics.clone();
+                    item.variants = e.variants.iter().map(|variant| variant.ident.to_string()).collect();
+                }
+                syn::Item::Trait(ref t) => {
+                    let declaration = self.declaration(without_attrs!(t), t.brace_token.span.open());
+                    let public = public && is_pub(&t.vis);
+                    self.push(ItemKind::Trait, path, t.ident.to_string(), declaration,
+                              public, &t.attrs)
+                        .generics = t.generics.clone();
+
+                    let path = extend(path, t.ident.to_string());
+                    for item in &t.items {
+                        if let syn::TraitItem::Fn(ref m) = *item {
+                            let end = match m.default {
+                                Some(ref block) => block.brace_token.span.open(),
+                                None => m.semi_token.map(|semi| semi.span()).unwrap_or_else(|| m.span()),
+                            };
+                            let declaration = self.declaration(without_attrs!(m), end);
+                            self.push(ItemKind::Method, &path, m.sig.ident.to_string(), declaration,
+                                      public, &m.attrs)
+                                .set_sig(&m.sig);
+                        }
+                    }
+                }
+                syn::Item::Impl(ref i) => {
+                    let path = extend(path, self.type_name(&i.self_ty));
+                    for item in &i.items {
+                        if let syn::ImplItem::Fn(ref m) = *item {
+                            // Methods of trait impls are documented by the
+                            // trait
+                            let public = public && i.trait_.is_none() && is_pub(&m.vis);
+                            let declaration = self.declaration(without_attrs!(m), m.block.brace_token.span.open());
+                            self.push(ItemKind::Method, &path, m.sig.ident.to_string(), declaration,
+                                      public, &m.attrs)
+                                .set_sig(&m.sig);
+                        }
+                    }
+                }
+                syn::Item::Mod(ref m) => {
+                    let end = match m.content {
+                        Some((ref brace, _)) => brace.span.open(),
+                        None => m.semi.map(|semi| semi.span()).unwrap_or_else(|| m.span()),
+                    };
+                    let declaration = self.declaration(without_attrs!(m), end);
+                    let public = public && is_pub(&m.vis);
+                    self.push(ItemKind::Module, path, m.ident.to_string(), declaration,
+                              public, &m.attrs);
+
+                    let path = extend(path, m.ident.to_string());
+                    match m.content {
+                        Some((_, ref items)) => self.items(items, &path, public),
+                        None => self.declared.push(DeclaredModule {
+                            path,
+                            public,
+                            file: path_attribute(&m.attrs),
+                        }),
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Name of the type an `impl` block is for, without generic arguments
+    fn type_name(&self, ty: &syn::Type) -> String {
+        match *ty {
+            syn::Type::Path(ref path) => path.path.segments.last()
+                .map(|segment| segment.ident.to_string())
+                .unwrap_or_default(),
+            syn::Type::Reference(ref reference) => self.type_name(&reference.elem),
+            _ => self.source.get(ty.span().byte_range()).unwrap_or("")
+                .split_whitespace()
+                .collect(),
+        }
+    }
+
+    /// The source code from `start` up to (but not including) `end`, with
+    /// whitespace collapsed, and its location
+    fn declaration(&self, start: proc_macro2::Span, end: proc_macro2::Span) -> (String, Span) {
+        let range = start.byte_range().start..end.byte_range().start;
+        let text = self.source.get(range.clone()).unwrap_or("");
+        let signature = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let trimmed_end = range.start + text.trim_end().len();
+        (signature, self.lines.span(range.start..trimmed_end))
+    }
+
+    /// Add an item, given its declaration (see `declaration`)
+    fn push(&mut self, kind: ItemKind, path: &[String], name: String, declaration: (String, Span), public: bool, attrs: &[syn::Attribute]) -> &mut SourceItem {
+        let (signature, span) = declaration;
+        let lines = self.doc_lines(attrs);
+        let (docs, doc_lines) = if lines.is_empty() {
+            (None, vec![])
+        } else {
+            let (docs, positions): (Vec<String>, Vec<Position>) = lines.into_iter().unzip();
+            (Some(docs.join("\n")), positions)
+        };
+
+        let doc = docs.as_ref().map(|docs| {
+            ::parse_md_docblock_spanned_with(docs, self.options).map(|(doc, _)| doc)
+        });
+
+        let path = if name.is_empty() { path.to_vec() } else { extend(path, name) };
+
+        self.items.push(SourceItem {
+            kind,
+            path: path.join("::"),
+            signature,
+            public,
+            span,
+            docs,
+            doc_lines,
+            doc,
+            sig: None,
+            generics: syn::Generics::default(),
+            variants: vec![],
+        });
+        self.items.last_mut().expect("item was just pushed")
+    }
+
+    /// Lines of the doc comment made up of `attrs`, with their locations
+    fn doc_lines(&self, attrs: &[syn::Attribute]) -> Vec<(String, Position)> {
+        let mut lines = vec![];
+
+        for attr in attrs {
+            let lit = match doc_string(attr) {
+                Some(lit) => lit,
+                None => continue,
+            };
+
+            let text = lit.value();
+            let range = lit.span().byte_range();
+            let written = self.source.get(range.clone()).unwrap_or("");
+            let is_block = written.starts_with("/*");
+            let content_start = range.start + content_offset(written);
+
+            // Only when the text is written as is can its lines be found in the
+            // source
+            let verbatim = written.get(content_offset(written)..)
+                .map(|rest| rest.starts_with(&text[..]))
+                .unwrap_or(false);
+
+            let mut offset = content_start;
+            let mut block = vec![];
+            for line in text.split('\n') {
+                block.push((line.to_string(), self.lines.position(offset)));
+                if verbatim {
+                    offset += line.len() + 1;
+                }
+            }
+
+            if is_block {
+                block = strip_block_decoration(block);
+            }
+            lines.extend(block);
+        }
+
+        unindent(lines)
+    }
+}
+
+/// The file given in a `#[path = "..."]` attribute
+fn path_attribute(attrs: &[syn::Attribute]) -> Option<String> {
+    attrs.iter()
+        .filter_map(|attr| match attr.meta {
+            syn::Meta::NameValue(ref meta) if meta.path.is_ident("path") => match meta.value {
+                syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(ref lit), .. }) => Some(lit.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .next()
+}
+
+fn has_docs(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| doc_string(attr).is_some())
+}
+
+/// The string of a `#[doc = "..."]` attribute (which is what doc comments
+/// are turned into)
+fn doc_string(attr: &syn::Attribute) -> Option<syn::LitStr> {
+    if let syn::Meta::NameValue(ref meta) = attr.meta {
+        if meta.path.is_ident("doc") {
+            if let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(ref lit), .. }) = meta.value {
+                return Some(lit.clone());
+            }
+        }
+    }
+    None
+}
+
+/// Where the text of a doc comment or string literal starts
+fn content_offset(written: &str) -> usize {
+    if written.starts_with("///") || written.starts_with("//!") ||
+       written.starts_with("/**") || written.starts_with("/*!") {
+        3
+    } else if written.starts_with('r') {
+        written.find('"').map(|quote| quote + 1).unwrap_or(0)
+    } else if written.starts_with('"') {
+        1
+    } else {
+        0
+    }
+}
+
+/// Remove the blank first and last line of a block comment as well as a `*`
+/// at the start of each line (if all lines have one)
+fn strip_block_decoration(mut lines: Vec<(String, Position)>) -> Vec<(String, Position)> {
+    if lines.first().map(|(line, _)| line.trim().is_empty()).unwrap_or(false) {
+        lines.remove(0);
+    }
+    if lines.last().map(|(line, _)| line.trim().is_empty()).unwrap_or(false) {
+        lines.pop();
+    }
+
+    let decorated = lines.iter()
+        .all(|(line, _)| line.trim_start().starts_with('*'));
+    if decorated {
+        for &mut (ref mut line, ref mut position) in &mut lines {
+            let star = line.find('*').unwrap_or(0);
+            position.offset += star + 1;
+            position.column += line[..star + 1].chars().count();
+            *line = line[star + 1..].to_string();
+        }
+    }
+
+    lines
+}
+
+/// Remove the indentation all non-blank lines have in common
+///
+/// Trailing whitespace is kept, as two spaces are a hard break in Markdown.
+fn unindent(lines: Vec<(String, Position)>) -> Vec<(String, Position)> {
+    let indent = lines.iter()
+        .filter(|&(line, _)| !line.trim().is_empty())
+        .map(|(line, _)| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    lines.into_iter()
+        .map(|(line, mut position)| {
+            if line.trim().is_empty() {
+                (String::new(), position)
+            } else {
+                position.offset += indent;
+                position.column += line[..indent].chars().count();
+                (line[indent..].trim_end_matches('\r').to_string(), position)
+            }
+        })
+        .collect()
+}
+
+fn is_pub(vis: &syn::Visibility) -> bool {
+    matches!(*vis, syn::Visibility::Public(_))
+}
+
+fn extend(path: &[String], name: String) -> Vec<String> {
+    let mut path = path.to_vec();
+    path.push(name);
+    path
+}
@@ -0,0 +1,215 @@
+use ::std::fmt;
+use ::std::ops::Range;
+use ::pulldown_cmark::{Event, Parser};
+
+use ::types::DocBlock;
+
+/// A location in a documentation string
+///
+/// Line and column are `0` when the location is unknown, e.g. when parsing
+/// from an event stream that carries no offsets.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Position {
+    /// Byte offset from the start of the documentation string
+    pub offset: usize,
+    /// Line number, starting at 1
+    pub line: usize,
+    /// Column (counted in characters), starting at 1
+    pub column: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// A range in a documentation string
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Span {
+    /// First position covered by the span
+    pub start: Position,
+    /// Position right after the end of the span
+    pub end: Position,
+}
+
+impl Span {
+    /// Byte range of this span, usable to slice the documentation string
+    pub fn range(&self) -> Range<usize> {
+        self.start.offset..self.end.offset
+    }
+
+    /// Whether the span covers no text at all
+    pub fn is_empty(&self) -> bool {
+        self.start.offset >= self.end.offset
+    }
+
+    /// Smallest span covering both `self` and `other`
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: if other.start < self.start { other.start } else { self.start },
+            end: if other.end > self.end { other.end } else { self.end },
+        }
+    }
+}
+
+/// Information extracted from a doc comment, together with source locations
+///
+/// This is what `parse_md_docblock_spanned` returns. The spans are kept next
+/// to the `DocBlock` (instead of inside it) so the plain types stay easy to
+/// construct and compare.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SpannedDocBlock {
+    /// The extracted information
+    pub doc: DocBlock,
+    /// Location of the teaser
+    pub teaser: Span,
+    /// Location of the description
+    pub description: Option<Span>,
+    /// Locations of the subsections of the description, in the same order as
+    /// `doc.subsections`
+    pub subsections: Vec<SectionSpans>,
+    /// Locations of the sections, in the same order as `doc.sections`
+    pub sections: Vec<SectionSpans>,
+}
+
+/// Source locations of a `DocSection`
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SectionSpans {
+    /// The whole section, including its headline
+    pub span: Span,
+    /// The headline text
+    pub headline: Span,
+    /// Items of the list mapping identifiers (or patterns) to docs, empty for
+    /// sections without such a list
+    pub items: Vec<ItemSpans>,
+    /// Locations of the subsections, in the same order as in the section
+    pub subsections: Vec<SectionSpans>,
+}
+
+/// Source locations of an `(Identifier, Documentation)` list item
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ItemSpans {
+    /// The whole list item, including the bullet
+    pub span: Span,
+    /// The identifier (or pattern), without backticks
+    pub ident: Span,
+    /// The documentation following the identifier
+    pub docs: Span,
+    /// Items of the nested list of fields (see `ParameterDoc`), empty if there
+    /// is none
+    pub fields: Vec<ItemSpans>,
+}
+
+/// Maps byte offsets to lines and columns
+#[derive(Debug)]
+pub struct LineIndex<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> LineIndex<'a> {
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+
+        LineIndex {
+            text,
+            line_starts,
+        }
+    }
+
+    pub fn position(&self, offset: usize) -> Position {
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        let line_start = self.line_starts[line];
+
+        Position {
+            offset,
+            line: line + 1,
+            column: self.text[line_start..offset].chars().count() + 1,
+        }
+    }
+
+    pub fn span(&self, range: Range<usize>) -> Span {
+        Span {
+            start: self.position(range.start),
+            end: self.position(range.end),
+        }
+    }
+}
+
+impl<'a> Default for LineIndex<'a> {
+    fn default() -> LineIndex<'a> {
+        LineIndex::new("")
+    }
+}
+
+/// A Markdown event and the part of the documentation string it came from
+pub type SpannedEvent<'a> = (Event<'a>, Span);
+
+/// Markdown events with their locations
+///
+/// `pulldown-cmark` only tells us its offset after each event, so the span of
+/// an event is everything between that and the previous offset, minus
+/// surrounding whitespace.
+pub struct SpannedEvents<'a> {
+    parser: Parser<'a>,
+    lines: LineIndex<'a>,
+}
+
+impl<'a> SpannedEvents<'a> {
+    pub fn new(parser: Parser<'a>, text: &'a str) -> SpannedEvents<'a> {
+        SpannedEvents {
+            parser,
+            lines: LineIndex::new(text),
+        }
+    }
+}
+
+impl<'a> Iterator for SpannedEvents<'a> {
+    type Item = SpannedEvent<'a>;
+
+    fn next(&mut self) -> Option<SpannedEvent<'a>> {
+        let before = self.parser.get_offset();
+        let event = self.parser.next()?;
+        let after = self.parser.get_offset();
+        let (start, end) = if before <= after { (before, after) } else { (after, before) };
+
+        let raw = &self.lines.text[start..end];
+        let trimmed_start = start + (raw.len() - raw.trim_start().len());
+        let trimmed_end = start + raw.trim_end().len();
+
+        let range = if trimmed_start < trimmed_end {
+            trimmed_start..trimmed_end
+        } else if let Event::Start(_) = event {
+            // Nothing but whitespace: Put an empty span where the content of
+            // the tag starts or where the previous content ended.
+            end..end
+        } else {
+            start..start
+        };
+
+        let span = self.lines.span(range);
+        Some((event, span))
+    }
+}
+
+/// Smallest span covering all non-empty spans of the given events
+pub fn span_of<'a>(events: &[SpannedEvent<'a>]) -> Span {
+    events.iter()
+        .map(|&(_, span)| span)
+        .filter(|span| !span.is_empty())
+        .fold(None, |acc: Option<Span>, span| Some(match acc {
+            Some(acc) => acc.to(span),
+            None => span,
+        }))
+        .unwrap_or_default()
+}
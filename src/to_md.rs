@@ -1,67 +1,531 @@
-use pulldown_cmark::{Event, Tag};
+use ::std::mem;
+use ::pulldown_cmark::{Event, Tag};
 
+use ::span::{Span, SpannedEvent};
+
+/// Serialize Markdown events back to Markdown
 pub fn md<'a, I>(events: I) -> String where
     I: Iterator<Item = Event<'a>>,
 {
-    let mut res = String::new();
+    md_from_source(events.map(|event| (event, Span::default())), "")
+}
 
-    for event in events {
-        match event {
-            Event::Text(t) => {
-                res.push_str(&t);
+/// Serialize Markdown events back to Markdown, using the documentation string
+/// they were parsed from to keep reference-style links as they were written
+///
+/// The definitions of those links are not included, see `link_definitions`.
+pub fn md_from_source<'a, I>(events: I, source: &str) -> String where
+    I: Iterator<Item = SpannedEvent<'a>>,
+{
+    let mut writer = Writer::new(source);
+
+    for (event, span) in events {
+        writer.event(event, span);
+    }
+
+    writer.finish()
+}
+
+/// The definitions of all reference-style links in the events (e.g.
+/// `[1]: http://example.com`), one per line, as written in `source`
+///
+/// Each definition is listed once, no matter how often it is used.
+pub fn link_definitions<'a, I>(events: I, source: &str) -> Option<String> where
+    I: Iterator<Item = SpannedEvent<'a>>,
+{
+    let mut writer = Writer::new(source);
+
+    for (event, span) in events {
+        writer.event(event, span);
+    }
+
+    let definitions = writer.references.iter()
+        .filter_map(|label| link_definition(source, label))
+        .fold(Vec::<&str>::new(), |mut definitions, definition| {
+            if !definitions.contains(&definition) {
+                definitions.push(definition);
             }
-            Event::SoftBreak => {
-                res.push_str("\n");
+            definitions
+        });
+
+    if definitions.is_empty() {
+        None
+    } else {
+        Some(definitions.join("\n"))
+    }
+}
+
+/// A table while its cells are being collected
+struct Table {
+    /// Cells of the row separating header and body, e.g. `:--`
+    separator: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+struct Writer<'s> {
+    source: &'s str,
+    res: String,
+    /// Prefixes of continuation lines, e.g. `> ` in block quotes
+    prefixes: Vec<String>,
+    /// Number of the next item of each (nested) list, `None` for bullet lists
+    lists: Vec<Option<usize>>,
+    /// Line breaks to write before the next content
+    newlines: usize,
+    /// Content of the current code block
+    code: Option<String>,
+    /// Whether the last event was (part of) an HTML block
+    html: bool,
+    tables: Vec<Table>,
+    /// Everything written before the current table cell, and the line breaks
+    /// that were pending then
+    before_cell: Option<(String, usize)>,
+    /// Labels of the reference-style links that were written
+    references: Vec<String>,
+    /// Text not written yet: Consecutive text events are escaped together
+    text: String,
+    /// Whether the next text starts a line (possibly after the marker of a
+    /// list item or block quote), where it could be read as block syntax
+    line_start: bool,
+    /// Whether we are inside inline code, where nothing is escaped
+    inline_code: bool,
+}
+
+impl<'s> Writer<'s> {
+    fn new(source: &'s str) -> Writer<'s> {
+        Writer {
+            source,
+            res: String::new(),
+            prefixes: vec![],
+            lists: vec![],
+            newlines: 0,
+            code: None,
+            html: false,
+            tables: vec![],
+            before_cell: None,
+            references: vec![],
+            text: String::new(),
+            line_start: true,
+            inline_code: false,
+        }
+    }
+
+    /// Write some text, prefixing each new line
+    fn write(&mut self, text: &str) {
+        if !self.res.is_empty() {
+            for i in 0..self.newlines {
+                let blank = i + 1 < self.newlines;
+                self.newline(blank);
             }
+        }
+        self.newlines = 0;
+        if !text.is_empty() {
+            self.line_start = false;
+        }
 
-            Event::Start(Tag::Code) |
-            Event::End(Tag::Code) => {
-                res.push_str("`");
+        let mut lines = text.split('\n');
+        self.res.push_str(lines.next().unwrap_or(""));
+        for line in lines {
+            self.newline(line.is_empty());
+            self.res.push_str(line);
+        }
+    }
+
+    fn newline(&mut self, blank: bool) {
+        let prefix = self.prefixes.concat();
+        self.res.push('\n');
+        self.res.push_str(if blank { prefix.trim_end() } else { &prefix });
+    }
+
+    /// Make sure the next content starts on a new line, possibly after a blank
+    /// one
+    fn break_lines(&mut self, newlines: usize) {
+        if self.newlines < newlines {
+            self.newlines = newlines;
+        }
+    }
+
+    /// Write the text collected so far, escaped where it would be read as
+    /// Markdown syntax
+    fn flush_text(&mut self) {
+        if self.text.is_empty() {
+            return;
+        }
+        let text = mem::take(&mut self.text);
+
+        if self.inline_code {
+            self.write(&text);
+        } else if !self.tables.is_empty() {
+            self.write(&escape(text.trim(), false, true));
+        } else {
+            let line_start = self.line_start || self.newlines > 0;
+            self.write(&escape(&text, line_start, false));
+        }
+    }
+
+    fn event<'a>(&mut self, event: Event<'a>, span: Span) {
+        if let Event::Text(_) = event {} else {
+            self.flush_text();
+        }
+
+        if self.html {
+            if let Event::Html(_) = event {} else {
+                self.html = false;
+                self.break_lines(2);
             }
+        }
 
-            Event::Start(Tag::Emphasis) |
-            Event::End(Tag::Emphasis) => {
-                res.push_str("*");
+        match event {
+            Event::Text(text) => {
+                if let Some(ref mut code) = self.code {
+                    code.push_str(&text);
+                } else {
+                    self.text.push_str(&text);
+                }
+            }
+            Event::Html(html) => {
+                self.write(html.trim_end_matches('\n'));
+                self.html = true;
+                self.break_lines(1);
+            }
+            Event::InlineHtml(html) => self.write(&html),
+            Event::FootnoteReference(name) => self.write(&format!("[^{}]", name)),
+            Event::SoftBreak => self.break_lines(1),
+            Event::HardBreak => {
+                self.write("\\");
+                self.break_lines(1);
             }
 
-            Event::Start(Tag::Strong) |
-            Event::End(Tag::Strong) => {
-                res.push_str("**");
+            Event::Start(Tag::Paragraph) => {}
+            Event::End(Tag::Paragraph) => self.break_lines(2),
+
+            Event::Start(Tag::Header(n)) => {
+                let hashes = "#".repeat(n as usize);
+                self.write(&format!("{} ", hashes));
             }
+            Event::End(Tag::Header(_)) => self.break_lines(2),
 
-            Event::End(Tag::Paragraph) => {
-                res.push_str("\n\n");
+            Event::Start(Tag::Rule) => self.write("---"),
+            Event::End(Tag::Rule) => self.break_lines(2),
+
+            Event::Start(Tag::BlockQuote) => {
+                self.write("> ");
+                self.line_start = true;
+                self.prefixes.push("> ".into());
+            }
+            Event::End(Tag::BlockQuote) => {
+                self.prefixes.pop();
+                self.break_lines(2);
             }
 
-            Event::Start(Tag::Header(n)) => {
-                res.push_str("\n");
-                res.extend(::std::iter::repeat("#").take(n as usize));
-                res.push_str(" ");
+            Event::Start(Tag::CodeBlock(_)) => {
+                self.code = Some(String::new());
             }
-            Event::End(Tag::Header(_)) => {
-                res.push_str("\n\n");
+            Event::End(Tag::CodeBlock(info)) => {
+                let mut code = self.code.take().unwrap_or_default();
+                if !code.is_empty() && !code.ends_with('\n') {
+                    code.push('\n');
+                }
+                let fence = fence('`', &code);
+                self.write(&format!("{}{}\n{}{}", fence, info, code, fence));
+                self.break_lines(2);
             }
 
-            Event::Start(Tag::Item) => {
-                res.push_str("- ");
+            Event::Start(Tag::List(start)) => {
+                self.lists.push(start);
+                self.break_lines(1);
             }
-            Event::End(Tag::Item) |
             Event::End(Tag::List(_)) => {
-                res.push_str("\n");
+                self.lists.pop();
+                self.break_lines(if self.lists.is_empty() { 2 } else { 1 });
+            }
+            Event::Start(Tag::Item) => {
+                let marker = match self.lists.last_mut() {
+                    Some(&mut Some(ref mut number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "- ".into(),
+                };
+                self.write(&marker);
+                self.line_start = true;
+                self.prefixes.push(marker.chars().map(|_| ' ').collect());
+            }
+            Event::End(Tag::Item) => {
+                self.prefixes.pop();
+                self.break_lines(1);
+            }
+
+            Event::Start(Tag::FootnoteDefinition(name)) => {
+                self.write(&format!("[^{}]: ", name));
+                self.prefixes.push("    ".into());
+            }
+            Event::End(Tag::FootnoteDefinition(_)) => {
+                self.prefixes.pop();
+                self.break_lines(2);
+            }
+
+            Event::Start(Tag::Table(alignments)) => {
+                // `pulldown-cmark` does not export its `Alignment` type, so we
+                // can only go by its name.
+                let separator = alignments.iter()
+                    .map(|alignment| match &format!("{:?}", alignment)[..] {
+                        "Left" => ":--",
+                        "Center" => ":-:",
+                        "Right" => "--:",
+                        _ => "---",
+                    }.to_string())
+                    .collect();
+
+                self.tables.push(Table {
+                    separator,
+                    rows: vec![],
+                });
+            }
+            Event::End(Tag::Table(_)) => {
+                if let Some(table) = self.tables.pop() {
+                    let table = render_table(table);
+                    self.write(&table);
+                }
+                self.break_lines(2);
+            }
+            Event::Start(Tag::TableHead) |
+            Event::Start(Tag::TableRow) => {
+                if let Some(table) = self.tables.last_mut() {
+                    table.rows.push(vec![]);
+                }
+            }
+            Event::End(Tag::TableHead) |
+            Event::End(Tag::TableRow) => {}
+            Event::Start(Tag::TableCell) => {
+                // Collect the cell's content on its own
+                let before = mem::take(&mut self.res);
+                self.before_cell = Some((before, self.newlines));
+                self.newlines = 0;
+            }
+            Event::End(Tag::TableCell) => {
+                if let Some((before, newlines)) = self.before_cell.take() {
+                    let cell = mem::replace(&mut self.res, before);
+                    self.newlines = newlines;
+                    if let Some(row) = self.tables.last_mut().and_then(|t| t.rows.last_mut()) {
+                        row.push(cell);
+                    }
+                }
             }
 
-            Event::Start(Tag::CodeBlock(info)) => {
-                res.push_str("```");
-                res.push_str(&info);
-                res.push_str("\n");
+            Event::Start(Tag::Code) => {
+                self.write("`");
+                self.inline_code = true;
+            }
+            Event::End(Tag::Code) => {
+                self.write("`");
+                self.inline_code = false;
             }
-            Event::End(Tag::CodeBlock(_)) => {
-                res.push_str("```\n");
+
+            Event::Start(Tag::Emphasis) |
+            Event::End(Tag::Emphasis) => self.write("*"),
+
+            Event::Start(Tag::Strong) |
+            Event::End(Tag::Strong) => self.write("**"),
+
+            Event::Start(Tag::Link(..)) => self.write("["),
+            Event::Start(Tag::Image(..)) => self.write("!["),
+            Event::End(Tag::Link(url, title)) |
+            Event::End(Tag::Image(url, title)) => {
+                let end = self.link_end(&url, &title, span);
+                self.write(&end);
             }
+        }
+    }
+
+    /// The part of a link after its text, e.g. `](url)`
+    fn link_end(&mut self, url: &str, title: &str, span: Span) -> String {
+        // Reference-style links are written just like in the source
+        if let Some(written) = self.source.get(span.range()) {
+            if written.starts_with(']') && !written.starts_with("](") {
+                let label = if written.len() > 3 {
+                    written[2..written.len() - 1].to_string()
+                } else {
+                    // Collapsed and shortcut references use the text as label
+                    let text_start = self.res.rfind('[').map(|i| i + 1).unwrap_or(0);
+                    self.res[text_start..].to_string()
+                };
+                self.references.push(label);
+                return written.into();
+            }
+        }
+
+        let url = if url.is_empty() || url.contains(|c: char| c.is_whitespace() || c == '(' || c == ')') {
+            format!("<{}>", url)
+        } else {
+            url.into()
+        };
+
+        if title.is_empty() {
+            format!("]({})", url)
+        } else {
+            format!("]({} \"{}\")", url, title.replace('"', "\\\""))
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.flush_text();
+        self.res.trim_end().into()
+    }
+}
+
+/// Escape the characters of `text` that would be read as Markdown syntax
+///
+/// If `line_start` is set, `text` starts a line, so block syntax like `# `,
+/// `> ` or `1. ` is escaped at its start, as it is after each line break.
+/// Pipes are only escaped `in_table`.
+fn escape(text: &str, line_start: bool, in_table: bool) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut escaped = String::with_capacity(text.len());
+    // Index of the character to escape because of the block syntax it starts
+    let mut marker = if line_start { block_marker(&chars) } else { None };
+
+    for (i, &c) in chars.iter().enumerate() {
+        let before = i.checked_sub(1).and_then(|i| chars.get(i)).cloned();
+        let after = chars.get(i + 1).cloned();
+        let is_word = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+
+        let escape = marker == Some(i) || match c {
+            '*' | '`' | '[' | ']' => true,
+            '_' => !(is_word(before) && is_word(after)),
+            '\\' => after.is_none_or(|c| c.is_ascii_punctuation()),
+            '<' => after.is_some_and(|c| c.is_alphabetic() || "/!?".contains(c)) &&
+                chars[i + 1..].contains(&'>'),
+            '&' => {
+                let name = chars[i + 1..].iter().take_while(|c| c.is_alphanumeric() || **c == '#').count();
+                name > 0 && chars.get(i + 1 + name) == Some(&';')
+            }
+            '|' => in_table,
+            _ => false,
+        };
+        if escape {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+
+        if c == '\n' {
+            marker = block_marker(&chars[i + 1..]).map(|marker| marker + i + 1);
+        }
+    }
+
+    escaped
+}
+
+/// Remove the backslashes `escape` added, e.g. to read an annotation written
+/// in the docs of an item
+pub fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(&next) if c == '\\' && next.is_ascii_punctuation() => {
+                unescaped.push(next);
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+
+    unescaped
+}
+
+/// The index of the character in `line` that would make it a heading, list
+/// item, block quote, thematic break or code fence, if any
+///
+/// Lines starting with whitespace are continuations of something else, as
+/// the text of paragraphs is written without indentation.
+fn block_marker(line: &[char]) -> Option<usize> {
+    let end = line.iter().position(|&c| c == '\n').unwrap_or(line.len());
+    let line = &line[..end];
+    let is_space = |c: Option<&char>| c.is_none_or(|c| c.is_whitespace());
+    let run = |c: char| line.iter().take_while(|&&x| x == c).count();
+    let only = |c: char| line.iter().all(|&x| x == c || x == ' ');
+
+    let marker = match line.first() {
+        Some('#') => run('#') <= 6 && is_space(line.get(run('#'))),
+        Some('>') => true,
+        Some('-') | Some('+') => is_space(line.get(1)) || only('-'),
+        Some('=') => only('='),
+        Some('~') => run('~') >= 3,
+        Some(c) if c.is_ascii_digit() => {
+            let digits = line.iter().take_while(|c| c.is_ascii_digit()).count();
+            return if digits <= 9 && (line.get(digits) == Some(&'.') || line.get(digits) == Some(&')')) &&
+                is_space(line.get(digits + 1)) {
+                Some(digits)
+            } else {
+                None
+            };
+        }
+        _ => false,
+    };
+
+    if marker {
+        Some(0)
+    } else {
+        None
+    }
+}
+
+/// A code fence that is longer than any run of `c` in `code`
+fn fence(c: char, code: &str) -> String {
+    let longest_run = code.split(|x| x != c).map(|run| run.len()).max().unwrap_or(0);
+    c.to_string().repeat(::std::cmp::max(3, longest_run + 1))
+}
+
+fn render_table(table: Table) -> String {
+    let row = |cells: &[String]| format!("| {} |", cells.join(" | "));
+
+    let mut rows = table.rows.iter().map(|cells| row(cells)).collect::<Vec<_>>();
+    let header_len = if rows.is_empty() { 0 } else { 1 };
+    rows.insert(header_len, row(&table.separator));
+    rows.join("\n")
+}
+
+/// Find the definition of a link reference (e.g. `[1]: http://example.com`)
+/// in `source`
+///
+/// Lines of code blocks are skipped, as are lines indented by four or more
+/// spaces, which are code as well.
+fn link_definition<'s>(source: &'s str, label: &str) -> Option<&'s str> {
+    let normalize = |label: &str| {
+        label.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+    };
+    let label = normalize(label);
+    // The character and length of the fence of the current code block
+    let mut fence: Option<(char, usize)> = None;
+
+    for line in source.lines() {
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        let line = line.trim();
+        let run = |c: char| line.chars().take_while(|&x| x == c).count();
+
+        if let Some((c, len)) = fence {
+            if indent < 4 && run(c) >= len && line.trim_start_matches(c).is_empty() {
+                fence = None;
+            }
+            continue;
+        }
+        if indent >= 4 {
+            continue;
+        }
+        if let Some(c) = line.chars().next().filter(|&c| c == '`' || c == '~') {
+            if run(c) >= 3 {
+                fence = Some((c, run(c)));
+                continue;
+            }
+        }
 
-            _ => {}
+        let is_definition = line.starts_with('[') &&
+            line.find("]:").map(|end| normalize(&line[1..end]) == label).unwrap_or(false);
+        if is_definition {
+            return Some(line);
         }
     }
 
-    res.trim_right().into()
+    None
 }
@@ -1,8 +1,4 @@
-/// A Rust identifier
-pub type Identifier = String;
-
-/// A Rust pattern, as used in pattern matching.
-pub type Pattern = String;
+use ::patterns::{Identifier, Pattern};
 
 /// Documentation, a Markdown `String`
 pub type Documentation = String;
@@ -11,30 +7,299 @@ pub type Documentation = String;
 pub type SectionHeadline = String;
 
 /// Information extracted from a doc comment
+///
+/// With the `serde` feature, this (like all types it contains) implements
+/// `Serialize` and `Deserialize`. Subsections are written as objects with a
+/// `headline` and a `section`, see `DocSection` for the shape of sections.
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DocBlock {
     /// First line
     pub teaser: String,
     /// Paragraphs after first line
+    ///
+    /// The definitions of reference-style links used anywhere in the doc
+    /// block (e.g. `[1]: http://example.com`) are at its end.
     pub description: Option<Documentation>,
+    /// Subsections of the description, i.e. parts of it starting with a
+    /// headline deeper than those of the sections (like `## Headline`)
+    ///
+    /// Each is a `Custom` section.
+    #[cfg_attr(feature = "serde", serde(with = "::serialize::subsections"))]
+    pub subsections: Vec<Subsection>,
     /// Sections
     pub sections: Vec<DocSection>,
 }
 
+/// A subsection: the headline, and the content up to the next headline of the
+/// same (or a higher) level
+///
+/// The content of a subsection is parsed like the content of the section it
+/// is in, e.g. a subsection of `Examples` is a `DocSection::Examples` (and a
+/// subsection of the description or of a custom section is a
+/// `DocSection::Custom` with an empty headline, as the headline is the
+/// subsection's). Subsections of subsections are part of that `DocSection`.
+pub type Subsection = (SectionHeadline, DocSection);
+
 /// Documentation sections
+///
+/// The last field of each variant holds its subsections, see `Subsection`.
+/// The other fields only hold content before the first of them.
+///
+/// # Serialization
+///
+/// With the `serde` feature, sections are serialized as objects with a `kind`
+/// (`parameters`, `type_parameters`, `lifetime_parameters`, `returns`,
+/// `examples`, `panics`, `errors`, `safety` or `custom`) and named fields.
+/// Identifiers and patterns are written as strings. In JSON:
+///
+/// ```json
+/// { "kind": "parameters", "parameters": [
+///     { "pattern": "timeout", "docs": "How long to wait", "ty": "Duration",
+///       "optional": true, "default": null, "constraints": [],
+///       "fields": [{ "name": "secs", "docs": "Seconds" }] }
+/// ], "subsections": [] }
+/// { "kind": "type_parameters", "parameters": [{ "name": "T", "docs": "..." }], "subsections": [] }
+/// { "kind": "lifetime_parameters", "parameters": [{ "name": "'a", "docs": "..." }], "subsections": [] }
+/// { "kind": "returns", "docs": "...", "variants": [{ "pattern": "Ok(_)", "docs": "..." }], "subsections": [] }
+/// { "kind": "examples", "examples": [{ "description": "...", "code_blocks": [
+///     { "language": "rust", "attributes": ["no_run"], "lines": [
+///         { "kind": "hidden", "text": "use foo::*;" },
+///         { "kind": "visible", "text": "foo();" }
+///     ] }
+/// ] }], "subsections": [] }
+/// { "kind": "panics", "docs": "...", "conditions": ["..."], "subsections": [] }
+/// { "kind": "errors", "docs": "...", "conditions": [{ "pattern": null, "docs": "..." }], "subsections": [] }
+/// { "kind": "safety", "docs": "...", "subsections": [] }
+/// { "kind": "custom", "headline": "Notes", "docs": "...", "subsections": [
+///     { "headline": "More notes", "section": { "kind": "custom", "headline": "More notes", "docs": "...", "subsections": [] } }
+/// ] }
+/// ```
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "::serialize::SectionRepr", into = "::serialize::SectionRepr"))]
 pub enum DocSection {
-    /// Function parameters, mapping param name to docs
-    Parameters(Vec<(Identifier, Documentation)>),
+    /// Function parameters, mapping param name (or the pattern it is bound
+    /// to) to docs
+    Parameters(Vec<(Pattern, ParameterDoc)>, Vec<Subsection>),
     /// Type parameters (generics), mapping ident of generic to docs
-    TypeParameters(Vec<(Identifier, Documentation)>),
+    TypeParameters(Vec<(Identifier, Documentation)>, Vec<Subsection>),
     /// Lifetime parameters, documenting the life and death of your times
-    LifetimeParameters(Vec<(Identifier, Documentation)>),
+    LifetimeParameters(Vec<(Identifier, Documentation)>, Vec<Subsection>),
     /// Return value documentation with optional list of enum variants.
-    Returns(Documentation, Vec<(Pattern, Documentation)>),
+    Returns(Documentation, Vec<(Pattern, Documentation)>, Vec<Subsection>),
+    /// Usage examples
+    Examples(Vec<Example>, Vec<Subsection>),
+    /// Conditions under which the function panics: Some text, followed by an
+    /// optional list of conditions
+    Panics(Documentation, Vec<Documentation>, Vec<Subsection>),
+    /// Conditions under which the function returns an error: Some text,
+    /// followed by an optional list of conditions
+    ///
+    /// List items written like `` `Pattern`: Docs `` (e.g., with an error
+    /// variant) are split into pattern and docs, all others are just docs.
+    Errors(Documentation, Vec<(Option<Pattern>, Documentation)>, Vec<Subsection>),
+    /// Invariants the caller of an `unsafe` function has to uphold
+    Safety(Documentation, Vec<Subsection>),
     /// Custom/unknown sections, mapping headlines to docs
     ///
     /// In the future, some of the sections currently treated as 'custom' may
-    /// be added as new variants, e.g. 'Examples', or 'Panics'.
-    Custom(SectionHeadline, Documentation),
+    /// be added as new variants.
+    Custom(SectionHeadline, Documentation, Vec<Subsection>),
+}
+
+/// Documentation of a function parameter
+///
+/// The identifier of a parameter may be followed by an annotation in
+/// parentheses, listing its type, whether it is optional, its default value
+/// and any other constraints:
+///
+/// ```markdown
+/// - `timeout` (Duration, optional, default: 30s, at least 1s): How long to wait
+/// ```
+///
+/// All parts of the annotation are optional. The first part is the type if it
+/// looks like one (e.g. `u32`, `HashMap<K, V>` or `&'a str`, but not
+/// `at least 1s`) or is written in backticks, and isn't `optional`,
+/// `required` or a default value (written as `default: value`,
+/// `default = value` or `defaults to value`). All other parts are
+/// constraints.
+///
+/// Parameters taking a struct (like a set of options) can document its fields
+/// in a nested list ending the item:
+///
+/// ```markdown
+/// - `options`: How to frobnicate
+///     - `depth`: How deep to go
+///     - `force`: Whether to go on after errors
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ParameterDoc {
+    /// Docs of the parameter, without the list of fields
+    pub docs: Documentation,
+    /// Type given in the annotation, without backticks
+    pub ty: Option<String>,
+    /// Whether the annotation says the parameter is `optional`
+    pub optional: bool,
+    /// Default value given in the annotation, without backticks
+    pub default: Option<String>,
+    /// All other parts of the annotation, e.g. `at least 1s`
+    pub constraints: Vec<String>,
+    /// Fields of the parameter, mapping field name to docs
+    #[cfg_attr(feature = "serde", serde(with = "::serialize::named_list"))]
+    pub fields: Vec<(Identifier, Documentation)>,
+}
+
+impl ParameterDoc {
+    /// Whether there is anything to write in an annotation
+    pub fn is_annotated(&self) -> bool {
+        self.ty.is_some() || self.optional || self.default.is_some() || !self.constraints.is_empty()
+    }
+}
+
+impl<'a> From<&'a str> for ParameterDoc {
+    fn from(docs: &'a str) -> ParameterDoc {
+        ParameterDoc::from(docs.to_string())
+    }
+}
+
+impl From<String> for ParameterDoc {
+    fn from(docs: Documentation) -> ParameterDoc {
+        ParameterDoc {
+            docs,
+            ..ParameterDoc::default()
+        }
+    }
+}
+
+/// One example of an `Examples` section
+///
+/// An example starts with some text (which may be missing) and ends with the
+/// last code block before the next paragraph of text.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Example {
+    /// Text explaining the example
+    pub description: Documentation,
+    /// The code of the example
+    pub code_blocks: Vec<CodeBlock>,
+}
+
+/// A fenced (or indented) code block
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CodeBlock {
+    /// Language given after the opening fence, e.g. `rust` or `text`
+    ///
+    /// Rustdoc treats code blocks without a language as Rust code.
+    pub language: Option<String>,
+    /// Rustdoc attributes given after the opening fence, e.g. `ignore`,
+    /// `no_run`, `should_panic`, `compile_fail` or `edition2018`
+    pub attributes: Vec<String>,
+    /// Lines of code
+    pub lines: Vec<CodeLine>,
+}
+
+/// A line of code in a `CodeBlock`
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "text", rename_all = "snake_case"))]
+pub enum CodeLine {
+    /// A regular line
+    Visible(String),
+    /// A line rustdoc compiles but does not show (written as `# line`),
+    /// without the `# ` marker but with its indentation
+    Hidden(String),
+}
+
+impl CodeBlock {
+    /// Whether rustdoc treats this as Rust code
+    pub fn is_rust(&self) -> bool {
+        match self.language {
+            None => true,
+            Some(ref language) => language == "rust",
+        }
+    }
+
+    /// Whether the given rustdoc attribute (e.g. `no_run`) is set
+    pub fn has_attribute(&self, attribute: &str) -> bool {
+        self.attributes.iter().any(|a| a == attribute)
+    }
+
+    /// The code as shown in the rendered documentation
+    pub fn visible_code(&self) -> String {
+        self.lines.iter()
+            .filter_map(|line| match *line {
+                CodeLine::Visible(ref line) => Some(&line[..]),
+                CodeLine::Hidden(_) => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The code as compiled by rustdoc, including hidden lines
+    pub fn code(&self) -> String {
+        self.lines.iter()
+            .map(|line| match *line {
+                CodeLine::Visible(ref line) | CodeLine::Hidden(ref line) => &line[..],
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// The kinds of sections with a dedicated `DocSection` variant
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SectionKind {
+    /// `DocSection::Parameters`
+    Parameters,
+    /// `DocSection::TypeParameters`
+    TypeParameters,
+    /// `DocSection::LifetimeParameters`
+    LifetimeParameters,
+    /// `DocSection::Returns`
+    Returns,
+    /// `DocSection::Examples`
+    Examples,
+    /// `DocSection::Panics`
+    Panics,
+    /// `DocSection::Errors`
+    Errors,
+    /// `DocSection::Safety`
+    Safety,
+}
+
+impl DocSection {
+    /// Subsections of this section
+    pub fn subsections(&self) -> &[Subsection] {
+        match *self {
+            DocSection::Parameters(_, ref subsections) |
+            DocSection::TypeParameters(_, ref subsections) |
+            DocSection::LifetimeParameters(_, ref subsections) |
+            DocSection::Returns(_, _, ref subsections) |
+            DocSection::Examples(_, ref subsections) |
+            DocSection::Panics(_, _, ref subsections) |
+            DocSection::Errors(_, _, ref subsections) |
+            DocSection::Safety(_, ref subsections) |
+            DocSection::Custom(_, _, ref subsections) => subsections,
+        }
+    }
+
+    /// The kind of this section, `None` for `Custom` sections
+    pub fn kind(&self) -> Option<SectionKind> {
+        match *self {
+            DocSection::Parameters(..) => Some(SectionKind::Parameters),
+            DocSection::TypeParameters(..) => Some(SectionKind::TypeParameters),
+            DocSection::LifetimeParameters(..) => Some(SectionKind::LifetimeParameters),
+            DocSection::Returns(..) => Some(SectionKind::Returns),
+            DocSection::Examples(..) => Some(SectionKind::Examples),
+            DocSection::Panics(..) => Some(SectionKind::Panics),
+            DocSection::Errors(..) => Some(SectionKind::Errors),
+            DocSection::Safety(..) => Some(SectionKind::Safety),
+            DocSection::Custom(..) => None,
+        }
+    }
 }
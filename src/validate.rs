@@ -0,0 +1,474 @@
+use ::std::collections::HashMap;
+use ::syn;
+use ::syn::ext::IdentExt;
+
+use ::types::*;
+use ::patterns::{self, Pattern};
+use ::errors::ValidationError;
+use ::span::{Position, SectionSpans, SpannedDocBlock};
+
+/// Check the `Parameters` section of a function's docs against its signature
+///
+/// Parameters bound to a plain identifier are matched by their name (so
+/// `mut x` may be documented as `x`), all others by their pattern (e.g.
+/// `(a, b)`). Parameters whose name starts with `_` don't need to be
+/// documented (but may be), `self` and `_` never do. Docs without a
+/// `Parameters` section are not checked.
+///
+/// # Parameters
+///
+/// - `sig`: The function's signature
+/// - `doc`: The function's documentation
+///
+/// # Returns
+///
+/// All problems found, i.e. undocumented parameters, documented parameters
+/// that don't exist, parameters documented out of order and parameters
+/// documented more than once. The positions in these errors are unknown, use
+/// `validate_parameters_spanned` to get them.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate syn;
+/// # extern crate docstrings;
+/// # use docstrings::*;
+/// # fn main() {
+/// let sig: syn::Signature = syn::parse_str("fn foo(bar: u32, baz: u32)").unwrap();
+/// let doc = parse_md_docblock("Lorem ipsum\n\n# Parameters\n\n- `bar`: Bar\n- `quux`: Quux\n").unwrap();
+///
+/// let names: Vec<_> = validate_parameters(&sig, &doc).iter()
+///     .map(|error| error.name().to_string())
+///     .collect();
+/// assert_eq!(names, vec!["quux", "baz"]);
+/// # }
+/// ```
+pub fn validate_parameters(sig: &syn::Signature, doc: &DocBlock) -> Vec<ValidationError> {
+    validate_parameters_spanned(sig, &unspanned(doc))
+}
+
+/// Check the `Parameters` section of a function's docs against its signature,
+/// with the locations of the problems
+///
+/// # Parameters
+///
+/// - `sig`: The function's signature
+/// - `doc`: The function's documentation, as returned by
+///   `parse_md_docblock_spanned`
+///
+/// # Returns
+///
+/// Same as `validate_parameters`, but with positions: Problems with a
+/// documented parameter point to its identifier, undocumented parameters to
+/// the headline of the `Parameters` section.
+pub fn validate_parameters_spanned(sig: &syn::Signature, doc: &SpannedDocBlock) -> Vec<ValidationError> {
+    let parameters: Vec<String> = sig.inputs.iter()
+        .filter_map(|input| match *input {
+            syn::FnArg::Typed(ref typed) => patterns::from_syn(&typed.pat),
+            syn::FnArg::Receiver(_) => None,
+        })
+        .map(|pattern| parameter_key(&pattern))
+        .collect();
+    let (documented, section_position) = documented(doc, |section| match *section {
+        DocSection::Parameters(ref list, _) => Some(list),
+        _ => None,
+    });
+    let mut errors = vec![];
+    let section_position = match section_position {
+        Some(position) => position,
+        None => return errors,
+    };
+
+    // Documented parameters, in the order they are declared
+    let mut seen: Vec<String> = vec![];
+    let mut last_index = None;
+    for &(pattern, position) in &documented {
+        let name = parameter_key(pattern);
+        if seen.contains(&name) {
+            errors.push(ValidationError::DuplicateParameter(name, position));
+            continue;
+        }
+
+        match parameters.iter().position(|parameter| *parameter == name) {
+            None => errors.push(ValidationError::UnknownParameter(name.clone(), position)),
+            Some(index) => {
+                if last_index.map(|last| index < last).unwrap_or(false) {
+                    errors.push(ValidationError::MisorderedParameter(name.clone(), position));
+                } else {
+                    last_index = Some(index);
+                }
+            }
+        }
+        seen.push(name);
+    }
+
+    for parameter in &parameters {
+        if !parameter.starts_with('_') && !seen.contains(parameter) {
+            errors.push(ValidationError::UndocumentedParameter(parameter.clone(), section_position));
+        }
+    }
+
+    errors
+}
+
+/// Check the `Type Parameters` and `Lifetime Parameters` sections of an item's
+/// docs against its generics
+///
+/// Const generics are expected in the `Type Parameters` section. Lifetimes may
+/// be documented with or without their leading `'`. Each of the two sections
+/// is only checked if the docs have it.
+///
+/// # Parameters
+///
+/// - `generics`: The generics of the function, struct, enum or trait
+/// - `doc`: The item's documentation
+///
+/// # Returns
+///
+/// All undocumented type parameters and lifetimes as well as documented ones
+/// that don't exist, without positions. Use `validate_generics_spanned` to get
+/// them.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate syn;
+/// # extern crate docstrings;
+/// # use docstrings::*;
+/// # fn main() {
+/// let item: syn::ItemStruct = syn::parse_str("struct Foo<'a, T, const N: usize>(&'a [T; N]);").unwrap();
+/// let md = "Lorem ipsum\n\n# Type Parameters\n\n- `T`: Tee\n- `N`: Size\n\n# Lifetimes\n\n- `a`: Slice\n";
+/// let doc = parse_md_docblock(md).unwrap();
+///
+/// assert!(validate_generics(&item.generics, &doc).is_empty());
+/// # }
+/// ```
+pub fn validate_generics(generics: &syn::Generics, doc: &DocBlock) -> Vec<ValidationError> {
+    validate_generics_spanned(generics, &unspanned(doc))
+}
+
+/// Check the `Type Parameters` and `Lifetime Parameters` sections of an item's
+/// docs against its generics, with the locations of the problems
+///
+/// # Parameters
+///
+/// - `generics`: The generics of the function, struct, enum or trait
+/// - `doc`: The item's documentation, as returned by
+///   `parse_md_docblock_spanned`
+///
+/// # Returns
+///
+/// Same as `validate_generics`, with positions like those of
+/// `validate_parameters_spanned`.
+pub fn validate_generics_spanned(generics: &syn::Generics, doc: &SpannedDocBlock) -> Vec<ValidationError> {
+    let mut errors = vec![];
+
+    let type_parameters: Vec<String> = generics.params.iter()
+        .filter_map(|param| match *param {
+            syn::GenericParam::Type(ref param) => Some(param.ident.unraw().to_string()),
+            syn::GenericParam::Const(ref param) => Some(param.ident.unraw().to_string()),
+            syn::GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+    let (documented_types, types_position) = documented(doc, |section| match *section {
+        DocSection::TypeParameters(ref list, _) => Some(list),
+        _ => None,
+    });
+
+    if let Some(types_position) = types_position {
+        for &(ident, position) in &documented_types {
+            if !type_parameters.iter().any(|name| name == ident.name()) {
+                errors.push(ValidationError::UnknownTypeParameter(ident.to_string(), position));
+            }
+        }
+        for name in &type_parameters {
+            if !documented_types.iter().any(|&(ident, _)| ident.name() == name) {
+                errors.push(ValidationError::UndocumentedTypeParameter(name.clone(), types_position));
+            }
+        }
+    }
+
+    let lifetimes: Vec<String> = generics.lifetimes()
+        .map(|param| param.lifetime.ident.to_string())
+        .collect();
+    let (documented_lifetimes, lifetimes_position) = documented(doc, |section| match *section {
+        DocSection::LifetimeParameters(ref list, _) => Some(list),
+        _ => None,
+    });
+
+    if let Some(lifetimes_position) = lifetimes_position {
+        for &(ident, position) in &documented_lifetimes {
+            if !lifetimes.iter().any(|name| name == ident.name()) {
+                errors.push(ValidationError::UnknownLifetime(ident.to_string(), position));
+            }
+        }
+        for name in &lifetimes {
+            if !documented_lifetimes.iter().any(|&(ident, _)| ident.name() == name) {
+                errors.push(ValidationError::UndocumentedLifetime(format!("'{}", name), lifetimes_position));
+            }
+        }
+    }
+
+    errors
+}
+
+/// Check the patterns in the `Returns` section of a function's docs against
+/// its return type
+///
+/// Only functions returning a `Result`, an `Option`, or one of the given enums
+/// are checked, and only if their `Returns` section lists patterns at all.
+/// A `_` pattern documents all remaining variants.
+///
+/// # Parameters
+///
+/// - `sig`: The function's signature
+/// - `doc`: The function's documentation
+/// - `enums`: Names of the variants of enums the function may return, by the
+///   path of the enum as written in the signature, e.g. `Answer` or
+///   `answers::Answer` (see `local_enums`)
+///
+/// # Returns
+///
+/// All variants that are not documented and documented variants the return
+/// type does not have, without positions. Use `validate_returns_spanned` to
+/// get them.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate syn;
+/// # extern crate docstrings;
+/// # use docstrings::*;
+/// # use std::collections::HashMap;
+/// # fn main() {
+/// let sig: syn::Signature = syn::parse_str("fn foo() -> Option<u32>").unwrap();
+/// let doc = parse_md_docblock("Lorem ipsum\n\n# Returns\n\n- `Some(x)`: X\n- `Ok(_)`: Oops\n").unwrap();
+///
+/// let names: Vec<_> = validate_returns(&sig, &doc, &HashMap::new()).iter()
+///     .map(|error| error.name().to_string())
+///     .collect();
+/// assert_eq!(names, vec!["Ok", "None"]);
+/// # }
+/// ```
+pub fn validate_returns(sig: &syn::Signature, doc: &DocBlock, enums: &HashMap<String, Vec<String>>) -> Vec<ValidationError> {
+    validate_returns_spanned(sig, &unspanned(doc), enums)
+}
+
+/// Check the patterns in the `Returns` section of a function's docs against
+/// its return type, with the locations of the problems
+///
+/// # Parameters
+///
+/// - `sig`: The function's signature
+/// - `doc`: The function's documentation, as returned by
+///   `parse_md_docblock_spanned`
+/// - `enums`: Names of the variants of enums the function may return, by the
+///   path of the enum as written in the signature
+///
+/// # Returns
+///
+/// Same as `validate_returns`, with positions like those of
+/// `validate_parameters_spanned`.
+pub fn validate_returns_spanned(sig: &syn::Signature, doc: &SpannedDocBlock, enums: &HashMap<String, Vec<String>>) -> Vec<ValidationError> {
+    let mut errors = vec![];
+
+    let variants = match return_variants(&sig.output, enums) {
+        Some(variants) => variants,
+        None => return errors,
+    };
+    let (documented, section_position) = documented(doc, |section| match *section {
+        DocSection::Returns(_, ref list, _) => Some(list),
+        _ => None,
+    });
+    let section_position = match section_position {
+        Some(position) if !documented.is_empty() => position,
+        _ => return errors,
+    };
+
+    let mut documented_variants = vec![];
+    let mut wildcard = false;
+    for &(pattern, position) in &documented {
+        for name in pattern_variants(pattern) {
+            match name {
+                Some(name) => {
+                    if !variants.contains(&name) {
+                        errors.push(ValidationError::UnknownReturnVariant(name.clone(), position));
+                    }
+                    documented_variants.push(name);
+                }
+                None => wildcard = true,
+            }
+        }
+    }
+
+    if !wildcard {
+        for variant in variants {
+            if !documented_variants.contains(&variant) {
+                errors.push(ValidationError::UndocumentedReturnVariant(variant, section_position));
+            }
+        }
+    }
+
+    errors
+}
+
+/// Names of the variants of all enums in `items`, by the path of the enum
+/// (see `SourceItem::path`)
+///
+/// `SourceItem::validate` resolves the return types of functions against
+/// these paths. To call `validate_returns` directly, the keys need to be the
+/// paths as written in the signature instead, see `enums_in_scope`.
+pub fn local_enums(items: &[::source::SourceItem]) -> HashMap<String, Vec<String>> {
+    items.iter()
+        .filter(|item| item.kind == ::source::ItemKind::Enum)
+        .map(|item| (item.path.clone(), item.variants.clone()))
+        .collect()
+}
+
+/// The enums of `local_enums` by the paths they can be written with in the
+/// module `module` (e.g. `foo::bar`, empty for the crate root)
+///
+/// These are the paths relative to `module` (and with `self::`, `super::` or
+/// `crate::`) and from the crate root. Enums declared in another module are
+/// also found by their bare name if no other enum has that name, as they are
+/// probably imported.
+pub fn enums_in_scope(enums: &HashMap<String, Vec<String>>, module: &str) -> HashMap<String, Vec<String>> {
+    let parent = module.rfind("::").map(|end| &module[..end]).unwrap_or("");
+    let mut in_scope = HashMap::new();
+
+    for (path, variants) in enums {
+        let (enum_module, name) = match path.rfind("::") {
+            Some(end) => (&path[..end], &path[end + 2..]),
+            None => ("", &path[..]),
+        };
+        let mut paths = vec![path.clone(), format!("::{}", path), format!("crate::{}", path)];
+
+        let relative = if module.is_empty() {
+            Some(&path[..])
+        } else if path.starts_with(module) && path[module.len()..].starts_with("::") {
+            Some(&path[module.len() + 2..])
+        } else {
+            None
+        };
+        if let Some(relative) = relative {
+            paths.push(relative.to_string());
+            paths.push(format!("self::{}", relative));
+        }
+        if !module.is_empty() && enum_module == parent {
+            paths.push(format!("super::{}", name));
+        }
+        let unique = enums.keys().filter(|other| other.rsplit("::").next() == Some(name)).count() == 1;
+        if unique {
+            paths.push(name.to_string());
+        }
+
+        for path in paths {
+            in_scope.insert(path, variants.clone());
+        }
+    }
+
+    in_scope
+}
+
+/// Variants of the return type, if it is a `Result`, an `Option` or one of
+/// `enums`
+fn return_variants(output: &syn::ReturnType, enums: &HashMap<String, Vec<String>>) -> Option<Vec<String>> {
+    let ty = match *output {
+        syn::ReturnType::Type(_, ref ty) => ty,
+        syn::ReturnType::Default => return None,
+    };
+    let path = match **ty {
+        syn::Type::Path(ref path) => &path.path,
+        _ => return None,
+    };
+    let name = match path.segments.last() {
+        Some(segment) => segment.ident.to_string(),
+        None => return None,
+    };
+
+    match &name[..] {
+        "Result" => Some(vec!["Ok".into(), "Err".into()]),
+        "Option" => Some(vec!["Some".into(), "None".into()]),
+        _ => {
+            let segments: Vec<String> = path.segments.iter().map(|segment| segment.ident.to_string()).collect();
+            let prefix = if path.leading_colon.is_some() { "::" } else { "" };
+            enums.get(&format!("{}{}", prefix, segments.join("::"))).cloned()
+        }
+    }
+}
+
+/// Variants a pattern matches, `None` for a wildcard
+fn pattern_variants(pattern: &Pattern) -> Vec<Option<String>> {
+    match *pattern {
+        Pattern::Or(ref cases) => cases.iter().flat_map(pattern_variants).collect(),
+        Pattern::Ident { subpattern: Some(ref subpattern), .. } => pattern_variants(subpattern),
+        Pattern::Wild => vec![None],
+        _ => pattern.path()
+            .and_then(|path| path.rsplit("::").next())
+            .map(|name| vec![Some(name.to_string())])
+            .unwrap_or_default(),
+    }
+}
+
+/// What a parameter is documented as: The name of a plain binding, or the
+/// whole pattern
+fn parameter_key(pattern: &Pattern) -> String {
+    match pattern.binding() {
+        Some(ident) => ident.name().to_string(),
+        None => pattern.to_string(),
+    }
+}
+
+/// A `SpannedDocBlock` with unknown locations, to validate a plain `DocBlock`
+fn unspanned(doc: &DocBlock) -> SpannedDocBlock {
+    SpannedDocBlock {
+        doc: doc.clone(),
+        teaser: Default::default(),
+        description: None,
+        subsections: vec![],
+        sections: vec![],
+    }
+}
+
+/// Identifiers (or patterns) documented in all sections `list` returns a list
+/// for (and their subsections), with their positions, and where to report
+/// things missing from these sections: The headline of the first of them, or
+/// `None` if there is no such section
+fn documented<'d, T, D: 'd, F>(doc: &'d SpannedDocBlock, list: F) -> (Vec<(&'d T, Position)>, Option<Position>) where
+    F: Fn(&'d DocSection) -> Option<&'d Vec<(T, D)>>,
+{
+    let mut section_position = None;
+    let mut documented = vec![];
+
+    for (i, section) in doc.doc.sections.iter().enumerate() {
+        if list(section).is_none() {
+            continue;
+        }
+
+        let spans = doc.sections.get(i);
+        let headline = spans.map(|spans| spans.headline.start).unwrap_or_default();
+        section_position = section_position.or(Some(headline));
+        documented_in(section, spans, &list, &mut documented);
+    }
+
+    (documented, section_position)
+}
+
+/// Collect identifiers documented in a section and its subsections
+fn documented_in<'d, T, D: 'd, F>(section: &'d DocSection, spans: Option<&SectionSpans>, list: &F, documented: &mut Vec<(&'d T, Position)>) where
+    F: Fn(&'d DocSection) -> Option<&'d Vec<(T, D)>>,
+{
+    if let Some(list) = list(section) {
+        for (j, (ident, _)) in list.iter().enumerate() {
+            let position = spans.and_then(|spans| spans.items.get(j))
+                .map(|item| item.ident.start)
+                .unwrap_or_default();
+            documented.push((ident, position));
+        }
+    }
+
+    for (k, (_, subsection)) in section.subsections().iter().enumerate() {
+        let spans = spans.and_then(|spans| spans.subsections.get(k));
+        documented_in(subsection, spans, list, documented);
+    }
+}
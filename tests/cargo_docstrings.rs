@@ -0,0 +1,258 @@
+#![cfg(feature = "cli")]
+
+extern crate serde_json;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Write a workspace (given as file paths and contents) to a fresh directory
+fn workspace(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    for &(path, content) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    dir
+}
+
+fn check(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cargo-docstrings"))
+        .args(["docstrings", "check", "--manifest-path"])
+        .arg(dir.join("Cargo.toml"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+const WORKSPACE: &[(&str, &str)] = &[
+    ("Cargo.toml", "[workspace]\nmembers = [\"good\", \"bad\", \"ignored\"]\n"),
+    ("good/Cargo.toml", "[package]\nname = \"good\"\nversion = \"0.1.0\"\n"),
+    ("good/src/lib.rs", "\
+/// Add numbers
+///
+/// # Parameters
+///
+/// - `a`: A number
+/// - `b`: Another number
+pub fn add(a: u32, b: u32) -> u32 { a + b }
+"),
+    ("bad/Cargo.toml", "\
+[package]
+name = \"bad\"
+version = \"0.2.0\"
+
+[package.metadata.docstrings]
+exclude = [\"generated\"]
+"),
+    ("bad/src/lib.rs", "\
+pub mod generated;
+mod hidden;
+
+/// Frobnicate
+///
+/// # Parameters
+///
+/// - `x`: X
+/// - `y`: Y
+pub fn frob(x: u32) {}
+"),
+    ("bad/src/generated.rs", "/// Broken\n///\n/// # Parameters\n///\n/// - `z`: Z\npub fn broken() {}\n"),
+    ("bad/src/hidden.rs", "/// Hidden\n///\n/// # Parameters\n///\n/// - `z`: Z\npub fn hidden() {}\n"),
+    ("ignored/Cargo.toml", "\
+[package]
+name = \"ignored\"
+version = \"0.1.0\"
+
+[package.metadata.docstrings]
+skip = true
+"),
+    ("ignored/src/lib.rs", "/// Broken\n///\n/// # Parameters\n///\n/// - `z`: Z\npub fn broken() {}\n"),
+];
+
+#[test]
+fn diagnostics() {
+    let dir = workspace("cargo_docstrings_diagnostics", WORKSPACE);
+    let output = check(&dir, &[]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stderr(&output), concat!(
+        "    Checking good v0.1.0\n",
+        "    Checking bad v0.2.0\n",
+        "error[DS0102]: Documented parameter `y` does not exist\n",
+        " --> bad/src/lib.rs:9:8\n",
+        "  |\n",
+        "9 | /// - `y`: Y\n",
+        "  |        ^ not a parameter\n",
+        "  = note: in the docs of `frob`\n",
+        "  = help: remove it, or fix its name\n",
+        "\n",
+        "error: found 1 documentation error in 1 crate\n",
+    ));
+}
+
+#[test]
+fn private_items() {
+    let dir = workspace("cargo_docstrings_private", WORKSPACE);
+    let output = check(&dir, &["--private", "--package", "bad"]);
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = stderr(&output);
+    assert!(stderr.contains(" --> bad/src/hidden.rs:5:8\n"));
+    assert!(!stderr.contains("generated.rs"));
+    assert!(stderr.ends_with("error: found 2 documentation errors in 1 crate\n"));
+}
+
+#[test]
+fn exclude() {
+    let dir = workspace("cargo_docstrings_exclude", WORKSPACE);
+    let output = check(&dir, &["--exclude", "bad"]);
+    assert!(output.status.success());
+    assert_eq!(stderr(&output), "    Checking good v0.1.0\n");
+
+    let mut files = WORKSPACE.to_vec();
+    files[0] = ("Cargo.toml", "\
+[workspace]
+members = [\"good\", \"bad\", \"ignored\"]
+
+[workspace.metadata.docstrings]
+exclude = [\"bad\"]
+");
+    let dir = workspace("cargo_docstrings_exclude_config", &files);
+    assert!(check(&dir, &[]).status.success());
+}
+
+#[test]
+fn allow() {
+    let dir = workspace("cargo_docstrings_allow", WORKSPACE);
+    let output = check(&dir, &["--allow", "DS0102"]);
+    assert!(output.status.success());
+    assert_eq!(stderr(&output), "    Checking good v0.1.0\n    Checking bad v0.2.0\n");
+
+    let mut files = WORKSPACE.to_vec();
+    files[3] = ("bad/Cargo.toml", "\
+[package]
+name = \"bad\"
+version = \"0.2.0\"
+
+[package.metadata.docstrings]
+allow = [\"DS0102\"]
+");
+    let dir = workspace("cargo_docstrings_allow_config", &files);
+    assert!(check(&dir, &[]).status.success());
+}
+
+#[test]
+fn warnings() {
+    let mut files = WORKSPACE.to_vec();
+    files[2] = ("good/src/lib.rs", "\
+/// Add numbers
+///
+/// # Parameters
+///
+/// - `b`: Another number
+/// - `a`: A number
+pub fn add(a: u32, b: u32) -> u32 { a + b }
+");
+    let dir = workspace("cargo_docstrings_warnings", &files);
+    let output = check(&dir, &["--package", "good"]);
+
+    assert!(output.status.success());
+    let stderr = stderr(&output);
+    assert!(stderr.contains("warning[DS0103]: Parameter `a` is documented out of order\n"));
+    assert!(!stderr.contains("error"));
+}
+
+#[test]
+fn json_messages() {
+    let dir = workspace("cargo_docstrings_json_messages", WORKSPACE);
+    let output = check(&dir, &["--message-format", "json"]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let stdout = stdout(&output);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 1);
+
+    let json: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+    assert_eq!(json["file"], "bad/src/lib.rs");
+    assert_eq!(json["item"], "frob");
+    assert_eq!(json["code"], "DS0102");
+    assert_eq!(json["severity"], "error");
+    assert_eq!(json["labels"][0]["span"]["start"]["line"], 9);
+    assert_eq!(json["labels"][0]["span"]["start"]["column"], 8);
+    assert_eq!(json["labels"][0]["span"]["end"]["column"], 9);
+    assert_eq!(json["labels"][0]["primary"], true);
+}
+
+#[test]
+fn usage() {
+    let dir = workspace("cargo_docstrings_usage", WORKSPACE);
+    assert_eq!(check(&dir, &["--frobnicate"]).status.code(), Some(2));
+    assert_eq!(check(&dir, &["--package", "nope"]).status.code(), Some(2));
+    assert_eq!(check(&dir, &["--message-format", "xml"]).status.code(), Some(2));
+    assert_eq!(coverage(&dir, &["--allow", "DS0102"]).status.code(), Some(2));
+}
+
+fn coverage(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cargo-docstrings"))
+        .args(["docstrings", "coverage", "--manifest-path"])
+        .arg(dir.join("Cargo.toml"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn coverage_table() {
+    let dir = workspace("cargo_docstrings_coverage_table", WORKSPACE);
+    let output = coverage(&dir, &[]);
+
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), concat!(
+        "Crate  Functions      Teaser  Parameters     Returns    Examples    Complete\n",
+        "good           1      100.0%      100.0%        0.0%        0.0%        0.0%\n",
+        "bad            1      100.0%      100.0%      100.0%        0.0%        0.0%\n",
+        "Total          2      100.0%      100.0%        0.0%        0.0%        0.0%\n",
+    ));
+}
+
+#[test]
+fn coverage_json_and_html() {
+    let dir = workspace("cargo_docstrings_coverage_json", WORKSPACE);
+
+    let output = coverage(&dir, &["--format", "json", "--private"]);
+    let json: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(json["crates"][1]["name"], "bad");
+    assert_eq!(json["crates"][1]["summary"]["functions"], 2);
+    assert_eq!(json["crates"][1]["functions"][1]["path"], "hidden::hidden");
+    assert_eq!(json["crates"][1]["functions"][1]["parameters"], serde_json::Value::Null);
+    assert_eq!(json["total"]["functions"], 3);
+
+    let output = coverage(&dir, &["-f", "html"]);
+    let html = stdout(&output);
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<tr><td><code>add</code></td><td class=\"yes\">yes</td>"));
+}
+
+#[test]
+fn coverage_threshold() {
+    let dir = workspace("cargo_docstrings_coverage_threshold", WORKSPACE);
+
+    assert!(coverage(&dir, &["--fail-under", "0"]).status.success());
+
+    let output = coverage(&dir, &["--fail-under", "50%"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stderr(&output), "error: 0.0% of all functions are completely documented, less than 50.0%\n");
+
+    assert_eq!(check(&dir, &["--fail-under", "50"]).status.code(), Some(2));
+}
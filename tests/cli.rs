@@ -0,0 +1,125 @@
+#![cfg(feature = "cli")]
+
+extern crate serde_json;
+
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+const DOC: &str = "Lorem ipsum\n\n# Parameters\n\n- `a`: A\n";
+
+const SOURCE: &str = "\
+/// Lorem ipsum
+///
+/// # Parameters
+///
+/// - `x`: X
+pub fn foo(x: u32) {}
+
+/// Broken
+///
+/// # Parameters
+///
+/// - x: X
+pub fn bar(x: u32) {}
+";
+
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_docstrings"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+fn source_file(name: &str, source: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    File::create(&path).unwrap().write_all(source.as_bytes()).unwrap();
+    path
+}
+
+#[test]
+fn json_from_stdin() {
+    let output = run(&[], DOC);
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(json[0]["file"], "<stdin>");
+    assert_eq!(json[0]["item"], serde_json::Value::Null);
+    assert_eq!(json[0]["doc"]["teaser"], "Lorem ipsum");
+    assert_eq!(json[0]["doc"]["sections"][0]["kind"], "parameters");
+    assert_eq!(json[0]["doc"]["sections"][0]["parameters"][0]["pattern"], "a");
+}
+
+#[test]
+fn markdown_and_debug() {
+    let output = run(&["--format", "markdown", "-"], "Lorem ipsum\n\n# Parameters\n\n* `a`: A\n");
+    assert_eq!(stdout(&output), DOC);
+
+    let output = run(&["-f", "debug"], DOC);
+    assert!(stdout(&output).starts_with("DocBlock {\n    teaser: \"Lorem ipsum\","));
+}
+
+#[test]
+fn rust_source() {
+    let path = source_file("cli_rust_source.rs", &SOURCE[..SOURCE.find("/// Broken").unwrap()]);
+    let output = run(&[path.to_str().unwrap()], "");
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 1);
+    assert_eq!(json[0]["item"]["path"], "foo");
+    assert_eq!(json[0]["item"]["kind"], "function");
+    assert_eq!(json[0]["item"]["signature"], "pub fn foo(x: u32)");
+    assert_eq!(json[0]["doc"]["sections"][0]["parameters"][0]["pattern"], "x");
+
+    let output = run(&["--rust", "-f", "md"], "/// Foo\npub fn foo() {}\n\n/// Bar\npub fn bar() {}\n");
+    assert_eq!(stdout(&output), "==> <stdin>: `foo` <==\nFoo\n\n==> <stdin>: `bar` <==\nBar\n\n");
+}
+
+#[test]
+fn parse_errors() {
+    let output = run(&[], "Lorem ipsum\n\n# Parameters\n\n- a: A\n");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stderr(&output), "<stdin>:5:1: No identifier in list item \"a: A\" of section `Parameters`\n");
+
+    let output = run(&["--rust"], SOURCE);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stderr(&output),
+               "<stdin>:12:5: No identifier in list item \"x: X\" of section `Parameters` (in the docs of `bar`)\n");
+
+    // The docs of `foo` are fine and still get printed
+    let json: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 1);
+    assert_eq!(json[0]["item"]["path"], "foo");
+
+    let output = run(&["--rust"], "fn foo(");
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn usage() {
+    let output = run(&["--help"], "");
+    assert!(output.status.success());
+    assert!(stdout(&output).starts_with("Usage: docstrings"));
+
+    let output = run(&["--format", "yaml"], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).starts_with("docstrings: Unknown format `yaml`"));
+
+    let output = run(&["does/not/exist.md"], "");
+    assert_eq!(output.status.code(), Some(1));
+}
@@ -0,0 +1,111 @@
+#![cfg(feature = "source")]
+
+extern crate docstrings;
+use docstrings::*;
+
+const SOURCE: &str = r#"
+/// Complete
+///
+/// # Parameters
+///
+/// - `x`: X
+///
+/// # Returns
+///
+/// Twice `x`
+///
+/// # Examples
+///
+/// ```rust
+/// assert_eq!(double(2), 4);
+/// ```
+pub fn double(x: u32) -> u32 { x * 2 }
+
+/// Missing a parameter and examples
+///
+/// # Parameters
+///
+/// - `x`: X
+pub fn add(x: u32, y: u32) -> u32 { x + y }
+
+pub fn undocumented(&self) {}
+
+/// Not a function
+pub struct Foo;
+
+impl Foo {
+    /// Nothing to document but examples
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// Foo.frob();
+    /// ```
+    pub fn frob(&self) -> () {}
+
+    /// Private
+    fn private(&self) {}
+}
+"#;
+
+fn coverage(path: &str) -> FunctionCoverage {
+    let items = parse_rust_source(SOURCE).unwrap();
+    let item = items.iter().find(|item| item.path == path).unwrap();
+    FunctionCoverage::of(item).unwrap()
+}
+
+#[test]
+fn functions() {
+    assert_eq!(coverage("double"), FunctionCoverage {
+        path: "double".into(),
+        teaser: true,
+        parameters: Some(true),
+        returns: Some(true),
+        examples: true,
+    });
+    assert!(coverage("double").is_complete());
+
+    assert_eq!(coverage("add"), FunctionCoverage {
+        path: "add".into(),
+        teaser: true,
+        parameters: Some(false),
+        returns: Some(false),
+        examples: false,
+    });
+
+    assert_eq!(coverage("undocumented"), FunctionCoverage {
+        path: "undocumented".into(),
+        teaser: false,
+        parameters: None,
+        returns: None,
+        examples: false,
+    });
+
+    assert!(coverage("Foo::frob").is_complete());
+}
+
+#[test]
+fn other_items() {
+    let items = parse_rust_source(SOURCE).unwrap();
+    let foo = items.iter().find(|item| item.path == "Foo").unwrap();
+    assert_eq!(FunctionCoverage::of(foo), None);
+}
+
+#[test]
+fn summary() {
+    let items = parse_rust_source(SOURCE).unwrap();
+    let coverage = Coverage::of_public(&items);
+
+    let paths: Vec<_> = coverage.functions.iter().map(|function| &function.path[..]).collect();
+    assert_eq!(paths, vec!["double", "add", "undocumented", "Foo::frob"]);
+    assert_eq!(coverage.summary(), CoverageSummary {
+        functions: 4,
+        teaser: 75.0,
+        parameters: 50.0,
+        returns: 50.0,
+        examples: 50.0,
+        complete: 50.0,
+    });
+
+    assert_eq!(Coverage::default().summary().complete, 100.0);
+}
@@ -0,0 +1,306 @@
+extern crate docstrings;
+use docstrings::*;
+
+fn diagnose(md: &str) -> Diagnostic {
+    parse_md_docblock(md).unwrap_err().to_diagnostic(md)
+}
+
+/// Text, message and whether it is primary of each label
+fn labelled<'a>(md: &'a str, diagnostic: &'a Diagnostic) -> Vec<(&'a str, &'a str, bool)> {
+    diagnostic.labels.iter()
+        .map(|label| (&md[label.span.range()], &label.message[..], label.primary))
+        .collect()
+}
+
+#[test]
+fn message_and_display() {
+    let md = "Lorem ipsum\n\n# Parameters\n\n- param: Foo\n";
+    let error = parse_md_docblock(md).unwrap_err();
+
+    assert_eq!(error.code(), "DS0004");
+    assert_eq!(error.message(), "No identifier in list item \"param: Foo\" of section `Parameters`");
+    assert_eq!(error.to_string(), format!("5:1: {}", error.message()));
+    assert_eq!(error.to_diagnostic(md).to_string(), format!("error[DS0004]: {}", error.message()));
+}
+
+#[test]
+fn no_ident() {
+    let md = "Lorem ipsum\n\n# Parameters\n\n- param: Foo\n";
+    let diagnostic = diagnose(md);
+
+    assert_eq!(diagnostic.severity, Severity::Error);
+    assert_eq!(labelled(md, &diagnostic), vec![("param: Foo", "expected an identifier in backticks", true)]);
+    assert_eq!(diagnostic.help, Some("write list items like this: `ident`: Docs".into()));
+    assert_eq!(diagnostic.suggestions[0].apply(md), "Lorem ipsum\n\n# Parameters\n\n- `param`: Foo\n");
+
+    // Nothing to put in backticks
+    let md = "Lorem ipsum\n\n# Parameters\n\n- Some parameter\n";
+    assert!(diagnose(md).suggestions.is_empty());
+}
+
+#[test]
+fn wrong_separator() {
+    let md = "Lorem ipsum\n\n# Parameters\n\n- `param` \u{2014} Foo\n";
+    let diagnostic = diagnose(md);
+
+    assert_eq!(diagnostic.code, "DS0007");
+    assert_eq!(labelled(md, &diagnostic), vec![
+        (" \u{2014} ", "expected `: `", true),
+        ("param", "identifier", false),
+    ]);
+    assert_eq!(diagnostic.suggestions[0].message, "use a colon");
+    assert_eq!(diagnostic.suggestions[0].apply(md), "Lorem ipsum\n\n# Parameters\n\n- `param`: Foo\n");
+}
+
+#[test]
+fn wrong_separator_with_options() {
+    let md = "Lorem ipsum\n\n# Parameters\n\n- `param`: Foo\n";
+    let options = ParseOptions {
+        separators: vec![Separator::Newline, Separator::Dash],
+        ..ParseOptions::default()
+    };
+    let (_, diagnostics) = parse_md_docblock_diagnostics(md, &options);
+    let diagnostic = &diagnostics[0];
+
+    assert_eq!(diagnostic.message, "List item \"`param`: Foo\" of section `Parameters` must be written like \
+                                    this: `ident` with the docs on the next line or `ident` - Docs");
+    assert_eq!(labelled(md, diagnostic), vec![
+        (": ", "expected a line break or ` - `", true),
+        ("param", "identifier", false),
+    ]);
+    assert_eq!(diagnostic.suggestions[0].message, "use a dash");
+    assert_eq!(diagnostic.suggestions[0].apply(md), "Lorem ipsum\n\n# Parameters\n\n- `param` - Foo\n");
+
+    let options = ParseOptions {
+        separators: vec![Separator::Newline],
+        ..ParseOptions::default()
+    };
+    let (_, diagnostics) = parse_md_docblock_diagnostics(md, &options);
+    assert!(diagnostics[0].suggestions.is_empty());
+}
+
+#[test]
+fn invalid_pattern() {
+    let md = "Lorem ipsum\n\n# Returns\n\n- `1 +`: Nope\n";
+    let diagnostic = diagnose(md);
+
+    assert_eq!(diagnostic.code, "DS0006");
+    assert_eq!(labelled(md, &diagnostic), vec![("1 +", "not a pattern", true)]);
+    assert!(diagnostic.suggestions.is_empty());
+}
+
+#[test]
+fn no_teaser() {
+    let md = "# Parameters\n\n- `a`: A\n";
+    let diagnostic = diagnose(md);
+
+    assert_eq!(diagnostic.code, "DS0001");
+    assert_eq!(labelled(md, &diagnostic), vec![("# Parameters", "expected a teaser", true)]);
+    assert!(diagnostic.help.is_some());
+}
+
+#[test]
+fn unknown_sections() {
+    let md = "Lorem ipsum\n\n# Paramters\n\n- `a`: A\n\n# Notes\n\nFoo\n";
+    let options = ParseOptions {
+        unknown_sections: UnknownSections::Warn,
+        ..ParseOptions::default()
+    };
+
+    let (doc, diagnostics) = parse_md_docblock_diagnostics(md, &options);
+    assert!(doc.is_some());
+    assert_eq!(diagnostics.len(), 2);
+    assert!(diagnostics.iter().all(|diagnostic| diagnostic.code == "DS0003"));
+    assert!(diagnostics.iter().all(|diagnostic| diagnostic.severity == Severity::Warning));
+    assert_eq!(labelled(md, &diagnostics[0]), vec![("Paramters", "unknown headline", true)]);
+    assert_eq!(diagnostics[0].suggestions[0].apply(md), md.replace("Paramters", "Parameters"));
+    assert!(diagnostics[1].suggestions.is_empty());
+
+    let options = ParseOptions {
+        unknown_sections: UnknownSections::Error,
+        ..ParseOptions::default()
+    };
+    let (doc, diagnostics) = parse_md_docblock_diagnostics(md, &options);
+    assert!(doc.is_none());
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+}
+
+#[test]
+fn lenient() {
+    let md = "Lorem ipsum\n\n# Parameters\n\n- `a`: A\n- b: B\n- `c` - C\n";
+    let options = ParseOptions {
+        lenient: true,
+        ..ParseOptions::default()
+    };
+
+    let (doc, diagnostics) = parse_md_docblock_diagnostics(md, &options);
+    assert_eq!(doc.unwrap().doc.sections.len(), 1);
+
+    let codes: Vec<&str> = diagnostics.iter().map(|diagnostic| &diagnostic.code[..]).collect();
+    assert_eq!(codes, vec!["DS0004", "DS0007"]);
+
+    let mut fixed = md.to_string();
+    for diagnostic in diagnostics.iter().rev() {
+        fixed = diagnostic.suggestions[0].apply(&fixed);
+    }
+    assert_eq!(fixed, "Lorem ipsum\n\n# Parameters\n\n- `a`: A\n- `b`: B\n- `c`: C\n");
+    assert!(parse_md_docblock(&fixed).is_ok());
+}
+
+#[test]
+fn map_positions() {
+    let md = "Lorem ipsum\n\n# Parameters\n\n- param: Foo\n";
+    let diagnostic = diagnose(md).map_positions(|position| Position {
+        offset: position.offset + 4,
+        line: position.line + 10,
+        column: position.column + 4,
+    });
+
+    let span = diagnostic.primary_span().unwrap();
+    assert_eq!((span.start.line, span.start.column, span.end.column), (15, 7, 17));
+    assert_eq!(diagnostic.suggestions[0].span.start.offset, span.start.offset);
+}
+
+#[test]
+fn render() {
+    let md = "Lorem ipsum\n\n# Parameters\n\n- param: Foo\n";
+    let diagnostic = diagnose(md).with_note("in the docs of `foo`");
+
+    assert_eq!(diagnostic.render("foo.md", md), concat!(
+        "error[DS0004]: No identifier in list item \"param: Foo\" of section `Parameters`\n",
+        " --> foo.md:5:3\n",
+        "  |\n",
+        "5 | - param: Foo\n",
+        "  |   ^^^^^^^^^^ expected an identifier in backticks\n",
+        "  = note: in the docs of `foo`\n",
+        "  = help: write list items like this: `ident`: Docs\n",
+        "help: put the identifier in backticks\n",
+        "  |\n",
+        "5 | - `param`: Foo\n",
+    ));
+
+    let diagnostic = Diagnostic::new("", Severity::Note, "Nothing to see");
+    assert_eq!(diagnostic.render("foo.md", md), "note: Nothing to see\n --> foo.md\n");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn json() {
+    extern crate serde_json;
+
+    let md = "Lorem ipsum\n\n# Parameters\n\n- `a` - A\n";
+    let diagnostic = diagnose(md);
+    let json = serde_json::to_value(&diagnostic).unwrap();
+
+    assert_eq!(json["code"], "DS0007");
+    assert_eq!(json["severity"], "error");
+    assert_eq!(json["labels"][1]["primary"], false);
+    assert_eq!(json["suggestions"][0]["replacement"], ": ");
+    assert_eq!(serde_json::from_value::<Diagnostic>(json).unwrap(), diagnostic);
+}
+
+#[cfg(feature = "source")]
+mod validation {
+    use docstrings::*;
+
+    fn diagnostics(source: &str) -> Vec<Diagnostic> {
+        let items = parse_rust_source(source).unwrap();
+        items[0].diagnostics(&local_enums(&items))
+    }
+
+    #[test]
+    fn codes_and_severities() {
+        let source = "\
+/// Lorem ipsum
+///
+/// # Parameters
+///
+/// - `b`: B
+/// - `a`: A
+/// - `a`: A again
+///
+/// # Type Parameters
+///
+/// - `U`: U
+pub fn foo<T>(a: u32, b: u32, c: T) {}
+";
+        let found: Vec<(String, Severity)> = diagnostics(source).into_iter()
+            .map(|diagnostic| (diagnostic.code, diagnostic.severity))
+            .collect();
+
+        assert!(found.contains(&("DS0101".into(), Severity::Error)));
+        assert!(found.contains(&("DS0103".into(), Severity::Warning)));
+        assert!(found.contains(&("DS0104".into(), Severity::Error)));
+        assert!(found.contains(&("DS0105".into(), Severity::Error)));
+    }
+
+    #[test]
+    fn source_positions() {
+        let source = "\
+/// Lorem ipsum
+///
+/// # Parameters
+///
+/// - `x`: X
+pub fn foo(y: u32) {}
+";
+        let diagnostics = diagnostics(source);
+        let unknown = diagnostics.iter().find(|diagnostic| diagnostic.code == "DS0102").unwrap();
+        let span = unknown.primary_span().unwrap();
+
+        assert_eq!(&source[span.range()], "x");
+        assert_eq!((span.start.line, span.start.column), (5, 8));
+        assert_eq!(unknown.labels[0].message, "not a parameter");
+
+        // `x` and `y` are too short to tell whether one is a misspelling
+        assert!(unknown.suggestions.is_empty());
+    }
+
+    #[test]
+    fn rename_suggestions() {
+        let source = "\
+/// Lorem ipsum
+///
+/// # Lifetime Parameters
+///
+/// - `'inptu`: Input
+pub fn foo<'input>(x: &'input str) {}
+";
+        let diagnostics = diagnostics(source);
+        let unknown = diagnostics.iter().find(|diagnostic| diagnostic.code == "DS0108").unwrap();
+
+        assert_eq!(unknown.suggestions[0].message, "rename it to `'input`");
+        assert_eq!(unknown.suggestions[0].apply(source), source.replace("'inptu", "'input"));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let source = "/// Lorem ipsum\n///\n/// # Parameters\n///\n/// - x: X\npub fn foo(x: u32) {}\n";
+        let diagnostics = diagnostics(source);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "DS0004");
+        assert_eq!(diagnostics[0].suggestions[0].apply(source), source.replace("- x:", "- `x`:"));
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let source = "pub fn foo(x: u32 {}\n";
+        let error = parse_rust_source(source).unwrap_err();
+        let diagnostic = error.to_diagnostic(source);
+
+        assert_eq!(diagnostic.code, "DS0202");
+        let span = diagnostic.primary_span().unwrap();
+        assert!(!span.is_empty());
+        assert_eq!(&source[span.range()], source[span.start.offset..].trim_end());
+    }
+
+    #[test]
+    fn validation_error_messages() {
+        let error = ValidationError::UnknownParameter("x".into(), Position::default());
+        assert_eq!(error.code(), "DS0102");
+        assert_eq!(error.message(), "Documented parameter `x` does not exist");
+        assert_eq!(error.to_string(), "0:0: Documented parameter `x` does not exist");
+    }
+}
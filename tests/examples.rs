@@ -0,0 +1,153 @@
+extern crate docstrings;
+
+use docstrings::*;
+
+fn examples(md: &str) -> Vec<Example> {
+    match parse_md_docblock(md).unwrap().sections.pop() {
+        Some(DocSection::Examples(examples, _)) => examples,
+        other => panic!("expected examples, got {:?}", other),
+    }
+}
+
+#[test]
+fn split_into_examples() {
+    let examples = examples("\
+Lorem ipsum
+
+# Examples
+
+Basic usage:
+
+```
+let x = 1;
+```
+
+Now with *more*:
+
+```rust
+let y = 2;
+```
+
+```text
+Output
+```
+    ");
+
+    assert_eq!(examples.len(), 2);
+    assert_eq!(examples[0].description, "Basic usage:");
+    assert_eq!(examples[0].code_blocks, vec![
+        CodeBlock {
+            language: None,
+            attributes: vec![],
+            lines: vec![CodeLine::Visible("let x = 1;".into())],
+        },
+    ]);
+    assert_eq!(examples[1].description, "Now with *more*:");
+    assert_eq!(examples[1].code_blocks.len(), 2);
+    assert_eq!(examples[1].code_blocks[0].language, Some("rust".into()));
+    assert_eq!(examples[1].code_blocks[1].language, Some("text".into()));
+    assert!(!examples[1].code_blocks[1].is_rust());
+}
+
+#[test]
+fn rustdoc_attributes() {
+    let examples = examples("\
+Lorem ipsum
+
+# Examples
+
+```rust,no_run
+loop {}
+```
+
+```should_panic,edition2018
+panic!();
+```
+
+```compile_fail,E0308
+let x: u8 = \"\";
+```
+    ");
+
+    let blocks = &examples[0].code_blocks;
+    assert_eq!(blocks[0].language, Some("rust".into()));
+    assert_eq!(blocks[0].attributes, vec!["no_run".to_string()]);
+    assert!(blocks[0].has_attribute("no_run"));
+    assert_eq!(blocks[1].language, None);
+    assert_eq!(blocks[1].attributes, vec!["should_panic".to_string(), "edition2018".to_string()]);
+    assert!(blocks[1].is_rust());
+    assert_eq!(blocks[2].attributes, vec!["compile_fail".to_string(), "E0308".to_string()]);
+}
+
+#[test]
+fn hidden_lines() {
+    let examples = examples("\
+Lorem ipsum
+
+# Examples
+
+```
+# use std::collections::HashMap;
+#
+let mut map = HashMap::new();
+## not hidden
+#[derive(Debug)] struct Foo;
+# fn main() {
+    # let hidden = 1;
+    #
+# }
+```
+
+```text
+# not hidden either
+```
+    ");
+
+    let blocks = &examples[0].code_blocks;
+    assert_eq!(blocks[0].lines, vec![
+        CodeLine::Hidden("use std::collections::HashMap;".into()),
+        CodeLine::Hidden("".into()),
+        CodeLine::Visible("let mut map = HashMap::new();".into()),
+        CodeLine::Visible("# not hidden".into()),
+        CodeLine::Visible("#[derive(Debug)] struct Foo;".into()),
+        CodeLine::Hidden("fn main() {".into()),
+        CodeLine::Hidden("    let hidden = 1;".into()),
+        CodeLine::Hidden("    ".into()),
+        CodeLine::Hidden("}".into()),
+    ]);
+    assert_eq!(blocks[0].visible_code(),
+               "let mut map = HashMap::new();\n# not hidden\n#[derive(Debug)] struct Foo;");
+    assert_eq!(blocks[0].code(),
+               "use std::collections::HashMap;\n\nlet mut map = HashMap::new();\n# not hidden\n#[derive(Debug)] struct Foo;\n\
+                fn main() {\n    let hidden = 1;\n    \n}");
+    assert_eq!(blocks[1].lines, vec![CodeLine::Visible("# not hidden either".into())]);
+
+    // Indentation stays in front of the marker
+    let doc = DocBlock {
+        teaser: "Lorem ipsum".into(),
+        description: None,
+        subsections: vec![],
+        sections: vec![DocSection::Examples(examples.clone(), vec![])],
+    };
+    let rendered = render_md_docblock(&doc);
+    assert!(rendered.contains("\n# fn main() {\n    # let hidden = 1;\n    #\n# }\n"));
+    assert_eq!(parse_md_docblock(&rendered).unwrap(), doc);
+}
+
+#[test]
+fn example_without_code() {
+    let examples = examples("\
+Lorem ipsum
+
+# Example
+
+Just call it.
+    ");
+
+    assert_eq!(examples, vec![
+        Example {
+            description: "Just call it.".into(),
+            code_blocks: vec![],
+        },
+    ]);
+}
@@ -0,0 +1,103 @@
+extern crate docstrings;
+
+use docstrings::*;
+
+#[test]
+fn collects_all_broken_list_items() {
+    let (doc, errors) = parse_md_docblock_lenient("\
+Lorem ipsum
+
+# Parameters
+
+- `foo`: Foo
+- bar: Bar
+- `baz` Baz
+- `quux`: Quux
+
+# Type parameters
+
+- T: Some type
+    ");
+
+    assert_eq!(doc, DocBlock {
+        teaser: "Lorem ipsum".into(),
+        description: None,
+        subsections: vec![],
+        sections: vec![
+            DocSection::Parameters(vec![
+                ("foo".parse().unwrap(), "Foo".into()),
+                ("quux".parse().unwrap(), "Quux".into()),
+            ], vec![]),
+            DocSection::TypeParameters(vec![], vec![]),
+        ],
+    });
+
+    assert_eq!(errors.len(), 3);
+    assert_eq!(errors[0].item(), Some("bar: Bar"));
+    assert_eq!(errors[0].position().line, 6);
+    assert_eq!(errors[1].item(), Some("`baz` Baz"));
+    assert_eq!(errors[1].position().line, 7);
+    assert_eq!(errors[2].item(), Some("T: Some type"));
+    assert_eq!(errors[2].section(), Some("Type parameters"));
+}
+
+#[test]
+fn skips_unexpected_markdown() {
+    let (doc, errors) = parse_md_docblock_lenient("\
+Lorem ipsum
+
+# Parameters
+
+Dafuq
+
+- `foo`: Bar
+
+> Quote
+
+- `baz`: Quux
+    ");
+
+    assert_eq!(doc.sections, vec![
+        DocSection::Parameters(vec![
+            ("foo".parse().unwrap(), "Bar".into()),
+            ("baz".parse().unwrap(), "Quux".into()),
+        ], vec![]),
+    ]);
+    assert_eq!(errors, vec![
+        ParseError::UnexpectedMarkdown(
+            "Parameters".into(), "Start(Paragraph)".into(),
+            Position { offset: 27, line: 5, column: 1 }),
+        ParseError::UnexpectedMarkdown(
+            "Parameters".into(), "Start(BlockQuote)".into(),
+            Position { offset: 48, line: 9, column: 1 }),
+    ]);
+}
+
+#[test]
+fn missing_teaser() {
+    let (doc, errors) = parse_md_docblock_lenient("\
+# Parameters
+
+- `foo`: Bar
+    ");
+
+    assert_eq!(doc, DocBlock {
+        teaser: "".into(),
+        description: None,
+        subsections: vec![],
+        sections: vec![
+            DocSection::Parameters(vec![
+                ("foo".parse().unwrap(), "Bar".into()),
+            ], vec![]),
+        ],
+    });
+    assert_eq!(errors, vec![ParseError::NoTeaser(Position { offset: 0, line: 1, column: 1 })]);
+}
+
+#[test]
+fn no_errors() {
+    let md = "Lorem ipsum\n\n# Parameters\n\n- `foo`: Bar\n";
+
+    assert_eq!(parse_md_docblock_lenient(md),
+               (parse_md_docblock(md).unwrap(), vec![]));
+}
@@ -0,0 +1,122 @@
+extern crate docstrings;
+use docstrings::*;
+
+fn description(md: &str) -> String {
+    parse_md_docblock(&format!("Lorem ipsum\n\n{}\n", md)).unwrap()
+        .description.unwrap()
+}
+
+fn description_with(md: &str, options: &ParseOptions) -> String {
+    parse_md_docblock_with(&format!("Lorem ipsum\n\n{}\n", md), options).unwrap()
+        .0.description.unwrap()
+}
+
+#[test]
+fn unchanged() {
+    for md in &[
+        "See [the book](https://doc.rust-lang.org/book/ \"The Book\") for details.",
+        "Links with spaces work: [here](<some file.md>).",
+        "![Logo](logo.png)",
+        "Some <b>inline</b> HTML.",
+        "<div>\nBlock HTML\n</div>\n\nAfter the block.",
+        "> Quoted\n> lines\n>\n> and paragraphs",
+        "Above\n\n---\n\nBelow",
+        "Line with a hard break\\\nand more.",
+        "3. Three\n4. Four\n5. Five",
+        "- Outer\n  - Inner\n  - Also inner\n- Outer again",
+        "```text\nsome code\n```",
+        "*Emphasis*, **strong** and `code`",
+    ] {
+        assert_eq!(&description(md), md);
+    }
+}
+
+#[test]
+fn escaped() {
+    for md in &[
+        "\\*not emphasis\\*",
+        "1\\. not a list",
+        "\\# not a heading",
+        "\\> not a quote",
+        "Not\n\\- an item",
+        "\\[not a link\\] and \\`not code\\`",
+        "A \\<b> tag, \\&amp; an entity and a backslash: \\\\",
+        "Not escaped: snake_case, 1 < 2, &mut str, C# and -1",
+    ] {
+        assert_eq!(&description(md), md);
+    }
+
+    let doc = parse_md_docblock("Lorem ipsum\n\n# Notes\n\n\\*Not\\* a \\_list\\_:\n\n2\\) no\n").unwrap();
+    assert_eq!(parse_md_docblock(&render_md_docblock(&doc)).unwrap(), doc);
+}
+
+#[test]
+fn reference_links() {
+    let md = "Read [the docs][docs], [this][] and [that].\n\n\
+              [docs]: https://docs.rs\n\
+              [this]: https://example.com/this\n\
+              [that]: https://example.com/that";
+    assert_eq!(description(md), md);
+}
+
+#[test]
+fn reference_definitions_in_code_are_not_used() {
+    let md = "Read [the docs][docs].\n\n\
+              ```text\n\
+              [docs]: https://example.com/not-this\n\
+              ```\n\n\
+              [docs]: https://docs.rs";
+    let doc = parse_md_docblock(&format!("Lorem ipsum\n\n{}\n", md)).unwrap();
+    assert_eq!(doc.description.unwrap(), md);
+
+    let md = "Lorem ipsum\n\n# Notes\n\nSee [this][1].\n\n    [1]: https://example.com/code\n\n\
+              [1]: https://example.com/this\n";
+    let doc = parse_md_docblock(md).unwrap();
+    assert_eq!(doc.description, Some("[1]: https://example.com/this".into()));
+}
+
+#[test]
+fn reference_definitions_go_in_the_description() {
+    let md = "Lorem ipsum\n\n\
+              # Parameters\n\n\
+              - `foo`: See [bar][1]\n\n\
+              # Notes\n\n\
+              Also see [bar][1].\n\n\
+              [1]: https://example.com/bar\n";
+
+    let doc = parse_md_docblock(md).unwrap();
+    assert_eq!(doc.description, Some("[1]: https://example.com/bar".into()));
+    assert_eq!(doc.sections, vec![
+        DocSection::Parameters(vec![("foo".parse().unwrap(), "See [bar][1]".into())], vec![]),
+        DocSection::Custom("Notes".into(), "Also see [bar][1].".into(), vec![]),
+    ]);
+    assert_eq!(parse_md_docblock(&render_md_docblock(&doc)).unwrap(), doc);
+
+    let md = "Teaser with [link][1].\n\nMore.\n\n[1]: http://example.com\n";
+    let doc = parse_md_docblock(md).unwrap();
+    assert_eq!(doc.teaser, "Teaser with [link][1].");
+    assert_eq!(doc.description, Some("More.\n\n[1]: http://example.com".into()));
+    assert_eq!(parse_md_docblock(&render_md_docblock(&doc)).unwrap(), doc);
+}
+
+#[test]
+fn tables() {
+    let options = ParseOptions {
+        tables: true,
+        ..ParseOptions::default()
+    };
+
+    let md = "| Name | Value |\n| :-- | --: |\n| `a` | 1 |";
+    assert_eq!(description_with(md, &options), md);
+}
+
+#[test]
+fn footnotes() {
+    let options = ParseOptions {
+        footnotes: true,
+        ..ParseOptions::default()
+    };
+
+    let md = "Some claim[^1].";
+    assert_eq!(description_with(md, &options), md);
+}
@@ -0,0 +1,305 @@
+extern crate docstrings;
+
+use docstrings::*;
+
+#[test]
+fn heading_level() {
+    let options = ParseOptions {
+        heading_level: 2,
+        ..ParseOptions::default()
+    };
+
+    let (doc, warnings) = parse_md_docblock_with("\
+Lorem ipsum
+
+Dolor sit amet
+
+# Not a section
+
+## Parameters
+
+- `foo`: Bar
+    ", &options).unwrap();
+
+    assert_eq!(doc, DocBlock {
+        teaser: "Lorem ipsum".into(),
+        description: Some("Dolor sit amet\n\n# Not a section".into()),
+        subsections: vec![],
+        sections: vec![
+            DocSection::Parameters(vec![
+                ("foo".parse().unwrap(), "Bar".into()),
+            ], vec![]),
+        ],
+    });
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn warn_about_unknown_sections() {
+    let options = ParseOptions {
+        unknown_sections: UnknownSections::Warn,
+        ..ParseOptions::default()
+    };
+
+    let (doc, warnings) = parse_md_docblock_with("\
+Lorem ipsum
+
+# Paramters
+
+- `foo`: Bar
+    ", &options).unwrap();
+
+    assert_eq!(doc.sections, vec![
+        DocSection::Custom("Paramters".into(), "- `foo`: Bar".into(), vec![]),
+    ]);
+    assert_eq!(warnings, vec![
+        ParseError::UnknownSection("Paramters".into(), Position { offset: 15, line: 3, column: 3 }),
+    ]);
+}
+
+#[test]
+fn reject_unknown_sections() {
+    let md = "Lorem ipsum\n\n# Paramters\n\n- `foo`: Bar\n";
+    let mut options = ParseOptions {
+        unknown_sections: UnknownSections::Error,
+        ..ParseOptions::default()
+    };
+
+    assert_eq!(
+        parse_md_docblock_with(md, &options).unwrap_err(),
+        ParseError::UnknownSection("Paramters".into(), Position { offset: 15, line: 3, column: 3 })
+    );
+
+    options.lenient = true;
+    let (doc, errors) = parse_md_docblock_with(md, &options).unwrap();
+    assert_eq!(doc.sections, vec![
+        DocSection::Custom("Paramters".into(), "- `foo`: Bar".into(), vec![]),
+    ]);
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn lenient() {
+    let options = ParseOptions {
+        lenient: true,
+        ..ParseOptions::default()
+    };
+
+    let (doc, errors) = parse_md_docblock_with(
+        "Lorem ipsum\n\n# Parameters\n\n- foo: Bar\n", &options).unwrap();
+
+    assert_eq!(doc.sections, vec![DocSection::Parameters(vec![], vec![])]);
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn case_sensitivity() {
+    let md = "Lorem ipsum\n\n# parameters\n\n- `foo`: Bar\n";
+
+    let (doc, _) = parse_md_docblock_with(md, &ParseOptions::default()).unwrap();
+    assert_eq!(doc.sections, vec![
+        DocSection::Parameters(vec![("foo".parse().unwrap(), "Bar".into())], vec![]),
+    ]);
+
+    let options = ParseOptions {
+        case_sensitive: true,
+        ..ParseOptions::default()
+    };
+    let (doc, _) = parse_md_docblock_with(md, &options).unwrap();
+    assert_eq!(doc.sections, vec![
+        DocSection::Custom("parameters".into(), "- `foo`: Bar".into(), vec![]),
+    ]);
+}
+
+#[test]
+fn markdown_extensions() {
+    let md = "Lorem ipsum\n\n| a | b |\n|---|---|\n| 1 | 2 |\n";
+
+    let (doc, _) = parse_md_docblock_with(md, &ParseOptions::default()).unwrap();
+    assert_eq!(doc.description.unwrap(), "| a | b |\n|---|---|\n| 1 | 2 |");
+
+    let options = ParseOptions {
+        tables: true,
+        ..ParseOptions::default()
+    };
+    let (doc, _) = parse_md_docblock_with(md, &options).unwrap();
+    assert_eq!(doc.description.unwrap(), "| a | b |\n| --- | --- |\n| 1 | 2 |");
+}
+
+#[test]
+fn custom_headlines() {
+    let mut headlines = SectionHeadlines::empty();
+    headlines.insert("Inputs", SectionKind::Parameters);
+    headlines.insert("Outputs", SectionKind::Returns);
+    let options = ParseOptions {
+        headlines,
+        ..ParseOptions::default()
+    };
+
+    let (doc, _) = parse_md_docblock_with("\
+Lorem ipsum
+
+# Parameters
+
+Not what it used to be
+
+# Inputs
+
+- `foo`: Bar
+
+# Outputs
+
+Baz
+    ", &options).unwrap();
+
+    assert_eq!(doc.sections, vec![
+        DocSection::Custom("Parameters".into(), "Not what it used to be".into(), vec![]),
+        DocSection::Parameters(vec![("foo".parse().unwrap(), "Bar".into())], vec![]),
+        DocSection::Returns("Baz".into(), vec![], vec![]),
+    ]);
+    assert_eq!(doc.sections[1].kind(), Some(SectionKind::Parameters));
+    assert_eq!(doc.sections[0].kind(), None);
+}
+
+#[test]
+fn default_headlines() {
+    let headlines = SectionHeadlines::default();
+
+    assert_eq!(headlines.headlines(SectionKind::Parameters),
+               vec!["Parameters", "Arguments", "Args", "Params"]);
+    assert_eq!(headlines.kind("  return  ", false), Some(SectionKind::Returns));
+    assert_eq!(headlines.kind("return", true), None);
+}
+
+const LEGACY: &str = "\
+Lorem ipsum
+
+# Parameters
+
+- `foo`: Foo
+- `bar` - Bar
+- `baz` \u{2014} Baz
+- `quux`
+    Quux
+- **frob**: Frob
+";
+
+#[test]
+fn separators() {
+    assert_eq!(
+        parse_md_docblock(LEGACY).unwrap_err(),
+        ParseError::WrongIdentDocsSeparator(
+            "Parameters".into(), "`bar` - Bar".into(), Position { offset: 46, line: 6, column: 7 })
+    );
+
+    let options = ParseOptions {
+        separators: vec![Separator::Colon, Separator::Dash, Separator::EmDash, Separator::Newline],
+        bold_idents: true,
+        ..ParseOptions::default()
+    };
+    let (doc, warnings) = parse_md_docblock_with(LEGACY, &options).unwrap();
+
+    assert_eq!(doc.sections, vec![
+        DocSection::Parameters(vec![
+            ("foo".parse().unwrap(), "Foo".into()),
+            ("bar".parse().unwrap(), "Bar".into()),
+            ("baz".parse().unwrap(), "Baz".into()),
+            ("quux".parse().unwrap(), "Quux".into()),
+            ("frob".parse().unwrap(), "Frob".into()),
+        ], vec![]),
+    ]);
+    assert!(warnings.is_empty());
+
+    let (spanned, _) = parse_md_docblock_spanned_with(LEGACY, &options).unwrap();
+    let docs: Vec<_> = spanned.sections[0].items.iter().map(|item| &LEGACY[item.docs.range()]).collect();
+    assert_eq!(docs, vec!["Foo", "Bar", "Baz", "Quux", "Frob"]);
+}
+
+#[test]
+fn normalize_separators() {
+    let options = ParseOptions {
+        separators: vec![Separator::Colon, Separator::Dash, Separator::EmDash, Separator::Newline],
+        bold_idents: true,
+        ..ParseOptions::default()
+    };
+    let (doc, _) = parse_md_docblock_with(LEGACY, &options).unwrap();
+
+    assert_eq!(render_md_docblock(&doc), "\
+Lorem ipsum
+
+# Parameters
+
+- `foo`: Foo
+- `bar`: Bar
+- `baz`: Baz
+- `quux`: Quux
+- `frob`: Frob
+");
+
+    let rendered = render_md_docblock_with(&doc, &RenderOptions { separator: Separator::Newline, ..RenderOptions::default() });
+    assert!(rendered.contains("- `quux`\n    Quux\n"));
+    assert_eq!(parse_md_docblock_with(&rendered, &options).unwrap().0, doc);
+}
+
+#[test]
+fn tables() {
+    let md = "\
+Lorem ipsum
+
+# Parameters
+
+| Name | Description |
+|------|-------------|
+| `foo` | A *foo* |
+| bar | A bar |
+| `baz` (u32, default: 1) | A baz |
+
+# Returns
+
+A `Result`:
+
+| Variant | Meaning |
+| --- | --- |
+| `Ok(_)` | Yes |
+";
+    let options = ParseOptions {
+        tables: true,
+        ..ParseOptions::default()
+    };
+    let (spanned, warnings) = parse_md_docblock_spanned_with(md, &options).unwrap();
+
+    assert_eq!(spanned.doc.sections, vec![
+        DocSection::Parameters(vec![
+            ("foo".parse().unwrap(), "A *foo*".into()),
+            ("bar".parse().unwrap(), "A bar".into()),
+            ("baz".parse().unwrap(), ParameterDoc {
+                docs: "A baz".into(),
+                ty: Some("u32".into()),
+                default: Some("1".into()),
+                ..ParameterDoc::default()
+            }),
+        ], vec![]),
+        DocSection::Returns("A `Result`:".into(), vec![
+            ("Ok(_)".parse().unwrap(), "Yes".into()),
+        ], vec![]),
+    ]);
+    assert!(warnings.is_empty());
+
+    let items = &spanned.sections[0].items;
+    assert_eq!(&md[items[0].ident.range()], "foo");
+    assert_eq!(&md[items[0].docs.range()], "A *foo*");
+    assert_eq!(&md[items[1].ident.range()], "bar");
+
+    // Tables need exactly two columns
+    let error = parse_md_docblock_with(
+        "Lorem ipsum\n\n# Parameters\n\n| Name | Type | Description |\n|---|---|---|\n| `a` | u32 | A |\n",
+        &options,
+    ).unwrap_err();
+    match error {
+        ParseError::UnexpectedMarkdown(section, _, position) => {
+            assert_eq!(section, "Parameters");
+            assert_eq!(position.line, 5);
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+}
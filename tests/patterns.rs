@@ -0,0 +1,136 @@
+extern crate docstrings;
+
+use docstrings::*;
+
+fn canonical(pattern: &str) -> String {
+    Pattern::new(pattern).unwrap().to_string()
+}
+
+#[test]
+fn identifiers() {
+    for ident in &["foo", "self", "r#type", "'a", "'static", "_foo"] {
+        assert_eq!(Identifier::new(ident).unwrap(), *ident);
+    }
+
+    let raw = Identifier::new(" r#type ").unwrap();
+    assert_eq!(raw.as_str(), "r#type");
+    assert_eq!(raw.name(), "type");
+    assert!(raw.is_raw() && !raw.is_lifetime());
+
+    let lifetime: Identifier = "'a".parse().unwrap();
+    assert_eq!(lifetime.name(), "a");
+    assert!(lifetime.is_lifetime());
+
+    for ident in &["foo bar", "", "_", "1x", "a::b", "'"] {
+        assert_eq!(
+            Identifier::new(ident),
+            Err(ParseError::InvalidIdent(String::new(), ident.to_string(), Position::default()))
+        );
+    }
+}
+
+#[cfg(feature = "source")]
+#[test]
+fn bindings() {
+    assert_eq!(Pattern::new("mut x").unwrap(), Pattern::Ident {
+        by_ref: false,
+        mutable: true,
+        ident: "x".parse().unwrap(),
+        subpattern: None,
+    });
+    assert_eq!(Pattern::new("ref mut x").unwrap().binding().unwrap().as_str(), "x");
+    assert_eq!(Pattern::new("type").unwrap().binding().unwrap().as_str(), "type");
+    assert_eq!(Pattern::new("x @ 1..=5").unwrap().binding(), None);
+    assert_eq!(Pattern::new("(a, b)").unwrap().binding(), None);
+}
+
+#[test]
+fn paths() {
+    assert_eq!(Pattern::new("None").unwrap().path(), Some("None"));
+    assert_eq!(Pattern::new("Some(_)").unwrap().path(), Some("Some"));
+    assert_eq!(Pattern::new("io::ErrorKind::NotFound").unwrap().path(), Some("io::ErrorKind::NotFound"));
+    assert_eq!(Pattern::new("Foo::Bar { .. }").unwrap().path(), Some("Foo::Bar"));
+    assert_eq!(Pattern::new("_").unwrap().path(), None);
+}
+
+#[cfg(feature = "source")]
+#[test]
+fn structure() {
+    assert_eq!(Pattern::new("Some(_) | None").unwrap(), Pattern::Or(vec![
+        Pattern::TupleStruct("Some".into(), vec![Pattern::Wild]),
+        Pattern::new("None").unwrap(),
+    ]));
+    assert_eq!(Pattern::new("Foo { a, b: 1, .. }").unwrap(), Pattern::Struct("Foo".into(), vec![
+        FieldPattern { member: "a".into(), pattern: Pattern::new("a").unwrap(), shorthand: true },
+        FieldPattern { member: "b".into(), pattern: Pattern::Literal("1".into()), shorthand: false },
+    ], true));
+    assert_eq!(Pattern::new("&mut [first, ..]").unwrap(), Pattern::Reference(true, Box::new(
+        Pattern::Slice(vec![Pattern::new("first").unwrap(), Pattern::Rest])
+    )));
+}
+
+#[cfg(feature = "source")]
+#[test]
+fn display() {
+    assert_eq!(canonical("(a,b)"), "(a, b)");
+    assert_eq!(canonical("( a , )"), "(a,)");
+    assert_eq!(canonical("Foo{a,ref mut b , ..}"), "Foo { a, ref mut b, .. }");
+    assert_eq!(canonical("Foo {}"), "Foo {}");
+    assert_eq!(canonical("Point { x: 0, y }"), "Point { x: 0, y }");
+    assert_eq!(canonical("Some(x)|None"), "Some(x) | None");
+    assert_eq!(canonical("n @ 1 ..= 5"), "n @ 1..=5");
+    assert_eq!(canonical("'a' ..= 'z'"), "'a'..='z'");
+    assert_eq!(canonical("\"foo\""), "\"foo\"");
+    assert_eq!(canonical("-1"), "-1");
+    assert_eq!(canonical("& mut x"), "&mut x");
+    assert_eq!(canonical("[a, .., z]"), "[a, .., z]");
+}
+
+#[test]
+fn raw() {
+    for pattern in &["Ok(Vec<u8>)", "Ok((DocBlock, Vec<ParseError>))", "vec![]", "<T as Trait>::X", "Vec::<u8>::new"] {
+        assert_eq!(Pattern::new(pattern).unwrap(), Pattern::Raw(pattern.to_string()));
+        assert_eq!(canonical(pattern), *pattern);
+    }
+
+    assert_eq!(Pattern::new("Ok(Vec<u8>)").unwrap().path(), Some("Ok"));
+    assert_eq!(Pattern::new("Foo::Bar { x: Vec<u8> }").unwrap().path(), Some("Foo::Bar"));
+    assert_eq!(Pattern::new("vec![]").unwrap().path(), None);
+    assert_eq!(Pattern::new("Vec::<u8>::new").unwrap().path(), None);
+}
+
+#[test]
+fn types_in_returns() {
+    // From the docs of `parse_md_docblock_with`
+    let doc = parse_md_docblock("\
+Parse documentation and extract data, configured by `ParseOptions`
+
+# Returns
+
+A `Result`, which is either
+
+- `Ok((DocBlock, Vec<ParseError>))`: The extracted information and all
+  problems that did not stop parsing: Warnings as well as, in lenient
+  mode, all errors.
+- `Err(ParseError)`: In strict mode, the first encountered error while
+  parsing the documentation string.
+").unwrap();
+
+    match doc.sections[0] {
+        DocSection::Returns(_, ref variants, _) => {
+            assert_eq!(variants[0].0, Pattern::Raw("Ok((DocBlock, Vec<ParseError>))".into()));
+            assert_eq!(variants[1].0.path(), Some("Err"));
+        }
+        ref other => panic!("expected returns, got {:?}", other),
+    }
+}
+
+#[test]
+fn invalid() {
+    for pattern in &["", "Some(", "1 +", "Foo { a:", "(a, b]", "x =", "`x`"] {
+        assert_eq!(
+            Pattern::new(pattern),
+            Err(ParseError::InvalidPattern(String::new(), pattern.to_string(), Position::default()))
+        );
+    }
+}
@@ -0,0 +1,290 @@
+extern crate docstrings;
+
+use docstrings::*;
+
+const KITCHENSINK: &str = r#"Fooify a `Foo` with a label
+
+A longer description lorem ipsum dolor sit amet. With multiple lines, of course,
+to see that this is actually just one more paragraph in Markdown land.
+
+But actually, let's add more fancy stuff, e.g., a list:
+
+- Yes, a list
+- Lists are cool
+- I write lists now
+
+## Sub-headline for description
+
+Yes, we are using *subheadlines* as well. To confuse everyone.
+
+# Parameters
+
+- `label`: A string labelling the foo
+- `magic`: A `Foo` that will be labeled
+- `times` (`usize`, optional, default: 1, at most 3): How often to label it
+- `options`: How to label
+
+    Defaults are fine.
+
+    - `color`: Color of the label
+    - `size`: Size of the label
+
+# Returns
+
+A `Result` which is:
+
+- `Ok`: A `Bar` that is the labeled `Foo` and thus lives as long as the
+    `Foo` given in `magic`.
+- `Err`: Returns the number of gravely appalled people.
+
+# Type parameters
+
+- `T`: A type that can be converted into a `FooLabel`
+
+# Lifetimes
+
+- `floof`: The life time of the given foo
+
+# Errors
+
+- `Error::Appalled`: When people are appalled
+- When it rains
+
+# Panics
+
+Panics if the floof source is empty.
+
+# Safety
+
+None needed.
+
+# Notes
+
+Some **notes**.
+
+# Examples
+
+Basic usage:
+
+```rust
+# use foo::*;
+assert_eq!(fooify("lorem", Foo::extract_from_global_floof_resource()).label(),
+           Bar::with_label("lorem"))
+## escaped
+```
+
+```text,ignore
+Output
+```
+
+Advanced usage:
+
+```
+#
+let x = 1;
+```
+"#;
+
+#[test]
+fn roundtrip() {
+    let doc = parse_md_docblock(KITCHENSINK).unwrap();
+    let canonical = RenderOptions { canonical_order: true, ..RenderOptions::default() };
+    let rendered = render_md_docblock_with(&doc, &canonical);
+    let reparsed = parse_md_docblock(&rendered).unwrap();
+
+    assert_eq!(reparsed.teaser, doc.teaser);
+    assert_eq!(reparsed.description, doc.description);
+    assert_eq!(reparsed.sections.len(), doc.sections.len());
+    for section in &doc.sections {
+        assert!(reparsed.sections.contains(section), "missing {:?}", section);
+    }
+
+    // Generics are rendered before returns in canonical order
+    assert_eq!(
+        reparsed.sections.iter().map(|s| s.kind()).collect::<Vec<_>>(),
+        vec![
+            Some(SectionKind::Parameters),
+            Some(SectionKind::TypeParameters),
+            Some(SectionKind::LifetimeParameters),
+            Some(SectionKind::Returns),
+            Some(SectionKind::Errors),
+            Some(SectionKind::Panics),
+            Some(SectionKind::Safety),
+            None,
+            Some(SectionKind::Examples),
+        ]
+    );
+
+    // Rendering is stable and canonical order survives
+    assert_eq!(render_md_docblock_with(&reparsed, &canonical), rendered);
+    assert_eq!(render_md_docblock(&reparsed), rendered);
+    assert_eq!(parse_md_docblock(&render_md_docblock(&reparsed)).unwrap(), reparsed);
+
+    // By default, sections keep the order they were written in
+    assert_eq!(parse_md_docblock(&render_md_docblock(&doc)).unwrap(), doc);
+}
+
+#[test]
+fn annotations_roundtrip() {
+    let parameter = |ty: Option<&str>, constraints: &[&str]| ParameterDoc {
+        docs: "X".into(),
+        ty: ty.map(|ty| ty.into()),
+        constraints: constraints.iter().map(|constraint| constraint.to_string()).collect(),
+        ..ParameterDoc::default()
+    };
+    let doc = DocBlock {
+        teaser: "Lorem ipsum".into(),
+        description: None,
+        subsections: vec![],
+        sections: vec![DocSection::Parameters(vec![
+            ("a".parse().unwrap(), parameter(None, &["at least 1s"])),
+            ("b".parse().unwrap(), parameter(None, &["positive"])),
+            ("c".parse().unwrap(), parameter(Some("&'a mut str"), &["not empty"])),
+            ("d".parse().unwrap(), parameter(Some("some type"), &[])),
+        ], vec![])],
+    };
+
+    let rendered = render_md_docblock(&doc);
+    assert_eq!(rendered, "\
+Lorem ipsum
+
+# Parameters
+
+- `a` (at least 1s): X
+- `b` (required, positive): X
+- `c` (&'a mut str, not empty): X
+- `d` (`some type`): X
+");
+    assert_eq!(parse_md_docblock(&rendered).unwrap(), doc);
+}
+
+#[test]
+fn canonical_markdown() {
+    let doc = DocBlock {
+        teaser: "Lorem ipsum".into(),
+        description: Some("Dolor sit amet.".into()),
+        subsections: vec![],
+        sections: vec![
+            DocSection::Examples(vec![
+                Example {
+                    description: "".into(),
+                    code_blocks: vec![
+                        CodeBlock {
+                            language: None,
+                            attributes: vec!["no_run".into()],
+                            lines: vec![
+                                CodeLine::Hidden("fn main() {".into()),
+                                CodeLine::Visible("# not hidden".into()),
+                                CodeLine::Visible("let s = \"```\";".into()),
+                                CodeLine::Hidden("}".into()),
+                            ],
+                        },
+                    ],
+                },
+            ], vec![]),
+            DocSection::Custom("Notes".into(), "Foo".into(), vec![]),
+            DocSection::Parameters(vec![
+                ("foo".parse().unwrap(), "Bar\nbaz".into()),
+            ], vec![]),
+            DocSection::Panics("".into(), vec!["If foo".into()], vec![]),
+        ],
+    };
+
+    let canonical = RenderOptions { canonical_order: true, ..RenderOptions::default() };
+    let md = render_md_docblock_with(&doc, &canonical);
+    assert_eq!(md, "\
+Lorem ipsum
+
+Dolor sit amet.
+
+# Parameters
+
+- `foo`: Bar
+    baz
+
+# Panics
+
+- If foo
+
+# Notes
+
+Foo
+
+# Examples
+
+````no_run
+# fn main() {
+## not hidden
+let s = \"```\";
+# }
+````
+");
+
+    let reparsed = parse_md_docblock(&md).unwrap();
+    assert_eq!(reparsed.sections[0], doc.sections[2]);
+    assert_eq!(reparsed.sections[1], doc.sections[3]);
+    assert_eq!(reparsed.sections[2], doc.sections[1]);
+    assert_eq!(reparsed.sections[3], doc.sections[0]);
+
+    // Without `canonical_order`, sections keep their order
+    assert_eq!(parse_md_docblock(&doc.to_string()).unwrap().sections, doc.sections);
+}
+
+#[test]
+fn subsections() {
+    let md = "\
+Lorem ipsum
+
+## Details
+
+Dolor sit amet.
+
+# Parameters
+
+- `a`: A
+
+## Options
+
+- `b`: B
+
+# Notes
+
+Some notes.
+
+## More notes
+
+Even more.
+
+# Examples
+
+## Basic usage
+
+Foo
+
+### Really basic
+
+Bar
+";
+
+    let doc = parse_md_docblock(md).unwrap();
+    assert_eq!(doc.sections[1], DocSection::Custom("Notes".into(), "Some notes.".into(), vec![
+        ("More notes".into(), DocSection::Custom(String::new(), "Even more.".into(), vec![])),
+    ]));
+    assert_eq!(render_md_docblock(&doc), md);
+    assert_eq!(render_md_toc(&doc), "\
+- Details
+- Parameters
+    - Options
+- Notes
+    - More notes
+- Examples
+    - Basic usage
+        - Really basic
+");
+}
+
+#[test]
+fn normalize_headlines() {
+    let doc = parse_md_docblock("Lorem ipsum\n\nParameters\n==========\n\n- `a`: A\n\n# *Notes*\n\nFoo\n").unwrap();
+    assert_eq!(render_md_docblock(&doc), "Lorem ipsum\n\n# Parameters\n\n- `a`: A\n\n# *Notes*\n\nFoo\n");
+}
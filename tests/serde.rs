@@ -0,0 +1,174 @@
+#![cfg(feature = "serde")]
+
+extern crate docstrings;
+#[macro_use] extern crate serde_json;
+use docstrings::*;
+
+const DOC: &str = r#"Lorem ipsum
+
+Dolor sit amet.
+
+## Details
+
+More details.
+
+# Parameters
+
+- `a` (u32, optional): A
+- `options`: Options
+    - `depth`: Depth
+
+# Type Parameters
+
+- `T`: Tee
+
+# Returns
+
+Something:
+
+- `Ok(_)`: Yes
+
+# Errors
+
+- `Error::Io`: On IO errors
+- When it rains
+
+# Panics
+
+- If `a` is zero
+
+# Safety
+
+None needed.
+
+# Notes
+
+Foo
+
+# Examples
+
+## Basic usage
+
+```rust,no_run
+# use foo::*;
+foo();
+```
+"#;
+
+#[test]
+fn json_shape() {
+    let doc = parse_md_docblock(DOC).unwrap();
+
+    assert_eq!(serde_json::to_value(&doc).unwrap(), json!({
+        "teaser": "Lorem ipsum",
+        "description": "Dolor sit amet.",
+        "subsections": [
+            {
+                "headline": "Details",
+                "section": { "kind": "custom", "headline": "", "docs": "More details.", "subsections": [] },
+            },
+        ],
+        "sections": [
+            {
+                "kind": "parameters",
+                "parameters": [
+                    {
+                        "pattern": "a",
+                        "docs": "A",
+                        "ty": "u32",
+                        "optional": true,
+                        "default": null,
+                        "constraints": [],
+                        "fields": [],
+                    },
+                    {
+                        "pattern": "options",
+                        "docs": "Options",
+                        "ty": null,
+                        "optional": false,
+                        "default": null,
+                        "constraints": [],
+                        "fields": [{ "name": "depth", "docs": "Depth" }],
+                    },
+                ],
+                "subsections": [],
+            },
+            {
+                "kind": "type_parameters",
+                "parameters": [{ "name": "T", "docs": "Tee" }],
+                "subsections": [],
+            },
+            {
+                "kind": "returns",
+                "docs": "Something:",
+                "variants": [{ "pattern": "Ok(_)", "docs": "Yes" }],
+                "subsections": [],
+            },
+            {
+                "kind": "errors",
+                "docs": "",
+                "conditions": [
+                    { "pattern": "Error::Io", "docs": "On IO errors" },
+                    { "pattern": null, "docs": "When it rains" },
+                ],
+                "subsections": [],
+            },
+            {
+                "kind": "panics",
+                "docs": "",
+                "conditions": ["If `a` is zero"],
+                "subsections": [],
+            },
+            { "kind": "safety", "docs": "None needed.", "subsections": [] },
+            { "kind": "custom", "headline": "Notes", "docs": "Foo", "subsections": [] },
+            {
+                "kind": "examples",
+                "examples": [],
+                "subsections": [
+                    {
+                        "headline": "Basic usage",
+                        "section": {
+                            "kind": "examples",
+                            "examples": [
+                                {
+                                    "description": "",
+                                    "code_blocks": [
+                                        {
+                                            "language": "rust",
+                                            "attributes": ["no_run"],
+                                            "lines": [
+                                                { "kind": "hidden", "text": "use foo::*;" },
+                                                { "kind": "visible", "text": "foo();" },
+                                            ],
+                                        },
+                                    ],
+                                },
+                            ],
+                            "subsections": [],
+                        },
+                    },
+                ],
+            },
+        ],
+    }));
+}
+
+#[test]
+fn roundtrip() {
+    let doc = parse_md_docblock(DOC).unwrap();
+    let json = serde_json::to_string(&doc).unwrap();
+    assert_eq!(serde_json::from_str::<DocBlock>(&json).unwrap(), doc);
+
+    let spanned = parse_md_docblock_spanned(DOC).unwrap();
+    let json = serde_json::to_string(&spanned).unwrap();
+    assert_eq!(serde_json::from_str::<SpannedDocBlock>(&json).unwrap(), spanned);
+}
+
+#[test]
+fn invalid_patterns() {
+    let json = r#"{ "kind": "returns", "docs": "", "variants": [{ "pattern": "1 +", "docs": "" }], "subsections": [] }"#;
+    assert!(serde_json::from_str::<DocSection>(json).is_err());
+
+    let json = r#"{ "kind": "type_parameters", "parameters": [{ "name": "not an ident", "docs": "" }], "subsections": [] }"#;
+    assert!(serde_json::from_str::<DocSection>(json).is_err());
+}
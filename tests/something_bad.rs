@@ -1,4 +1,5 @@
 extern crate docstrings;
+extern crate pulldown_cmark;
 
 use docstrings::*;
 
@@ -9,7 +10,7 @@ fn no_teaser() {
 # Parameters
 
 - `foo`: Bar").unwrap_err(),
-        docstrings::ParseError::NoTeaser
+        docstrings::ParseError::NoTeaser(Position { offset: 0, line: 1, column: 1 })
     );
 }
 
@@ -24,7 +25,9 @@ Lorem ipsum
 Dafuq
 
 - `foo`: Bar").unwrap_err(),
-        docstrings::ParseError::UnexpectedMarkdown("Parameters".into(), "Start(Paragraph)".into())
+        docstrings::ParseError::UnexpectedMarkdown(
+            "Parameters".into(), "Start(Paragraph)".into(),
+            Position { offset: 27, line: 5, column: 1 })
     );
 }
 
@@ -38,7 +41,9 @@ Lorem ipsum
 
 - Bar
     ").unwrap_err(),
-        docstrings::ParseError::NoIdent
+        docstrings::ParseError::NoIdent(
+            "Parameters".into(), "Bar".into(),
+            Position { offset: 27, line: 5, column: 1 })
     );
 }
 
@@ -54,6 +59,63 @@ Lorem ipsum
 - `foo`: Bar
 - `other` some other thing
     ").unwrap_err(),
-        docstrings::ParseError::WrongIdentDocsSeparator
+        docstrings::ParseError::WrongIdentDocsSeparator(
+            "Parameters".into(), "`other` some other thing".into(),
+            Position { offset: 48, line: 6, column: 9 })
+    );
+}
+
+#[test]
+fn invalid_ident() {
+    assert_eq!(
+        parse_md_docblock("Lorem ipsum\n\n# Type Parameters\n\n- `T U`: Tee\n").unwrap_err(),
+        docstrings::ParseError::InvalidIdent(
+            "Type Parameters".into(), "T U".into(),
+            Position { offset: 35, line: 5, column: 4 })
+    );
+}
+
+#[test]
+fn invalid_pattern() {
+    assert_eq!(
+        parse_md_docblock("Lorem ipsum\n\n# Returns\n\n- `Some(1 +)`: Huh\n").unwrap_err(),
+        docstrings::ParseError::InvalidPattern(
+            "Returns".into(), "Some(1 +)".into(),
+            Position { offset: 27, line: 5, column: 4 })
+    );
+
+    // In `Errors` sections, this is just text
+    let doc = parse_md_docblock("Lorem ipsum\n\n# Errors\n\n- `1 +`: Huh\n").unwrap();
+    assert_eq!(doc.sections, vec![
+        DocSection::Errors(String::new(), vec![(None, "`1 +`: Huh".into())], vec![]),
+    ]);
+}
+
+#[test]
+fn error_context() {
+    let err = parse_md_docblock("\
+Lorem ipsum
+
+# Type parameters
+
+- `T`: Foo
+- `U`: Bar
+- V: Baz
+    ").unwrap_err();
+
+    assert_eq!(err.section(), Some("Type parameters"));
+    assert_eq!(err.item(), Some("V: Baz"));
+    assert_eq!((err.position().line, err.position().column), (7, 1));
+    assert_eq!(err.to_string(),
+               "7:1: No identifier in list item \"V: Baz\" of section `Type parameters`");
+}
+
+#[test]
+fn events_have_no_location() {
+    let mut events = pulldown_cmark::Parser::new("# Parameters").peekable();
+
+    assert_eq!(
+        parse_md_docblock_events(&mut events).unwrap_err().position(),
+        Position::default()
     );
 }
@@ -11,6 +11,7 @@ Lorem ipsum
         DocBlock {
             teaser: "Lorem ipsum".into(),
             description: None,
+            subsections: vec![],
             sections: vec![],
         }
     );
@@ -29,6 +30,7 @@ to see that this is actually just one more paragraph in Markdown land.
             teaser: "Lorem ipsum".into(),
             description: Some("A longer description lorem ipsum dolor sit amet. With multiple lines, of course,
 to see that this is actually just one more paragraph in Markdown land.".into()),
+            subsections: vec![],
             sections: vec![],
         }
     );
@@ -64,21 +66,44 @@ But actually, let's add more fancy stuff, e.g., a list:
 
 - Yes, a list
 - Lists are cool
-- I write lists now
-
+- I write lists now".into()),
+            subsections: vec![
+                ("Sub-headline for description".into(), DocSection::Custom(
+                    String::new(),
+                    "Yes, we are using subheadlines as well. To confuse everyone.\n\nVery nice.".into(),
+                    vec![],
+                )),
+            ],
+            sections: vec![],
+        }
+    );
+}
 
-## Sub-headline for description
+#[test]
+fn parameters() {
+    assert_eq!(
+        parse_md_docblock("\
+Lorem ipsum
 
-Yes, we are using subheadlines as well. To confuse everyone.
+# Parameters
 
-Very nice.".into()),
-            sections: vec![],
+- `foo`: Bar
+        ").unwrap(),
+        DocBlock {
+            teaser: "Lorem ipsum".into(),
+            description: None,
+            subsections: vec![],
+            sections: vec![
+                DocSection::Parameters(vec![
+                    ("foo".parse().unwrap(), "Bar".into()),
+                ], vec![]),
+            ],
         }
     );
 }
 
 #[test]
-fn parameters() {
+fn block_content_in_items() {
     assert_eq!(
         parse_md_docblock("\
 Lorem ipsum
@@ -86,14 +111,191 @@ Lorem ipsum
 # Parameters
 
 - `foo`: Bar
+
+    More about bar.
+
+    ```rust
+    let bar = 42;
+    ```
+- `baz`: Baz, one of
+
+    1. Quux
+    2. Quuux
+
+# Returns
+
+- `Ok(_)`: Yes
+
+    Really.
         ").unwrap(),
         DocBlock {
             teaser: "Lorem ipsum".into(),
             description: None,
+            subsections: vec![],
             sections: vec![
                 DocSection::Parameters(vec![
-                    ("foo".into(), "Bar".into()),
-                ]),
+                    ("foo".parse().unwrap(), "Bar\n\nMore about bar.\n\n```rust\nlet bar = 42;\n```".into()),
+                    ("baz".parse().unwrap(), "Baz, one of\n\n1. Quux\n2. Quuux".into()),
+                ], vec![]),
+                DocSection::Returns(String::new(), vec![
+                    ("Ok(_)".parse().unwrap(), "Yes\n\nReally.".into()),
+                ], vec![]),
+            ],
+        }
+    );
+}
+
+#[test]
+fn parameter_fields() {
+    assert_eq!(
+        parse_md_docblock("\
+Lorem ipsum
+
+# Parameters
+
+- `options`: How to frobnicate
+    - `depth`: How deep to go
+    - `force`: Whether to go on
+        after errors
+- `names`: Things to frobnicate, e.g.
+    - foo
+    - `bar`: Bar
+        ").unwrap(),
+        DocBlock {
+            teaser: "Lorem ipsum".into(),
+            description: None,
+            subsections: vec![],
+            sections: vec![
+                DocSection::Parameters(vec![
+                    ("options".parse().unwrap(), ParameterDoc {
+                        docs: "How to frobnicate".into(),
+                        fields: vec![
+                            ("depth".parse().unwrap(), "How deep to go".into()),
+                            ("force".parse().unwrap(), "Whether to go on\nafter errors".into()),
+                        ],
+                        ..ParameterDoc::default()
+                    }),
+                    // Not a list of fields
+                    ("names".parse().unwrap(), "Things to frobnicate, e.g.\n- foo\n- `bar`: Bar".into()),
+                ], vec![]),
+            ],
+        }
+    );
+}
+
+#[test]
+fn parameter_annotations() {
+    assert_eq!(
+        parse_md_docblock("\
+Lorem ipsum
+
+# Parameters
+
+- `timeout` (Duration, optional, default: 30s): How long to wait
+- `retries` (`u32`, at least 1, at most 10): How often to try
+- `headers` (HashMap<String, String>): Extra headers
+- `token` (optional): Who we are
+- `mode` (required, defaults to `Mode::Fast`): How to go
+- `delay` (at least 1s): How long to sleep
+        ").unwrap(),
+        DocBlock {
+            teaser: "Lorem ipsum".into(),
+            description: None,
+            subsections: vec![],
+            sections: vec![
+                DocSection::Parameters(vec![
+                    ("timeout".parse().unwrap(), ParameterDoc {
+                        docs: "How long to wait".into(),
+                        ty: Some("Duration".into()),
+                        optional: true,
+                        default: Some("30s".into()),
+                        ..ParameterDoc::default()
+                    }),
+                    ("retries".parse().unwrap(), ParameterDoc {
+                        docs: "How often to try".into(),
+                        ty: Some("u32".into()),
+                        constraints: vec!["at least 1".into(), "at most 10".into()],
+                        ..ParameterDoc::default()
+                    }),
+                    ("headers".parse().unwrap(), ParameterDoc {
+                        docs: "Extra headers".into(),
+                        ty: Some("HashMap<String, String>".into()),
+                        ..ParameterDoc::default()
+                    }),
+                    ("token".parse().unwrap(), ParameterDoc {
+                        docs: "Who we are".into(),
+                        optional: true,
+                        ..ParameterDoc::default()
+                    }),
+                    ("mode".parse().unwrap(), ParameterDoc {
+                        docs: "How to go".into(),
+                        default: Some("Mode::Fast".into()),
+                        ..ParameterDoc::default()
+                    }),
+                    ("delay".parse().unwrap(), ParameterDoc {
+                        docs: "How long to sleep".into(),
+                        constraints: vec!["at least 1s".into()],
+                        ..ParameterDoc::default()
+                    }),
+                ], vec![]),
+            ],
+        }
+    );
+}
+
+#[test]
+fn definition_lists() {
+    assert_eq!(
+        parse_md_docblock("\
+Lorem ipsum
+
+# Parameters
+
+`foo`
+    A foo
+`bar` (u32, optional)
+    A bar, which is
+    quite long
+
+`baz`
+    A baz
+
+# Type Parameters
+
+`T`
+    Tee
+
+# Returns
+
+Either
+
+`Ok(_)`
+    Yes
+`Err(_)`
+    No
+        ").unwrap(),
+        DocBlock {
+            teaser: "Lorem ipsum".into(),
+            description: None,
+            subsections: vec![],
+            sections: vec![
+                DocSection::Parameters(vec![
+                    ("foo".parse().unwrap(), "A foo".into()),
+                    ("bar".parse().unwrap(), ParameterDoc {
+                        docs: "A bar, which is\nquite long".into(),
+                        ty: Some("u32".into()),
+                        optional: true,
+                        ..ParameterDoc::default()
+                    }),
+                    ("baz".parse().unwrap(), "A baz".into()),
+                ], vec![]),
+                DocSection::TypeParameters(vec![
+                    ("T".parse().unwrap(), "Tee".into()),
+                ], vec![]),
+                DocSection::Returns("Either".into(), vec![
+                    ("Ok(_)".parse().unwrap(), "Yes".into()),
+                    ("Err(_)".parse().unwrap(), "No".into()),
+                ], vec![]),
             ],
         }
     );
@@ -112,10 +314,11 @@ Lorem ipsum
         DocBlock {
             teaser: "Lorem ipsum".into(),
             description: None,
+            subsections: vec![],
             sections: vec![
                 DocSection::TypeParameters(vec![
-                    ("T".into(), "Some type".into()),
-                ]),
+                    ("T".parse().unwrap(), "Some type".into()),
+                ], vec![]),
             ],
         }
     );
@@ -134,10 +337,11 @@ Lorem ipsum
         DocBlock {
             teaser: "Lorem ipsum".into(),
             description: None,
+            subsections: vec![],
             sections: vec![
                 DocSection::LifetimeParameters(vec![
-                    ("'foo".into(), "The life time of foo".into()),
-                ]),
+                    ("'foo".parse().unwrap(), "The life time of foo".into()),
+                ], vec![]),
             ],
         }
     );
@@ -159,14 +363,15 @@ This returns a wonderful `Result`, with is either:
         DocBlock {
             teaser: "Lorem ipsum".into(),
             description: None,
+            subsections: vec![],
             sections: vec![
                 DocSection::Returns(
                     "This returns a wonderful `Result`, with is either:".into(),
                     vec![
-                        ("Ok(Wonderful)".into(), "A gloriously positive answer".into()),
-                        ("Err(Misantropy)".into(), "Also a valid answer".into()),
+                        ("Ok(Wonderful)".parse().unwrap(), "A gloriously positive answer".into()),
+                        ("Err(Misantropy)".parse().unwrap(), "Also a valid answer".into()),
                     ]
-                ),
+                , vec![]),
             ],
         }
     );
@@ -189,9 +394,10 @@ dolor sit amet
         DocBlock {
             teaser: "Lorem ipsum".into(),
             description: None,
+            subsections: vec![],
             sections: vec![
-                DocSection::Custom("Custom1".into(), "Lorem ipsum".into()),
-                DocSection::Custom("Custom Two".into(), "dolor sit amet".into()),
+                DocSection::Custom("Custom1".into(), "Lorem ipsum".into(), vec![]),
+                DocSection::Custom("Custom Two".into(), "dolor sit amet".into(), vec![]),
             ],
         }
     );
@@ -258,41 +464,338 @@ But actually, let's add more fancy stuff, e.g., a list:
 
 - Yes, a list
 - Lists are cool
-- I write lists now
-
-
-## Sub-headline for description
-
-Yes, we are using subheadlines as well. To confuse everyone.
-
-Very nice.".into()),
+- I write lists now".into()),
+            subsections: vec![
+                ("Sub-headline for description".into(), DocSection::Custom(
+                    String::new(),
+                    "Yes, we are using subheadlines as well. To confuse everyone.\n\nVery nice.".into(),
+                    vec![],
+                )),
+            ],
             sections: vec![
                 DocSection::Parameters(vec![
-                    ("label".into(), "A string labelling the foo".into()),
-                    ("magic".into(), "A `Foo` that will be labeled".into()),
-                ]),
+                    ("label".parse().unwrap(), "A string labelling the foo".into()),
+                    ("magic".parse().unwrap(), "A `Foo` that will be labeled".into()),
+                ], vec![]),
                 DocSection::Returns(
                     "A `Result` which is:".into(),
                     vec![
-                        ("Ok".into(), "A `Bar` that is the labeled `Foo` and thus lives as long as the
+                        ("Ok".parse().unwrap(), "A `Bar` that is the labeled `Foo` and thus lives as long as the
 `Foo` given in `magic`.".into()),
-                        ("Err".into(), "Returns the number of gravely appalled people (per half-century
+                        ("Err".parse().unwrap(), "Returns the number of gravely appalled people (per half-century
 per country) if you were to use that label *and* `Foo`'s acceptance
 indicator is less than it.".into()),
                     ]
-                ),
+                , vec![]),
                 DocSection::TypeParameters(vec![
-                    ("T".into(), "A type that can be converted into a `FooLabel`, e.g. a `String`, a
+                    ("T".parse().unwrap(), "A type that can be converted into a `FooLabel`, e.g. a `String`, a
 `BananaPeelRope`, or a `Cow<str>`.".into()),
-                ]),
+                ], vec![]),
                 DocSection::LifetimeParameters(vec![
-                    ("floof".into(), "The life time of the given foo as determined by the floof source
+                    ("floof".parse().unwrap(), "The life time of the given foo as determined by the floof source
 it was originally loaded from.".into()),
+                ], vec![]),
+                DocSection::Examples(vec![
+                    Example {
+                        description: "".into(),
+                        code_blocks: vec![
+                            CodeBlock {
+                                language: Some("rust".into()),
+                                attributes: vec![],
+                                lines: vec![
+                                    CodeLine::Visible(r#"assert_eq!(fooify("lorem", Foo::extract_from_global_floof_resource()).label(),"#.into()),
+                                    CodeLine::Visible(r#"           Bar::with_label("lorem"))"#.into()),
+                                ],
+                            },
+                        ],
+                    },
+                ], vec![]),
+            ],
+        }
+    );
+}
+
+#[test]
+fn headline_aliases() {
+    assert_eq!(
+        parse_md_docblock("\
+Lorem ipsum
+
+# Arguments
+
+- `foo`: Bar
+
+# Generics
+
+- `T`: Some type
+
+# Return value
+
+Nothing much
+        ").unwrap(),
+        DocBlock {
+            teaser: "Lorem ipsum".into(),
+            description: None,
+            subsections: vec![],
+            sections: vec![
+                DocSection::Parameters(vec![
+                    ("foo".parse().unwrap(), "Bar".into()),
+                ], vec![]),
+                DocSection::TypeParameters(vec![
+                    ("T".parse().unwrap(), "Some type".into()),
+                ], vec![]),
+                DocSection::Returns("Nothing much".into(), vec![], vec![]),
+            ],
+        }
+    );
+}
+
+#[test]
+fn panics_errors_and_safety() {
+    assert_eq!(
+        parse_md_docblock("\
+Lorem ipsum
+
+# Panics
+
+Panics if `x` is zero.
+
+# Errors
+
+Fails when:
+
+- `Error::NotFound`: The file does not exist
+- the disk is full
+
+# Safety
+
+`ptr` must be valid.
+        ").unwrap(),
+        DocBlock {
+            teaser: "Lorem ipsum".into(),
+            description: None,
+            subsections: vec![],
+            sections: vec![
+                DocSection::Panics("Panics if `x` is zero.".into(), vec![], vec![]),
+                DocSection::Errors("Fails when:".into(), vec![
+                    (Some("Error::NotFound".parse().unwrap()), "The file does not exist".into()),
+                    (None, "the disk is full".into()),
+                ], vec![]),
+                DocSection::Safety("`ptr` must be valid.".into(), vec![]),
+            ],
+        }
+    );
+}
+
+#[test]
+fn panic_conditions() {
+    assert_eq!(
+        parse_md_docblock("\
+Lorem ipsum
+
+# Panics
+
+- If `x` is zero
+- If `y` is
+    greater than `x`
+        ").unwrap().sections,
+        vec![
+            DocSection::Panics("".into(), vec![
+                "If `x` is zero".into(),
+                "If `y` is\ngreater than `x`".into(),
+            ], vec![]),
+        ]
+    );
+}
+
+#[test]
+fn text_after_conditions() {
+    assert_eq!(
+        parse_md_docblock("\
+Lorem ipsum
+
+# Errors
+
+- `Error::NotFound`: The file does not exist
+
+Other I/O errors are passed on.
+
+# Panics
+
+- If `x` is zero
+
+Never panics in release builds.
+        ").unwrap().sections,
+        vec![
+            DocSection::Errors("Other I/O errors are passed on.".into(), vec![
+                (Some("Error::NotFound".parse().unwrap()), "The file does not exist".into()),
+            ], vec![]),
+            DocSection::Panics("Never panics in release builds.".into(), vec![
+                "If `x` is zero".into(),
+            ], vec![]),
+        ]
+    );
+}
+
+#[test]
+fn subsections() {
+    assert_eq!(
+        parse_md_docblock("\
+Lorem ipsum
+
+# Examples
+
+## Basic usage
+
+```rust
+foo();
+```
+
+## Advanced
+
+### With bar
+
+```rust
+foo_with(bar);
+```
+
+# Notes
+
+Some notes.
+
+## More notes
+
+Even more.
+        ").unwrap(),
+        DocBlock {
+            teaser: "Lorem ipsum".into(),
+            description: None,
+            subsections: vec![],
+            sections: vec![
+                DocSection::Examples(vec![], vec![
+                    ("Basic usage".into(), DocSection::Examples(vec![
+                        Example {
+                            description: "".into(),
+                            code_blocks: vec![CodeBlock {
+                                language: Some("rust".into()),
+                                attributes: vec![],
+                                lines: vec![CodeLine::Visible("foo();".into())],
+                            }],
+                        },
+                    ], vec![])),
+                    ("Advanced".into(), DocSection::Examples(vec![], vec![
+                        ("With bar".into(), DocSection::Examples(vec![
+                            Example {
+                                description: "".into(),
+                                code_blocks: vec![CodeBlock {
+                                    language: Some("rust".into()),
+                                    attributes: vec![],
+                                    lines: vec![CodeLine::Visible("foo_with(bar);".into())],
+                                }],
+                            },
+                        ], vec![])),
+                    ])),
+                ]),
+                DocSection::Custom("Notes".into(), "Some notes.".into(), vec![
+                    ("More notes".into(), DocSection::Custom(String::new(), "Even more.".into(), vec![])),
+                ]),
+            ],
+        }
+    );
+}
+
+#[test]
+fn subsections_of_lists() {
+    let doc = parse_md_docblock("\
+Lorem ipsum
+
+# Parameters
+
+- `a`: A
+
+## Options
+
+- `b`: B
+    ").unwrap();
+
+    assert_eq!(doc.sections, vec![
+        DocSection::Parameters(vec![("a".parse().unwrap(), "A".into())], vec![
+            ("Options".into(), DocSection::Parameters(vec![("b".parse().unwrap(), "B".into())], vec![])),
+        ]),
+    ]);
+}
+
+#[test]
+fn formatted_headlines() {
+    assert_eq!(
+        parse_md_docblock("\
+Lorem ipsum
+
+# `Parameters`
+
+- `a`: A
+
+# *Returns*
+
+Nothing
+
+# [Examples](https://doc.rust-lang.org/rustdoc/)
+
+Foo
+
+# **Notes** and *more*
+
+Bar
+        ").unwrap(),
+        DocBlock {
+            teaser: "Lorem ipsum".into(),
+            description: None,
+            subsections: vec![],
+            sections: vec![
+                DocSection::Parameters(vec![("a".parse().unwrap(), "A".into())], vec![]),
+                DocSection::Returns("Nothing".into(), vec![], vec![]),
+                DocSection::Examples(vec![
+                    Example { description: "Foo".into(), code_blocks: vec![] },
+                ], vec![]),
+                DocSection::Custom("**Notes** and *more*".into(), "Bar".into(), vec![]),
+            ],
+        }
+    );
+}
+
+#[test]
+fn setext_headlines() {
+    assert_eq!(
+        parse_md_docblock("\
+Lorem ipsum
+
+Dolor sit amet.
+
+Details
+-------
+
+More details.
+
+Parameters
+==========
+
+- `a`: A
+
+Options
+-------
+
+- `b`: B
+        ").unwrap(),
+        DocBlock {
+            teaser: "Lorem ipsum".into(),
+            description: Some("Dolor sit amet.".into()),
+            subsections: vec![
+                ("Details".into(), DocSection::Custom(String::new(), "More details.".into(), vec![])),
+            ],
+            sections: vec![
+                DocSection::Parameters(vec![("a".parse().unwrap(), "A".into())], vec![
+                    ("Options".into(), DocSection::Parameters(vec![("b".parse().unwrap(), "B".into())], vec![])),
                 ]),
-                DocSection::Custom("Examples".into(), r#"```rust
-assert_eq!(fooify("lorem", Foo::extract_from_global_floof_resource()).label(),
-           Bar::with_label("lorem"))
-```"#.into()),
             ],
         }
     );
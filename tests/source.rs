@@ -0,0 +1,224 @@
+#![cfg(feature = "source")]
+
+extern crate docstrings;
+use docstrings::*;
+
+const SOURCE: &str = r##"//! Crate docs
+//!
+//! With a description.
+
+/// Lorem ipsum
+///
+/// # Parameters
+///
+/// - `x`: Foo
+pub fn foo(x: u32) -> u32 { x }
+
+/**
+ * A struct
+ *
+ * With a block comment.
+ */
+pub struct Bar<T> where T: Clone {
+    t: T,
+}
+
+#[doc = "A tuple struct"]
+struct Baz(u32);
+
+/// An enum
+pub enum Quux { A, B }
+
+/// A trait
+pub trait Frob {
+    /// Frobnicate
+    fn frob(&self) -> bool;
+}
+
+impl<T: Clone> Bar<T> {
+    /// Make a new `Bar`
+    pub fn new(t: T) -> Self { Bar { t: t } }
+
+    fn undocumented(&self) {}
+}
+
+impl<T: Clone> Frob for Bar<T> {
+    fn frob(&self) -> bool { true }
+}
+
+/// Inner stuff
+mod inner {
+    //! More docs
+
+    /// Not visible outside
+    pub fn hidden() {}
+}
+"##;
+
+#[test]
+fn items() {
+    let items = parse_rust_source(SOURCE).unwrap();
+    let summary: Vec<_> = items.iter()
+        .map(|item| (item.kind, &item.path[..], &item.signature[..], item.public))
+        .collect();
+
+    assert_eq!(summary, vec![
+        (ItemKind::Module, "", "", true),
+        (ItemKind::Function, "foo", "pub fn foo(x: u32) -> u32", true),
+        (ItemKind::Struct, "Bar", "pub struct Bar<T> where T: Clone", true),
+        (ItemKind::Struct, "Baz", "struct Baz(u32)", false),
+        (ItemKind::Enum, "Quux", "pub enum Quux", true),
+        (ItemKind::Trait, "Frob", "pub trait Frob", true),
+        (ItemKind::Method, "Frob::frob", "fn frob(&self) -> bool", true),
+        (ItemKind::Method, "Bar::new", "pub fn new(t: T) -> Self", true),
+        (ItemKind::Method, "Bar::undocumented", "fn undocumented(&self)", false),
+        (ItemKind::Method, "Bar::frob", "fn frob(&self) -> bool", false),
+        (ItemKind::Module, "inner", "mod inner", false),
+        (ItemKind::Function, "inner::hidden", "pub fn hidden()", false),
+    ]);
+}
+
+#[test]
+fn docs() {
+    let items = parse_rust_source(SOURCE).unwrap();
+    let docs: Vec<_> = items.iter().map(|item| item.docs.as_ref().map(|d| &d[..])).collect();
+
+    assert_eq!(docs, vec![
+        Some("Crate docs\n\nWith a description."),
+        Some("Lorem ipsum\n\n# Parameters\n\n- `x`: Foo"),
+        Some("A struct\n\nWith a block comment."),
+        Some("A tuple struct"),
+        Some("An enum"),
+        Some("A trait"),
+        Some("Frobnicate"),
+        Some("Make a new `Bar`"),
+        None,
+        None,
+        Some("Inner stuff\nMore docs"),
+        Some("Not visible outside"),
+    ]);
+
+    assert_eq!(items[1].doc.clone().map(|doc| doc.map(|doc| doc.doc)), Some(Ok(DocBlock {
+        teaser: "Lorem ipsum".into(),
+        description: None,
+        subsections: vec![],
+        sections: vec![
+            DocSection::Parameters(vec![("x".parse().unwrap(), "Foo".into())], vec![]),
+        ],
+    })));
+    assert!(items[8].doc.is_none());
+}
+
+#[test]
+fn hard_breaks() {
+    let source = "/// Lorem ipsum\n///\n/// Roses are red,  \n/// violets are blue.\r\npub fn foo() {}\n";
+    let items = parse_rust_source(source).unwrap();
+
+    assert_eq!(items[0].docs.as_ref().unwrap(), "Lorem ipsum\n\nRoses are red,  \nviolets are blue.");
+    let doc = items[0].doc.clone().unwrap().unwrap().doc;
+    // Still a hard break, written the canonical way
+    assert_eq!(doc.description, Some("Roses are red,\\\nviolets are blue.".into()));
+}
+
+#[test]
+fn locations() {
+    let items = parse_rust_source(SOURCE).unwrap();
+
+    let foo = &items[1];
+    assert_eq!(&SOURCE[foo.span.range()], "pub fn foo(x: u32) -> u32");
+    assert_eq!((foo.span.start.line, foo.span.start.column), (10, 1));
+
+    // `- `x`: Foo` is on line 5 of the docs, and line 9 of the file
+    let position = Position { offset: 29, line: 5, column: 3 };
+    assert_eq!(&foo.docs.as_ref().unwrap()[position.offset..], "`x`: Foo");
+    let in_source = foo.source_position(position);
+    assert_eq!((in_source.line, in_source.column), (9, 7));
+    assert_eq!(&SOURCE[in_source.offset..in_source.offset + 8], "`x`: Foo");
+
+    let bar = &items[2];
+    let in_source = bar.source_position(Position { offset: 10, line: 3, column: 1 });
+    assert_eq!((in_source.line, in_source.column), (15, 4));
+}
+
+#[test]
+fn syntax_errors() {
+    match parse_rust_source("fn foo() {\n    let x = ;\n}\n") {
+        Err(SourceError::Syntax(_, position)) => assert_eq!(position.line, 2),
+        other => panic!("expected a syntax error, got {:?}", other),
+    }
+}
+
+/// Write files (given as path and content) to a fresh directory
+fn write_files(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+    let dir = std::path::PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    for &(path, content) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn crates() {
+    let dir = write_files("source_crates", &[
+        ("src/lib.rs", "//! Root\n\n/// Foo\npub mod foo;\nmod bar;\npub mod inline {\n    pub mod nested;\n}\nmod missing;\n"),
+        ("src/foo.rs", "/// Frob\npub fn frob() {}\n\npub mod baz;\n"),
+        ("src/foo/baz.rs", "/// Baz\npub fn baz() {}\n"),
+        ("src/bar/mod.rs", "#[path = \"other.rs\"]\npub mod quux;\n"),
+        ("src/bar/other.rs", "/// Quux\npub fn quux() {}\n"),
+        ("src/inline/nested.rs", "/// Nested\npub fn nested() {}\n"),
+    ]);
+
+    let files = parse_rust_crate(dir.join("src/lib.rs"));
+    let summary: Vec<_> = files.iter()
+        .map(|file| (file.path.strip_prefix(&dir).unwrap().to_str().unwrap().to_string(), &file.module[..]))
+        .collect();
+    assert_eq!(summary, vec![
+        ("src/lib.rs".to_string(), ""),
+        ("src/foo.rs".to_string(), "foo"),
+        ("src/foo/baz.rs".to_string(), "foo::baz"),
+        ("src/bar/mod.rs".to_string(), "bar"),
+        ("src/bar/other.rs".to_string(), "bar::quux"),
+        ("src/inline/nested.rs".to_string(), "inline::nested"),
+    ]);
+
+    let items: Vec<_> = files.iter()
+        .flat_map(|file| file.items.as_ref().unwrap().iter())
+        .filter(|item| item.kind == ItemKind::Function)
+        .map(|item| (&item.path[..], item.public))
+        .collect();
+    assert_eq!(items, vec![
+        ("foo::frob", true),
+        ("foo::baz::baz", true),
+        ("bar::quux::quux", false),
+        ("inline::nested::nested", true),
+    ]);
+}
+
+#[test]
+fn crates_with_errors() {
+    let dir = write_files("source_crates_with_errors", &[
+        ("src/lib.rs", "pub mod broken;\n"),
+        ("src/broken.rs", "pub fn broken(\n"),
+    ]);
+
+    let files = parse_rust_crate(dir.join("src/lib.rs"));
+    assert_eq!(files.len(), 2);
+    match files[1].items {
+        Err(SourceError::Syntax(_, position)) => assert_eq!(position.line, 1),
+        ref other => panic!("expected a syntax error, got {:?}", other),
+    }
+}
+
+#[test]
+fn own_docs() {
+    // Docs with types where patterns go, like `Ok((DocBlock, Vec<ParseError>))`
+    for file in &["src/lib.rs", "src/patterns.rs", "src/source.rs"] {
+        let items = parse_rust_file(format!("{}/{}", env!("CARGO_MANIFEST_DIR"), file)).unwrap();
+        for item in items {
+            assert!(item.doc.map(|doc| doc.is_ok()).unwrap_or(true), "docs of `{}` in {}", item.path, file);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+extern crate docstrings;
+
+use docstrings::*;
+
+const DOC: &str = "\
+Lorem ipsum
+
+Dolor sit amet.
+
+# Parameters
+
+- `foo`: Bar
+- `baz`: Quux and
+    more quux
+
+# Returns
+
+Something:
+
+- `Ok(_)`: Yes
+";
+
+fn text(span: Span) -> &'static str {
+    &DOC[span.range()]
+}
+
+#[test]
+fn teaser_and_description() {
+    let spanned = parse_md_docblock_spanned(DOC).unwrap();
+
+    assert_eq!(spanned.doc, parse_md_docblock(DOC).unwrap());
+    assert_eq!(text(spanned.teaser), "Lorem ipsum");
+    assert_eq!(text(spanned.description.unwrap()), "Dolor sit amet.");
+    assert_eq!(spanned.description.unwrap().start,
+               Position { offset: 13, line: 3, column: 1 });
+}
+
+#[test]
+fn section_headlines() {
+    let spanned = parse_md_docblock_spanned(DOC).unwrap();
+
+    assert_eq!(spanned.sections.len(), 2);
+    assert_eq!(text(spanned.sections[0].headline), "Parameters");
+    assert_eq!(spanned.sections[0].headline.start.line, 5);
+    assert_eq!(text(spanned.sections[1].headline), "Returns");
+    assert_eq!(spanned.sections[1].headline.start.line, 11);
+    assert!(text(spanned.sections[1].span).starts_with("# Returns"));
+    assert!(text(spanned.sections[1].span).ends_with("Yes"));
+}
+
+#[test]
+fn list_items() {
+    let spanned = parse_md_docblock_spanned(DOC).unwrap();
+    let items = &spanned.sections[0].items;
+
+    assert_eq!(items.len(), 2);
+    assert_eq!(text(items[0].span), "- `foo`: Bar");
+    assert_eq!(text(items[0].ident), "foo");
+    assert_eq!(text(items[0].docs), "Bar");
+    assert_eq!(text(items[1].ident), "baz");
+    assert_eq!(text(items[1].docs), "Quux and\n    more quux");
+    assert_eq!(items[1].docs.start, Position { offset: 66, line: 8, column: 10 });
+    assert_eq!(items[1].docs.end.line, 9);
+
+    let items = &spanned.sections[1].items;
+    assert_eq!(items.len(), 1);
+    assert_eq!(text(items[0].ident), "Ok(_)");
+    assert_eq!(text(items[0].docs), "Yes");
+}
+
+#[test]
+fn custom_sections_have_no_items() {
+    let spanned = parse_md_docblock_spanned("Lorem\n\n# Custom\n\nIpsum\n").unwrap();
+
+    assert_eq!(spanned.sections.len(), 1);
+    assert!(spanned.sections[0].items.is_empty());
+}
+
+#[test]
+fn field_items() {
+    let md = "Lorem\n\n# Parameters\n\n- `options`: Options\n    - `depth`: Depth\n";
+    let spanned = parse_md_docblock_spanned(md).unwrap();
+    let item = &spanned.sections[0].items[0];
+
+    assert_eq!(&md[item.docs.range()], "Options");
+    assert_eq!(&md[item.span.range()], "- `options`: Options\n    - `depth`: Depth");
+    assert_eq!(item.fields.len(), 1);
+    assert_eq!(&md[item.fields[0].ident.range()], "depth");
+    assert_eq!(&md[item.fields[0].docs.range()], "Depth");
+    assert_eq!(item.fields[0].ident.start, Position { offset: 49, line: 6, column: 8 });
+}
+
+#[test]
+fn annotated_items() {
+    let md = "Lorem\n\n# Parameters\n\n- `timeout` (Duration, optional): How long\n- `n` (u32)\n  How many\n";
+    let options = ParseOptions {
+        separators: vec![Separator::Colon, Separator::Newline],
+        ..ParseOptions::default()
+    };
+    let (spanned, _) = parse_md_docblock_spanned_with(md, &options).unwrap();
+    let items = &spanned.sections[0].items;
+
+    assert_eq!(&md[items[0].ident.range()], "timeout");
+    assert_eq!(&md[items[0].docs.range()], "How long");
+    assert_eq!(&md[items[1].docs.range()], "How many");
+    assert_eq!(items[1].docs.start, Position { offset: 78, line: 7, column: 3 });
+}
+
+#[test]
+fn subsections() {
+    let md = "Lorem\n\n## Details\n\nMore\n\n# Parameters\n\n- `a`: A\n\n## Options\n\n- `b`: B\n";
+    let spanned = parse_md_docblock_spanned(md).unwrap();
+
+    assert_eq!(&md[spanned.subsections[0].headline.range()], "Details");
+    assert_eq!(&md[spanned.subsections[0].span.range()], "## Details\n\nMore");
+
+    let options = &spanned.sections[0].subsections[0];
+    assert_eq!(&md[options.headline.range()], "Options");
+    assert_eq!(options.headline.start, Position { offset: 52, line: 11, column: 4 });
+    assert_eq!(&md[options.items[0].ident.range()], "b");
+    assert!(&md[spanned.sections[0].span.range()].ends_with("- `b`: B"));
+}
+
+#[test]
+fn formatted_and_setext_headlines() {
+    let md = "Lorem\n\n# `Returns`\n\nFoo\n\nNotes\n=====\n\nBar\n";
+    let spanned = parse_md_docblock_spanned(md).unwrap();
+
+    assert_eq!(&md[spanned.sections[0].headline.range()], "`Returns`");
+    assert_eq!(&md[spanned.sections[1].headline.range()], "Notes");
+    assert_eq!(&md[spanned.sections[1].span.range()], "Notes\n=====\n\nBar");
+}
@@ -0,0 +1,316 @@
+#![cfg(feature = "source")]
+
+extern crate docstrings;
+extern crate syn;
+use docstrings::*;
+
+fn sig(code: &str) -> syn::Signature {
+    syn::parse_str(code).unwrap()
+}
+
+fn validate(code: &str, md: &str) -> Vec<ValidationError> {
+    let doc = parse_md_docblock_spanned(md).unwrap();
+    validate_parameters_spanned(&sig(code), &doc)
+}
+
+fn pos(offset: usize, line: usize, column: usize) -> Position {
+    Position { offset, line, column }
+}
+
+#[test]
+fn all_documented() {
+    assert_eq!(validate(
+        "fn foo(&self, bar: u32, mut baz: u32, r#type: u32, _unused: u32)",
+        "Lorem ipsum\n\n# Parameters\n\n- `bar`: Bar\n- `baz`: Baz\n- `type`: Type\n",
+    ), vec![]);
+}
+
+#[test]
+fn patterns() {
+    assert_eq!(validate(
+        "fn foo((a, b): (u32, u32), Point { x, y }: Point, mut c: u32)",
+        "Lorem ipsum\n\n# Parameters\n\n- `(a,b)`: A and B\n- `Point { x, y }`: Point\n- `mut c`: C\n",
+    ), vec![]);
+
+    let errors = validate("fn foo((a, b): (u32, u32), _: u32)", "Lorem ipsum\n\n# Parameters\n\n- `a`: A\n");
+    let names: Vec<_> = errors.iter().map(|e| e.name()).collect();
+    assert_eq!(names, vec!["a", "(a, b)"]);
+}
+
+#[test]
+fn undocumented() {
+    assert_eq!(validate(
+        "fn foo(bar: u32, baz: u32)",
+        "Lorem ipsum\n\n# Parameters\n\n- `bar`: Bar\n",
+    ), vec![
+        ValidationError::UndocumentedParameter("baz".into(), pos(15, 3, 3)),
+    ]);
+
+    // Without a `Parameters` section, nothing is checked
+    assert_eq!(validate("fn foo(bar: u32)", "Lorem ipsum\n"), vec![]);
+}
+
+#[test]
+fn nonexistent() {
+    assert_eq!(validate(
+        "fn foo(bar: u32)",
+        "Lorem ipsum\n\n# Parameters\n\n- `bar`: Bar\n- `baz`: Baz\n",
+    ), vec![
+        ValidationError::UnknownParameter("baz".into(), pos(43, 6, 4)),
+    ]);
+}
+
+#[test]
+fn order() {
+    assert_eq!(validate(
+        "fn foo(a: u32, b: u32, c: u32)",
+        "Lorem ipsum\n\n# Parameters\n\n- `a`: A\n- `c`: C\n- `b`: B\n",
+    ), vec![
+        ValidationError::MisorderedParameter("b".into(), pos(48, 7, 4)),
+    ]);
+}
+
+#[test]
+fn duplicates() {
+    assert_eq!(validate(
+        "fn foo(a: u32)",
+        "Lorem ipsum\n\n# Parameters\n\n- `a`: A\n- `a`: A again\n",
+    ), vec![
+        ValidationError::DuplicateParameter("a".into(), pos(39, 6, 4)),
+    ]);
+}
+
+#[test]
+fn without_spans() {
+    let doc = parse_md_docblock("Lorem ipsum\n\n# Parameters\n\n- `b`: B\n- `a`: A\n").unwrap();
+    let errors = validate_parameters(&sig("fn foo(a: u32, b: u32)"), &doc);
+    assert_eq!(errors, vec![
+        ValidationError::MisorderedParameter("a".into(), Position::default()),
+    ]);
+}
+
+#[test]
+fn source_items() {
+    let items = parse_rust_source("
+/// Lorem ipsum
+///
+/// # Parameters
+///
+/// - `x`: X
+/// - `y`: Y
+pub fn foo(x: u32) {}
+").unwrap();
+
+    let errors = items[0].validate(&local_enums(&items));
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].name(), "y");
+
+    let position = items[0].source_position(errors[0].position());
+    assert_eq!((position.line, position.column), (7, 8));
+}
+
+fn validate_generics(code: &str, md: &str) -> Vec<ValidationError> {
+    let item: syn::ItemFn = syn::parse_str(code).unwrap();
+    let doc = parse_md_docblock_spanned(md).unwrap();
+    validate_generics_spanned(&item.sig.generics, &doc)
+}
+
+#[test]
+fn generics_documented() {
+    assert_eq!(validate_generics(
+        "fn foo<'a, 'b, T, const N: usize>() {}",
+        "Lorem ipsum\n\n\
+         # Type Parameters\n\n- `T`: T\n- `N`: N\n\n\
+         # Lifetimes\n\n- `'a`: A\n- `b`: B\n",
+    ), vec![]);
+}
+
+#[test]
+fn generics_missing_and_extra() {
+    assert_eq!(validate_generics(
+        "fn foo<'a, T, const N: usize>() {}",
+        "Lorem ipsum\n\n\
+         # Type Parameters\n\n- `T`: T\n- `U`: U\n\n\
+         # Lifetimes\n\n- `'b`: B\n",
+    ), vec![
+        ValidationError::UnknownTypeParameter("U".into(), pos(44, 6, 4)),
+        ValidationError::UndocumentedTypeParameter("N".into(), pos(15, 3, 3)),
+        ValidationError::UnknownLifetime("'b".into(), pos(67, 10, 4)),
+        ValidationError::UndocumentedLifetime("'a".into(), pos(53, 8, 3)),
+    ]);
+}
+
+#[test]
+fn generics_without_sections() {
+    assert_eq!(validate_generics("fn foo<'a, T>() {}", "Lorem ipsum\n"), vec![]);
+
+    assert_eq!(validate_generics(
+        "fn foo<'a, T>() {}",
+        "Lorem ipsum\n\n# Type Parameters\n\n- `U`: U\n",
+    ), vec![
+        ValidationError::UnknownTypeParameter("U".into(), pos(35, 5, 4)),
+        ValidationError::UndocumentedTypeParameter("T".into(), pos(15, 3, 3)),
+    ]);
+}
+
+#[test]
+fn generics_of_source_items() {
+    let items = parse_rust_source("
+/// Lorem ipsum
+///
+/// # Type Parameters
+///
+/// - `T`: Tee
+pub struct Foo<T, U>(T, U);
+").unwrap();
+
+    let names: Vec<_> = items[0].validate(&local_enums(&items)).iter().map(|e| e.name().to_string()).collect();
+    assert_eq!(names, vec!["U"]);
+}
+
+fn validate_returns(code: &str, md: &str) -> Vec<ValidationError> {
+    let mut enums = std::collections::HashMap::new();
+    enums.insert("Answer".to_string(), vec!["Yes".to_string(), "No".to_string(), "Maybe".to_string()]);
+
+    let doc = parse_md_docblock_spanned(md).unwrap();
+    validate_returns_spanned(&sig(code), &doc, &enums)
+}
+
+#[test]
+fn returns_result() {
+    assert_eq!(validate_returns(
+        "fn foo() -> io::Result<u32>",
+        "Lorem ipsum\n\n# Returns\n\n- `Ok(n)`: N\n- `Err(e)`: E\n",
+    ), vec![]);
+
+    assert_eq!(validate_returns(
+        "fn foo() -> Result<u32, ()>",
+        "Lorem ipsum\n\n# Returns\n\n- `Ok(n)`: N\n- `Some(e)`: E\n",
+    ), vec![
+        ValidationError::UnknownReturnVariant("Some".into(), pos(40, 6, 4)),
+        ValidationError::UndocumentedReturnVariant("Err".into(), pos(15, 3, 3)),
+    ]);
+}
+
+#[test]
+fn returns_option() {
+    assert_eq!(validate_returns(
+        "fn foo() -> Option<u32>",
+        "Lorem ipsum\n\n# Returns\n\n- `Some(_) | None`: Anything\n",
+    ), vec![]);
+
+    assert_eq!(validate_returns(
+        "fn foo() -> Option<u32>",
+        "Lorem ipsum\n\n# Returns\n\n- `x @ Some(1..=5)`: Small\n",
+    ), vec![
+        ValidationError::UndocumentedReturnVariant("None".into(), pos(15, 3, 3)),
+    ]);
+}
+
+#[test]
+fn returns_local_enum() {
+    assert_eq!(validate_returns(
+        "fn foo() -> Answer",
+        "Lorem ipsum\n\n# Returns\n\n- `Answer::Yes`: Yes\n- `_`: Anything else\n",
+    ), vec![]);
+
+    let errors = validate_returns(
+        "fn foo() -> Answer",
+        "Lorem ipsum\n\n# Returns\n\n- `Answer::Yes`: Yes\n- `Answer::Perhaps { .. }`: Perhaps\n",
+    );
+    let names: Vec<_> = errors.iter().map(|e| e.name()).collect();
+    assert_eq!(names, vec!["Perhaps", "No", "Maybe"]);
+}
+
+#[test]
+fn returns_without_patterns() {
+    assert_eq!(validate_returns("fn foo() -> Result<u32, ()>", "Lorem ipsum\n\n# Returns\n\nA number\n"), vec![]);
+    assert_eq!(validate_returns("fn foo() -> Vec<u32>", "Lorem ipsum\n\n# Returns\n\n- `x`: X\n"), vec![]);
+}
+
+#[test]
+fn returns_of_source_items() {
+    let items = parse_rust_source("
+pub enum Answer { Yes, No }
+
+/// Lorem ipsum
+///
+/// # Returns
+///
+/// - `Answer::Yes`: Sure
+pub fn ask() -> Answer { Answer::Yes }
+").unwrap();
+
+    let errors = items[1].validate(&local_enums(&items));
+    let names: Vec<_> = errors.iter().map(|e| e.name().to_string()).collect();
+    assert_eq!(names, vec!["No"]);
+}
+
+#[test]
+fn returns_of_enums_in_modules() {
+    let items = parse_rust_source("
+pub mod a {
+    pub enum Answer { Yes, No }
+
+    /// Lorem ipsum
+    ///
+    /// # Returns
+    ///
+    /// - `Answer::Yes`: Sure
+    /// - `Answer::No`: Nope
+    pub fn ask() -> Answer { Answer::Yes }
+
+    pub mod c {
+        /// Lorem ipsum
+        ///
+        /// # Returns
+        ///
+        /// - `Answer::Yes`: Sure
+        pub fn ask() -> super::Answer { super::Answer::Yes }
+    }
+}
+
+pub mod b {
+    pub enum Answer { Yes, No, Maybe }
+
+    /// Lorem ipsum
+    ///
+    /// # Returns
+    ///
+    /// - `Answer::Yes`: Sure
+    /// - `Answer::No`: Nope
+    pub fn ask() -> Answer { Answer::Yes }
+}
+
+/// Lorem ipsum
+///
+/// # Returns
+///
+/// - `Answer::Yes`: Sure
+pub fn ask() -> a::Answer { a::Answer::Yes }
+").unwrap();
+
+    let enums = local_enums(&items);
+    assert_eq!(enums["a::Answer"], vec!["Yes", "No"]);
+    assert_eq!(enums["b::Answer"], vec!["Yes", "No", "Maybe"]);
+
+    let undocumented = |path: &str| -> Vec<String> {
+        let item = items.iter().find(|item| item.path == path).unwrap();
+        item.validate(&enums).iter().map(|e| e.name().to_string()).collect()
+    };
+    assert_eq!(undocumented("a::ask"), Vec::<String>::new());
+    assert_eq!(undocumented("a::c::ask"), vec!["No"]);
+    assert_eq!(undocumented("b::ask"), vec!["Maybe"]);
+    assert_eq!(undocumented("ask"), vec!["No"]);
+}
+
+#[test]
+fn parameters_in_subsections() {
+    assert_eq!(validate(
+        "fn foo(a: u32, b: u32, c: u32)",
+        "Lorem ipsum\n\n# Parameters\n\n- `a`: A\n\n## Options\n\n- `b`: B\n- `d`: D\n",
+    ), vec![
+        ValidationError::UnknownParameter("d".into(), pos(61, 10, 4)),
+        ValidationError::UndocumentedParameter("c".into(), pos(15, 3, 3)),
+    ]);
+}